@@ -0,0 +1,100 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use errors::*;
+use {parse_field, ParseOptions, ResponseParse};
+use framing::terminate;
+
+/// Response from the "Alarm,?" command to query the configuration of a gas
+/// sensor's alarm pin, like "?Alarm,1000,100,1".  `T` is the unit in which
+/// the sensor reports its readings, like `co2::Ppm` or `o2::Percent`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AlarmConfigResponse<T> {
+    /// Reading at which the alarm goes off
+    pub threshold: T,
+    /// How far the reading must move back past the threshold for the alarm
+    /// to reset
+    pub tolerance: T,
+    pub enabled: bool
+}
+
+impl<T> AlarmConfigResponse<T>
+    where T: FromStr, T::Err: Error + Send + 'static
+{
+    /// Parses the result of the "Alarm,?" command.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse (response: &[u8]) -> Result<AlarmConfigResponse<T>> {
+        AlarmConfigResponse::parse_with_options (response, &ParseOptions::default ())
+    }
+
+    /// Like `parse()`, with the leniencies in `options`.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<AlarmConfigResponse<T>> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?Alarm,") {
+            let mut split = rest.split (',');
+
+            let threshold = parse_field (split.next ())?;
+            let tolerance = parse_field (split.next ())?;
+
+            let enabled = match split.next () {
+                Some ("0") => false,
+                Some ("1") => true,
+                _          => return Err (ErrorKind::ResponseParse.into ())
+            };
+
+            if split.next ().is_some () {
+                return Err (ErrorKind::ResponseParse.into ());
+            }
+
+            Ok (AlarmConfigResponse {
+                threshold,
+                tolerance,
+                enabled
+            })
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for AlarmConfigResponse<T> {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "alarm is {} at {} with a tolerance of {}",
+                if self.enabled { "enabled" } else { "disabled" },
+                self.threshold,
+                self.tolerance)
+    }
+}
+
+impl<T> ResponseParse for AlarmConfigResponse<T>
+    where T: FromStr, T::Err: Error + Send + 'static
+{
+    fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<AlarmConfigResponse<T>> {
+        AlarmConfigResponse::parse_with_options (response, options)
+    }
+}
+
+impl<'a, T> TryFrom<&'a [u8]> for AlarmConfigResponse<T>
+    where T: FromStr, T::Err: Error + Send + 'static
+{
+    type Error = ::errors::Error;
+
+    fn try_from (response: &'a [u8]) -> Result<AlarmConfigResponse<T>> {
+        AlarmConfigResponse::parse (response)
+    }
+}
+
+impl<T> FromStr for AlarmConfigResponse<T>
+    where T: FromStr, T::Err: Error + Send + 'static
+{
+    type Err = ::errors::Error;
+
+    fn from_str (s: &str) -> Result<AlarmConfigResponse<T>> {
+        AlarmConfigResponse::parse (&terminate (s))
+    }
+}
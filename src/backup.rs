@@ -0,0 +1,210 @@
+use errors::*;
+use {CalibrationData, CalibrationExport, Command, ExportInfoResponse, Export, ExportInfoQuery,
+     Import, ImportResponse, TypedCommand};
+
+/// Sequences the commands to back up the device's calibration data.
+///
+/// This first sends "Export,?" to find out the size of the export, and
+/// then "Export" repeatedly until the device sends `*DONE`.  Call
+/// `next_command()` to get the command to send, then pass the device's
+/// response to `handle_response()`, until `next_command()` returns `None`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CalibrationBackup {
+    info: Option<ExportInfoResponse>,
+    export: CalibrationExport
+}
+
+impl CalibrationBackup {
+    pub fn new () -> CalibrationBackup {
+        CalibrationBackup::default ()
+    }
+
+    /// Returns the next command to send, or `None` if the export is complete.
+    pub fn next_command (&self) -> Option<Command> {
+        if self.info.is_none () {
+            Some (ExportInfoQuery.command ())
+        } else if !self.export.is_done () {
+            Some (Export.command ())
+        } else {
+            None
+        }
+    }
+
+    /// Parses the device's response to the command from `next_command()`.
+    ///
+    /// Once the device sends `*DONE`, this checks that the number of
+    /// strings and bytes received match what "Export,?" announced.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn handle_response (&mut self, response: &[u8]) -> Result<()> {
+        let info = match self.info {
+            None => {
+                self.info = Some (ExportInfoQuery.parse_response (response)?);
+                return Ok (());
+            },
+
+            Some (info) => info
+        };
+
+        if self.export.push (Export.parse_response (response)?)? {
+            let num_strings = self.export.strings ().len ();
+            let num_bytes: usize = self.export.strings ().iter ().map (|s| s.len ()).sum ();
+
+            if num_strings != info.num_strings as usize || num_bytes != info.num_bytes as usize {
+                return Err (ErrorKind::ExportSequence.into ());
+            }
+        }
+
+        Ok (())
+    }
+
+    /// Whether the device has sent all of its calibration data.
+    pub fn is_done (&self) -> bool {
+        self.export.is_done ()
+    }
+
+    /// Returns the calibration data; it is an error to call this before
+    /// the export is complete.
+    pub fn into_export (self) -> Result<CalibrationExport> {
+        if self.export.is_done () {
+            Ok (self.export)
+        } else {
+            Err (ErrorKind::ExportSequence.into ())
+        }
+    }
+}
+
+/// Sequences the commands to restore calibration data previously obtained
+/// with `CalibrationBackup`.
+///
+/// This sends one "Import,n" command for each string in the export.  Use
+/// `next_command()` and `handle_response()` in the same way as for
+/// `CalibrationBackup`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibrationRestore {
+    strings: Vec<String>,
+    next: usize
+}
+
+impl CalibrationRestore {
+    /// Creates a restore sequence for the given export, which must be complete.
+    pub fn new (export: &CalibrationExport) -> Result<CalibrationRestore> {
+        if !export.is_done () {
+            return Err (ErrorKind::ExportSequence.into ());
+        }
+
+        Ok (CalibrationRestore {
+            strings: export.strings ().to_vec (),
+            next: 0
+        })
+    }
+
+    fn current (&self) -> Option<Import> {
+        self.strings.get (self.next).map (|s| Import (CalibrationData (s.clone ())))
+    }
+
+    /// Returns the next command to send, or `None` if all the calibration
+    /// data has been imported.
+    pub fn next_command (&self) -> Option<Command> {
+        self.current ().map (|import| import.command ())
+    }
+
+    /// Parses the device's acknowledgment of the command from
+    /// `next_command()`.  Returns an `ImportRejected` error if the device
+    /// did not accept the calibration string; in that case the same
+    /// command can be retried.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn handle_response (&mut self, response: &[u8]) -> Result<()> {
+        let import = match self.current () {
+            Some (import) => import,
+            None          => return Err (ErrorKind::ExportSequence.into ())
+        };
+
+        match import.parse_response (response)? {
+            ImportResponse::Accepted => {
+                self.next += 1;
+                Ok (())
+            },
+
+            ImportResponse::Rejected => Err (ErrorKind::ImportRejected.into ())
+        }
+    }
+
+    /// Whether all the calibration data has been imported.
+    pub fn is_done (&self) -> bool {
+        self.next == self.strings.len ()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backup () -> CalibrationBackup {
+        let mut backup = CalibrationBackup::new ();
+
+        assert_eq! (backup.next_command (), Some (Command::ExportInfo));
+        backup.handle_response ("2,24\0".as_bytes ()).unwrap ();
+
+        assert_eq! (backup.next_command (), Some (Command::Export));
+        backup.handle_response ("596F75206172\0".as_bytes ()).unwrap ();
+        assert_eq! (backup.next_command (), Some (Command::Export));
+        backup.handle_response ("652061206B69\0".as_bytes ()).unwrap ();
+        assert! (!backup.is_done ());
+
+        assert_eq! (backup.next_command (), Some (Command::Export));
+        backup.handle_response ("*DONE\0".as_bytes ()).unwrap ();
+
+        assert! (backup.is_done ());
+        assert_eq! (backup.next_command (), None);
+
+        backup
+    }
+
+    #[test]
+    fn sequences_calibration_backup () {
+        let export = backup ().into_export ().unwrap ();
+        assert_eq! (export.strings (), &["596F75206172".to_string (), "652061206B69".to_string ()]);
+    }
+
+    #[test]
+    fn calibration_backup_with_wrong_size_yields_error () {
+        let mut backup = CalibrationBackup::new ();
+        backup.handle_response ("3,36\0".as_bytes ()).unwrap ();
+        backup.handle_response ("596F75206172\0".as_bytes ()).unwrap ();
+        assert! (backup.handle_response ("*DONE\0".as_bytes ()).is_err ());
+
+        let mut backup = CalibrationBackup::new ();
+        assert! (backup.handle_response ("*DONE\0".as_bytes ()).is_err ());
+
+        let mut backup = CalibrationBackup::new ();
+        backup.handle_response ("1,12\0".as_bytes ()).unwrap ();
+        assert! (backup.clone ().into_export ().is_err ());
+    }
+
+    #[test]
+    fn sequences_calibration_restore () {
+        let export = backup ().into_export ().unwrap ();
+        let mut restore = CalibrationRestore::new (&export).unwrap ();
+
+        assert_eq! (restore.next_command (), Some (Command::import ("596F75206172").unwrap ()));
+        restore.handle_response ("\0".as_bytes ()).unwrap ();
+
+        assert_eq! (restore.next_command (), Some (Command::import ("652061206B69").unwrap ()));
+        assert! (restore.handle_response ("*ER\r".as_bytes ()).is_err ());
+        assert_eq! (restore.next_command (), Some (Command::import ("652061206B69").unwrap ()));
+        restore.handle_response ("*OK\r".as_bytes ()).unwrap ();
+
+        assert! (restore.is_done ());
+        assert_eq! (restore.next_command (), None);
+        assert! (restore.handle_response ("*OK\r".as_bytes ()).is_err ());
+    }
+
+    #[test]
+    fn calibration_restore_from_incomplete_export_yields_error () {
+        assert! (CalibrationRestore::new (&CalibrationExport::new ()).is_err ());
+    }
+}
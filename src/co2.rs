@@ -0,0 +1,436 @@
+//! Responses and commands for the EZO CO2 (gaseous carbon dioxide) sensor
+
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+use std::time::Duration;
+
+use errors::*;
+use framing::{parse_flag, parse_number, write_output_params, write_reading, SplitCommand};
+use {parse_field, parse_output_params, CommonCommand, ParseOptions, Temperature};
+
+/// Concentration in parts per million
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Ppm (pub u32);
+
+impl FromStr for Ppm {
+    type Err = ParseIntError;
+
+    fn from_str (s: &str) -> ::std::result::Result<Ppm, ParseIntError> {
+        u32::from_str (s).map (Ppm)
+    }
+}
+
+impl fmt::Display for Ppm {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{} ppm", self.0)
+    }
+}
+
+/// Which optional parameters the CO2 sensor includes in its readings.  The
+/// concentration is always included.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct OutputParams {
+    /// "t", the sensor's internal temperature in Celsius
+    pub internal_temperature: bool
+}
+
+impl fmt::Display for OutputParams {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_output_params (f, &[("ppm", true),
+                                  ("t", self.internal_temperature)])
+    }
+}
+
+/// Response from the "R" command to take a reading
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Co2Response {
+    pub concentration: Ppm,
+    /// Only present if enabled in the sensor's output configuration
+    pub internal_temperature: Option<Temperature>
+}
+
+impl Co2Response {
+    /// Parses the result of the "R" command to take a reading, given the
+    /// parameters which are enabled in the sensor, as reported by "O,?".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse (response: &[u8], params: &OutputParams) -> Result<Co2Response> {
+        Co2Response::parse_with_options (response, params, &ParseOptions::default ())
+    }
+
+    /// Like `parse()`, with the leniencies in `options`.
+    pub fn parse_with_options (response: &[u8], params: &OutputParams, options: &ParseOptions)
+                               -> Result<Co2Response> {
+        let r = options.body (response)?;
+        let mut split = r.split (',');
+
+        let concentration = Ppm (parse_field (split.next ())?);
+
+        let internal_temperature = if params.internal_temperature {
+            Some (Temperature::Celsius (parse_field (split.next ())?))
+        } else {
+            None
+        };
+
+        if split.next ().is_some () {
+            return Err (ErrorKind::ResponseParse.into ());
+        }
+
+        Ok (Co2Response {
+            concentration,
+            internal_temperature
+        })
+    }
+}
+
+impl fmt::Display for Co2Response {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_reading (f, &[("concentration", Some (&self.concentration)),
+                            ("internal temperature", self.internal_temperature.as_ref ().map (|v| v as _))])
+    }
+}
+
+/// Response from the "O,?" command to query which optional parameters the
+/// CO2 sensor includes in its readings
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct OutputParamsResponse (pub OutputParams);
+
+impl OutputParamsResponse {
+    /// Parses the result of the "O,?" command, like "?O,t".  The
+    /// concentration may also be listed as "ppm"; it is always enabled.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<OutputParamsResponse> {
+        let enabled = parse_output_params (response, &["ppm", "t"], options)?;
+
+        Ok (OutputParamsResponse (OutputParams {
+            internal_temperature: enabled[1]
+        }))
+    }
+}
+
+impl fmt::Display for OutputParamsResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "output parameters are {}", self.0)
+    }
+}
+
+/// Response from the "Alarm,?" command to query the configuration of the
+/// sensor's alarm pin, like "?Alarm,1000,100,1"
+pub type AlarmConfigResponse = ::AlarmConfigResponse<Ppm>;
+
+/// Maximum concentration which the sensor can measure, and so the maximum
+/// alarm threshold
+pub const MAX_CONCENTRATION: Ppm = Ppm (10000);
+
+/// Maximum alarm tolerance
+pub const MAX_ALARM_TOLERANCE: Ppm = Ppm (500);
+
+/// Commands which can be sent to the EZO CO2 sensor
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Command {
+    /// "R", take a reading
+    Read,
+    /// "Alarm,en,n", enable or disable the alarm pin
+    SetAlarmEnabled (bool),
+    /// "Alarm,n", set the concentration at which the alarm goes off
+    SetAlarmThreshold (Ppm),
+    /// "Alarm,tol,n", set how far below the threshold the alarm resets
+    SetAlarmTolerance (Ppm),
+    /// "Alarm,?", query the alarm configuration
+    AlarmQuery,
+    /// "O,t,n", enable or disable the internal temperature in readings
+    SetInternalTemperatureOutput (bool),
+    /// "O,?", query which parameters are enabled in readings
+    OutputQuery,
+    /// A command which is common to all the EZO circuits
+    Common (CommonCommand)
+}
+
+impl Command {
+    /// Creates a command to set the alarm threshold, which must not be
+    /// above `MAX_CONCENTRATION`.
+    pub fn set_alarm_threshold (threshold: Ppm) -> Result<Command> {
+        if threshold <= MAX_CONCENTRATION {
+            Ok (Command::SetAlarmThreshold (threshold))
+        } else {
+            Err (ErrorKind::InvalidCommandParameter ("alarm threshold is above the sensor's range").into ())
+        }
+    }
+
+    /// Creates a command to set the alarm tolerance, which must not be
+    /// above `MAX_ALARM_TOLERANCE`.
+    pub fn set_alarm_tolerance (tolerance: Ppm) -> Result<Command> {
+        if tolerance <= MAX_ALARM_TOLERANCE {
+            Ok (Command::SetAlarmTolerance (tolerance))
+        } else {
+            Err (ErrorKind::InvalidCommandParameter ("alarm tolerance must be at most 500 ppm").into ())
+        }
+    }
+
+    /// Parses a command string as it would be sent to the device, like
+    /// "Alarm,tol,50" or "O,t,1", in the same way as `saiba_parser::Command::parse()`.
+    pub fn parse (command: &[u8]) -> Result<Command> {
+        let split = SplitCommand::new (command)?;
+
+        if let Some (common) = CommonCommand::from_split (&split)? {
+            return Ok (Command::Common (common));
+        }
+
+        let mut args = split.keyword.as_ref ().map (|k| k.splitn (2, ','));
+        let first = args.as_mut ().and_then (|a| a.next ());
+        let second = args.as_mut ().and_then (|a| a.next ());
+
+        let command = match (split.name.as_str (), first, second) {
+            ("r", None, None)                   => Command::Read,
+            ("alarm", Some ("en"), Some (n))    => Command::SetAlarmEnabled (parse_flag (n)?),
+            ("alarm", Some ("tol"), Some (n))   => Command::set_alarm_tolerance (Ppm (parse_number (n)?))?,
+            ("alarm", Some ("?"), None)         => Command::AlarmQuery,
+            ("alarm", Some (n), None)           => Command::set_alarm_threshold (Ppm (parse_number (n)?))?,
+            ("o", Some ("t"), Some (n))         => Command::SetInternalTemperatureOutput (parse_flag (n)?),
+            ("o", Some ("?"), None)             => Command::OutputQuery,
+            _                                   => return Err (ErrorKind::CommandParse.into ())
+        };
+
+        Ok (command)
+    }
+
+    /// Returns how long to wait after sending this command before reading
+    /// the device's response over I2C.
+    pub fn delay (&self) -> Duration {
+        match *self {
+            Command::Read                => Duration::from_millis (900),
+            Command::Common (ref common) => common.delay (),
+            _                            => Duration::from_millis (300)
+        }
+    }
+
+    /// Returns the bytes to send to the device for this command.  In I2C
+    /// mode these are sent as-is; in UART mode, the caller must append a
+    /// carriage return.
+    pub fn encode (&self) -> Vec<u8> {
+        let s = match *self {
+            Command::Read                               => "R".to_string (),
+            Command::SetAlarmEnabled (enabled)          => format! ("Alarm,en,{}", if enabled { 1 } else { 0 }),
+            Command::SetAlarmThreshold (ppm)            => format! ("Alarm,{}", ppm.0),
+            Command::SetAlarmTolerance (ppm)            => format! ("Alarm,tol,{}", ppm.0),
+            Command::AlarmQuery                         => "Alarm,?".to_string (),
+            Command::SetInternalTemperatureOutput (on)  => format! ("O,t,{}", if on { 1 } else { 0 }),
+            Command::OutputQuery                        => "O,?".to_string (),
+            Command::Common (ref common)                => return common.encode ()
+        };
+
+        s.into_bytes ()
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Command::Read                                 => write! (f, "take a reading"),
+            Command::SetAlarmEnabled (true)               => write! (f, "enable alarm"),
+            Command::SetAlarmEnabled (false)              => write! (f, "disable alarm"),
+            Command::SetAlarmThreshold (ppm)              => write! (f, "set alarm threshold to {}", ppm),
+            Command::SetAlarmTolerance (ppm)              => write! (f, "set alarm tolerance to {}", ppm),
+            Command::AlarmQuery                           => write! (f, "query alarm configuration"),
+            Command::SetInternalTemperatureOutput (true)  => write! (f, "enable internal temperature in readings"),
+            Command::SetInternalTemperatureOutput (false) => write! (f, "disable internal temperature in readings"),
+            Command::OutputQuery                          => write! (f, "query output parameters"),
+            Command::Common (ref common)                  => common.fmt (f)
+        }
+    }
+}
+
+impl From<CommonCommand> for Command {
+    fn from (c: CommonCommand) -> Command {
+        Command::Common (c)
+    }
+}
+
+response_parse! (OutputParamsResponse);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_co2_response () {
+        let response = "425\0".as_bytes ();
+        assert_eq! (Co2Response::parse (response, &OutputParams::default ()).unwrap (),
+                    Co2Response {
+                        concentration: Ppm (425),
+                        internal_temperature: None
+                    });
+
+        let params = OutputParams { internal_temperature: true };
+
+        let response = "425,25.71\r".as_bytes ();
+        assert_eq! (Co2Response::parse (response, &params).unwrap (),
+                    Co2Response {
+                        concentration: Ppm (425),
+                        internal_temperature: Some (Temperature::Celsius (25.71))
+                    });
+    }
+
+    #[test]
+    fn parsing_invalid_co2_response_yields_error () {
+        let response = "425,25.71\0".as_bytes ();
+        assert! (Co2Response::parse (response, &OutputParams::default ()).is_err ());
+
+        let response = "425\0".as_bytes ();
+        assert! (Co2Response::parse (response, &OutputParams { internal_temperature: true }).is_err ());
+
+        let response = "-5\0".as_bytes ();
+        assert! (Co2Response::parse (response, &OutputParams::default ()).is_err ());
+
+        let response = "425.5\0".as_bytes ();
+        assert! (Co2Response::parse (response, &OutputParams::default ()).is_err ());
+    }
+
+    #[test]
+    fn displays_ppm () {
+        assert_eq! (Ppm (425).to_string (), "425 ppm");
+    }
+
+    #[test]
+    fn parses_output_params_response () {
+        let response = "?O,t\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams { internal_temperature: true }));
+
+        let response = "?O,ppm,t\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams { internal_temperature: true }));
+
+        let response = "?O,ppm\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams { internal_temperature: false }));
+
+        let response = "?O,No output\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams::default ()));
+    }
+
+    #[test]
+    fn parsing_invalid_output_params_response_yields_error () {
+        let response = "?O,\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+
+        let response = "?O,t,t\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+
+        let response = "?O,%\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_alarm_config_response () {
+        let response = "?Alarm,1000,100,1\0".as_bytes ();
+        assert_eq! (AlarmConfigResponse::parse (response).unwrap (),
+                    AlarmConfigResponse {
+                        threshold: Ppm (1000),
+                        tolerance: Ppm (100),
+                        enabled: true
+                    });
+
+        let response = "?Alarm,5000,0,0\r".as_bytes ();
+        assert_eq! (AlarmConfigResponse::parse (response).unwrap (),
+                    AlarmConfigResponse {
+                        threshold: Ppm (5000),
+                        tolerance: Ppm (0),
+                        enabled: false
+                    });
+
+        let response = "?alarm,5000,0,0\r".as_bytes ();
+        assert_eq! (AlarmConfigResponse::parse_with_options (response, &ParseOptions::lenient ()).unwrap (),
+                    AlarmConfigResponse {
+                        threshold: Ppm (5000),
+                        tolerance: Ppm (0),
+                        enabled: false
+                    });
+        assert! (AlarmConfigResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parsing_invalid_alarm_config_response_yields_error () {
+        let response = "?Alarm,1000,100\0".as_bytes ();
+        assert! (AlarmConfigResponse::parse (response).is_err ());
+
+        let response = "?Alarm,1000,100,2\0".as_bytes ();
+        assert! (AlarmConfigResponse::parse (response).is_err ());
+
+        let response = "?Alarm,1000,100,1,1\0".as_bytes ();
+        assert! (AlarmConfigResponse::parse (response).is_err ());
+
+        let response = "?Alarm,high,100,1\0".as_bytes ();
+        assert! (AlarmConfigResponse::parse (response).is_err ());
+
+        let response = "1000,100,1\0".as_bytes ();
+        assert! (AlarmConfigResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn encodes_commands () {
+        let commands = [(Command::Read, "R"),
+                        (Command::SetAlarmEnabled (true), "Alarm,en,1"),
+                        (Command::SetAlarmEnabled (false), "Alarm,en,0"),
+                        (Command::set_alarm_threshold (Ppm (1000)).unwrap (), "Alarm,1000"),
+                        (Command::set_alarm_tolerance (Ppm (100)).unwrap (), "Alarm,tol,100"),
+                        (Command::AlarmQuery, "Alarm,?"),
+                        (Command::SetInternalTemperatureOutput (true), "O,t,1"),
+                        (Command::OutputQuery, "O,?"),
+                        (CommonCommand::Status.into (), "Status")];
+
+        for &(ref command, encoded) in commands.iter () {
+            assert_eq! (command.encode (), encoded.as_bytes ());
+            assert_eq! (&Command::parse (encoded.as_bytes ()).unwrap (), command);
+        }
+
+        assert_eq! (Command::parse ("ALARM,EN,0\r".as_bytes ()).unwrap (), Command::SetAlarmEnabled (false));
+    }
+
+    #[test]
+    fn invalid_commands_yield_error () {
+        assert! (Command::set_alarm_threshold (Ppm (10001)).is_err ());
+        assert! (Command::set_alarm_tolerance (Ppm (501)).is_err ());
+
+        assert! (Command::parse ("Alarm,en,2".as_bytes ()).is_err ());
+        assert! (Command::parse ("Alarm,-5".as_bytes ()).is_err ());
+        assert! (Command::parse ("Alarm,tol".as_bytes ()).is_err ());
+        assert! (Command::parse ("O,ppm,1".as_bytes ()).is_err ());
+    }
+
+    #[test]
+    fn commands_have_processing_delay () {
+        assert_eq! (Command::Read.delay (), Duration::from_millis (900));
+        assert_eq! (Command::AlarmQuery.delay (), Duration::from_millis (300));
+    }
+
+    #[test]
+    fn displays_responses () {
+        let params = OutputParams {
+            internal_temperature: true
+        };
+
+        let response = Co2Response::parse ("412,25.3\0".as_bytes (), &params).unwrap ();
+        assert_eq! (response.to_string (), "concentration 412 ppm, internal temperature 25.3 °C");
+
+        let response = Co2Response::parse ("412\0".as_bytes (), &OutputParams::default ()).unwrap ();
+        assert_eq! (response.to_string (), "concentration 412 ppm");
+
+        assert_eq! (OutputParamsResponse (params).to_string (), "output parameters are ppm, t");
+    }
+
+    #[test]
+    fn displays_commands () {
+        assert_eq! (Command::SetAlarmEnabled (true).to_string (), "enable alarm");
+        assert_eq! (Command::SetAlarmThreshold (Ppm (1000)).to_string (), "set alarm threshold to 1000 ppm");
+        assert_eq! (Command::SetInternalTemperatureOutput (false).to_string (),
+                    "disable internal temperature in readings");
+        assert_eq! (Command::Common (CommonCommand::Find).to_string (), "blink LED to find device");
+    }
+}
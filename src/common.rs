@@ -0,0 +1,1722 @@
+//! Responses and commands which all the EZO circuits understand in the same way
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use errors::*;
+use framing::{is_acknowledgment, parse_number, ParseOptions, SplitCommand, StatusToken};
+
+/// Temperature scales supported by the EZO RTD sensor
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum TemperatureScale {
+    Celsius,
+    Kelvin,
+    Fahrenheit
+}
+
+impl fmt::Display for TemperatureScale {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            TemperatureScale::Celsius    => "Celsius",
+            TemperatureScale::Kelvin     => "Kelvin",
+            TemperatureScale::Fahrenheit => "Fahrenheit"
+        };
+
+        write! (f, "{}", name)
+    }
+}
+
+/// A temperature value from a temperature reading
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Temperature {
+    Celsius    (f64),
+    Kelvin     (f64),
+    Fahrenheit (f64)
+}
+
+impl Temperature {
+    pub fn new (scale: TemperatureScale, value: f64) -> Temperature {
+        match scale {
+            TemperatureScale::Celsius    => Temperature::Celsius (value),
+            TemperatureScale::Kelvin     => Temperature::Kelvin (value),
+            TemperatureScale::Fahrenheit => Temperature::Fahrenheit (value)
+        }
+    }
+
+    /// Converts the temperature to degrees Celsius.
+    pub fn to_celsius (&self) -> f64 {
+        match *self {
+            Temperature::Celsius (value)    => value,
+            Temperature::Kelvin (value)     => value - 273.15,
+            Temperature::Fahrenheit (value) => (value - 32.0) * 5.0 / 9.0
+        }
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Temperature::Celsius (value)    => write! (f, "{} °C", value),
+            Temperature::Kelvin (value)     => write! (f, "{} K", value),
+            Temperature::Fahrenheit (value) => write! (f, "{} °F", value)
+        }
+    }
+}
+
+/// Type of EZO circuit, as reported by the "I" command
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum DeviceType {
+    Rtd,
+    Ph,
+    Ec,
+    Do,
+    Orp,
+    Co2,
+    O2,
+    Hum,
+    Prs,
+    Flo,
+    Pmp,
+    Rgb
+}
+
+impl DeviceType {
+    fn from_code (code: &str, options: &ParseOptions) -> Option<DeviceType> {
+        match code {
+            c if options.matches (c, "RTD") => Some (DeviceType::Rtd),
+            c if options.matches (c, "pH")  => Some (DeviceType::Ph),
+            c if options.matches (c, "EC")  => Some (DeviceType::Ec),
+            c if options.matches (c, "DO")  => Some (DeviceType::Do),
+            c if options.matches (c, "ORP") => Some (DeviceType::Orp),
+            c if options.matches (c, "CO2") => Some (DeviceType::Co2),
+            c if options.matches (c, "O2")  => Some (DeviceType::O2),
+            c if options.matches (c, "HUM") => Some (DeviceType::Hum),
+            c if options.matches (c, "PRS") => Some (DeviceType::Prs),
+            c if options.matches (c, "FLO") => Some (DeviceType::Flo),
+            c if options.matches (c, "PMP") => Some (DeviceType::Pmp),
+            c if options.matches (c, "RGB") => Some (DeviceType::Rgb),
+            _                               => None
+        }
+    }
+}
+
+impl fmt::Display for DeviceType {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let code = match *self {
+            DeviceType::Rtd => "RTD",
+            DeviceType::Ph  => "pH",
+            DeviceType::Ec  => "EC",
+            DeviceType::Do  => "DO",
+            DeviceType::Orp => "ORP",
+            DeviceType::Co2 => "CO2",
+            DeviceType::O2  => "O2",
+            DeviceType::Hum => "HUM",
+            DeviceType::Prs => "PRS",
+            DeviceType::Flo => "FLO",
+            DeviceType::Pmp => "PMP",
+            DeviceType::Rgb => "RGB"
+        };
+
+        write! (f, "{}", code)
+    }
+}
+
+/// Firmware version of the device, like "2.01".  The minor version keeps
+/// the number of digits with which the device sent it, so "1.5" and "1.05"
+/// are displayed as they came.
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct FirmwareVersion {
+    pub major: u32,
+    pub minor: u32,
+    /// Number of digits of the minor version, including leading zeros
+    pub minor_digits: usize
+}
+
+impl FirmwareVersion {
+    fn parse (version: &str) -> Result<FirmwareVersion> {
+        let mut split = version.split ('.');
+
+        let major = if let Some (major_str) = split.next () {
+            u32::from_str (major_str).chain_err (|| ErrorKind::ResponseParse)?
+        } else {
+            return Err (ErrorKind::ResponseParse.into ());
+        };
+
+        let minor_str = match split.next () {
+            Some (s) if !s.is_empty () && s.bytes ().all (|b| b.is_ascii_digit ()) => s,
+            _ => return Err (ErrorKind::ResponseParse.into ())
+        };
+
+        let minor = u32::from_str (minor_str).chain_err (|| ErrorKind::ResponseParse)?;
+
+        if split.next ().is_some () {
+            return Err (ErrorKind::ResponseParse.into ());
+        }
+
+        Ok (FirmwareVersion {
+            major,
+            minor,
+            minor_digits: minor_str.len ()
+        })
+    }
+}
+
+impl fmt::Display for FirmwareVersion {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{}.{:0width$}", self.major, self.minor, width = self.minor_digits)
+    }
+}
+
+/// Response from the "I" command to query the device information
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DeviceInfoResponse {
+    pub device_type: DeviceType,
+    pub firmware: FirmwareVersion
+}
+
+impl DeviceInfoResponse {
+    /// Parses the result of the "I" command to get the device type and
+    /// firmware version.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<DeviceInfoResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?I,") {
+            let mut split = rest.split (',');
+
+            let device_type = match split.next ().and_then (|code| DeviceType::from_code (code, options)) {
+                Some (device_type) => device_type,
+                None               => return Err (ErrorKind::ResponseParse.into ())
+            };
+
+            let firmware = if let Some (version_str) = split.next () {
+                FirmwareVersion::parse (version_str)?
+            } else {
+                return Err (ErrorKind::ResponseParse.into ());
+            };
+
+            if split.next ().is_some () {
+                return Err (ErrorKind::ResponseParse.into ());
+            }
+
+            Ok (DeviceInfoResponse {
+                device_type,
+                firmware
+            })
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for DeviceInfoResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "EZO {} with firmware {}", self.device_type, self.firmware)
+    }
+}
+
+/// Reason for which the device restarted, data sheet pp. 58
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum RestartReason {
+    PoweredOff,
+    SoftwareReset,
+    BrownOut,
+    Watchdog,
+    Unknown
+}
+
+impl fmt::Display for RestartReason {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match *self {
+            RestartReason::PoweredOff    => "powered off",
+            RestartReason::SoftwareReset => "software reset",
+            RestartReason::BrownOut      => "brown-out",
+            RestartReason::Watchdog      => "watchdog",
+            RestartReason::Unknown       => "unknown"
+        };
+
+        write! (f, "{}", reason)
+    }
+}
+
+/// Response from the "Status" command to get the device status
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DeviceStatusResponse {
+    pub restart_reason: RestartReason,
+    pub vcc_voltage: f64
+}
+
+impl DeviceStatusResponse {
+    /// Parses the result of the "Status" command to get the device's status.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<DeviceStatusResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?Status,") {
+            let mut split = rest.split (',');
+
+            let restart_reason = match split.next () {
+                Some (c) if options.matches (c, "P") => RestartReason::PoweredOff,
+                Some (c) if options.matches (c, "S") => RestartReason::SoftwareReset,
+                Some (c) if options.matches (c, "B") => RestartReason::BrownOut,
+                Some (c) if options.matches (c, "W") => RestartReason::Watchdog,
+                Some (c) if options.matches (c, "U") => RestartReason::Unknown,
+                _                                    => return Err (ErrorKind::ResponseParse.into ())
+            };
+
+            let voltage = if let Some (voltage_str) = split.next () {
+                f64::from_str (voltage_str).chain_err (|| ErrorKind::ResponseParse)?
+            } else {
+                return Err (ErrorKind::ResponseParse.into ());
+            };
+
+            if let Some (_) = split.next() {
+                return Err (ErrorKind::ResponseParse.into ());
+            }
+
+            Ok (DeviceStatusResponse {
+                restart_reason: restart_reason,
+                vcc_voltage: voltage
+            })
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for DeviceStatusResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "restarted due to {}, supply voltage is {} V", self.restart_reason, self.vcc_voltage)
+    }
+}
+
+/// Calibration state of the device
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum CalibrationState {
+    Uncalibrated,
+    Calibrated
+}
+
+impl fmt::Display for CalibrationState {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CalibrationState::Uncalibrated => write! (f, "uncalibrated"),
+            CalibrationState::Calibrated   => write! (f, "calibrated")
+        }
+    }
+}
+
+/// Response from the "Cal,?" command to query whether the device has been calibrated
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CalibrationStateResponse (pub CalibrationState);
+
+impl CalibrationStateResponse {
+    /// Parses the result of the "Cal,?" command to query the device's
+    /// calibration state.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<CalibrationStateResponse> {
+        let r = options.body (response)?;
+
+        match options.strip_keyword (r, "?Cal,") {
+            Some ("0") => Ok (CalibrationStateResponse (CalibrationState::Uncalibrated)),
+            Some ("1") => Ok (CalibrationStateResponse (CalibrationState::Calibrated)),
+            _ => Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for CalibrationStateResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "device is {}", self.0)
+    }
+}
+
+/// State of the device's LED
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum LedState {
+    Off,
+    On
+}
+
+impl fmt::Display for LedState {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LedState::Off => write! (f, "off"),
+            LedState::On  => write! (f, "on")
+        }
+    }
+}
+
+/// Response from the "L,?" command to query the state of the LED
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LedStateResponse (pub LedState);
+
+impl LedStateResponse {
+    /// Parses the result of the "L,?" command to query the LED state.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<LedStateResponse> {
+        let r = options.body (response)?;
+
+        match options.strip_keyword (r, "?L,") {
+            Some ("0") => Ok (LedStateResponse (LedState::Off)),
+            Some ("1") => Ok (LedStateResponse (LedState::On)),
+            _ => Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for LedStateResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "LED is {}", self.0)
+    }
+}
+
+/// Whether the device's communication protocol can be switched between UART and I2C
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ProtocolLockState {
+    Unlocked,
+    Locked
+}
+
+impl fmt::Display for ProtocolLockState {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProtocolLockState::Unlocked => write! (f, "unlocked"),
+            ProtocolLockState::Locked   => write! (f, "locked")
+        }
+    }
+}
+
+/// Response from the "Plock,?" command to query the protocol lock
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ProtocolLockResponse (pub ProtocolLockState);
+
+impl ProtocolLockResponse {
+    /// Parses the result of the "Plock,?" command to query whether the
+    /// device's protocol is locked.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<ProtocolLockResponse> {
+        let r = options.body (response)?;
+
+        match options.strip_keyword (r, "?Plock,") {
+            Some ("0") => Ok (ProtocolLockResponse (ProtocolLockState::Unlocked)),
+            Some ("1") => Ok (ProtocolLockResponse (ProtocolLockState::Locked)),
+            _ => Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for ProtocolLockResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "protocol is {}", self.0)
+    }
+}
+
+/// Response from the "Export,?" command to query the size of the
+/// calibration export
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ExportInfoResponse {
+    pub num_strings: u32,
+    pub num_bytes: u32
+}
+
+impl ExportInfoResponse {
+    /// Parses the result of the "Export,?" command, which tells how many
+    /// strings the "Export" command will produce, and how many bytes they
+    /// contain in total.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<ExportInfoResponse> {
+        let r = options.body (response)?;
+        let mut split = r.split (',');
+
+        let num_strings = if let Some (num_str) = split.next () {
+            u32::from_str (num_str).chain_err (|| ErrorKind::ResponseParse)?
+        } else {
+            return Err (ErrorKind::ResponseParse.into ());
+        };
+
+        let num_bytes = if let Some (num_str) = split.next () {
+            u32::from_str (num_str).chain_err (|| ErrorKind::ResponseParse)?
+        } else {
+            return Err (ErrorKind::ResponseParse.into ());
+        };
+
+        if split.next ().is_some () {
+            return Err (ErrorKind::ResponseParse.into ());
+        }
+
+        Ok (ExportInfoResponse {
+            num_strings,
+            num_bytes
+        })
+    }
+}
+
+impl fmt::Display for ExportInfoResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "calibration export has {} strings with {} bytes", self.num_strings, self.num_bytes)
+    }
+}
+
+/// Response from the "Export" command; each one is a piece of the
+/// device's calibration data, until the device says it is done.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ExportStringResponse {
+    Data (String),
+    Done
+}
+
+impl ExportStringResponse {
+    /// Parses the result of the "Export" command.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<ExportStringResponse> {
+        Ok (ExportStringResponse::from (ExportStringResponseRef::parse_with_options (response, options)?))
+    }
+}
+
+impl fmt::Display for ExportStringResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExportStringResponse::Data (ref s) => write! (f, "{}", ExportStringResponseRef::Data (s)),
+            ExportStringResponse::Done         => write! (f, "{}", ExportStringResponseRef::Done)
+        }
+    }
+}
+
+// The pieces of calibration data from "Export" are printable ASCII, and an
+// asterisk would make them look like "*DONE" or a status token.
+fn is_valid_calibration_data (data: &str) -> bool {
+    !data.is_empty () && data.bytes ().all (|b| b.is_ascii_graphic () && b != b'*')
+}
+
+/// Like `ExportStringResponse`, but borrowing the calibration data from
+/// the response instead of allocating a `String` for it
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ExportStringResponseRef<'a> {
+    Data (&'a str),
+    Done
+}
+
+impl<'a> ExportStringResponseRef<'a> {
+    /// Parses the result of the "Export" command.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse (response: &'a [u8]) -> Result<ExportStringResponseRef<'a>> {
+        ExportStringResponseRef::parse_with_options (response, &ParseOptions::default ())
+    }
+
+    /// Like `parse()`, with the leniencies in `options`.
+    pub fn parse_with_options (response: &'a [u8], options: &ParseOptions) -> Result<ExportStringResponseRef<'a>> {
+        let r = options.body (response)?;
+
+        match r {
+            r if options.matches (r, "*DONE") => Ok (ExportStringResponseRef::Done),
+
+            _ if is_valid_calibration_data (r) => Ok (ExportStringResponseRef::Data (r)),
+
+            _ => Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ExportStringResponseRef<'a> {
+    type Error = ::errors::Error;
+
+    fn try_from (response: &'a [u8]) -> Result<ExportStringResponseRef<'a>> {
+        ExportStringResponseRef::parse (response)
+    }
+}
+
+impl<'a> From<ExportStringResponseRef<'a>> for ExportStringResponse {
+    fn from (response: ExportStringResponseRef<'a>) -> ExportStringResponse {
+        match response {
+            ExportStringResponseRef::Data (s) => ExportStringResponse::Data (s.to_string ()),
+            ExportStringResponseRef::Done     => ExportStringResponse::Done
+        }
+    }
+}
+
+impl<'a> fmt::Display for ExportStringResponseRef<'a> {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExportStringResponseRef::Data (s) => write! (f, "calibration data {}", s),
+            ExportStringResponseRef::Done     => write! (f, "calibration export done")
+        }
+    }
+}
+
+/// Collects the successive responses from the "Export" command into
+/// the device's full calibration data.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CalibrationExport {
+    strings: Vec<String>,
+    done: bool
+}
+
+impl CalibrationExport {
+    pub fn new () -> CalibrationExport {
+        CalibrationExport::default ()
+    }
+
+    /// Adds the result of an "Export" command to the accumulated data.
+    /// Returns whether the export is complete, i.e. whether the device
+    /// has sent `*DONE`.
+    pub fn push (&mut self, response: ExportStringResponse) -> Result<bool> {
+        if self.done {
+            return Err (ErrorKind::ExportSequence.into ());
+        }
+
+        match response {
+            ExportStringResponse::Data (s) => self.strings.push (s),
+            ExportStringResponse::Done     => self.done = true
+        }
+
+        Ok (self.done)
+    }
+
+    /// Whether the device has sent `*DONE`.
+    pub fn is_done (&self) -> bool {
+        self.done
+    }
+
+    /// The strings received so far, in order.
+    pub fn strings (&self) -> &[String] {
+        &self.strings
+    }
+
+    /// Returns the full calibration data.  This is an error if the device
+    /// has not sent `*DONE` yet.
+    pub fn into_bytes (self) -> Result<Vec<u8>> {
+        if !self.done {
+            return Err (ErrorKind::ExportSequence.into ());
+        }
+
+        Ok (self.strings.concat ().into_bytes ())
+    }
+}
+
+/// Baud rates supported by the device's UART mode
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum BaudRate {
+    Bps300,
+    Bps1200,
+    Bps2400,
+    Bps9600,
+    Bps19200,
+    Bps38400,
+    Bps57600,
+    Bps115200
+}
+
+impl BaudRate {
+    fn from_bits_per_second (bps: u32) -> Option<BaudRate> {
+        match bps {
+            300    => Some (BaudRate::Bps300),
+            1200   => Some (BaudRate::Bps1200),
+            2400   => Some (BaudRate::Bps2400),
+            9600   => Some (BaudRate::Bps9600),
+            19200  => Some (BaudRate::Bps19200),
+            38400  => Some (BaudRate::Bps38400),
+            57600  => Some (BaudRate::Bps57600),
+            115200 => Some (BaudRate::Bps115200),
+            _      => None
+        }
+    }
+
+    /// Returns the baud rate as a number of bits per second
+    pub fn bits_per_second (&self) -> u32 {
+        match *self {
+            BaudRate::Bps300    => 300,
+            BaudRate::Bps1200   => 1200,
+            BaudRate::Bps2400   => 2400,
+            BaudRate::Bps9600   => 9600,
+            BaudRate::Bps19200  => 19200,
+            BaudRate::Bps38400  => 38400,
+            BaudRate::Bps57600  => 57600,
+            BaudRate::Bps115200 => 115200
+        }
+    }
+}
+
+impl fmt::Display for BaudRate {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{} bps", self.bits_per_second ())
+    }
+}
+
+/// Response from the "Baud,?" command to query the UART baud rate
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BaudRateResponse (pub BaudRate);
+
+impl BaudRateResponse {
+    /// Parses the result of the "Baud,?" command to query the baud rate.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<BaudRateResponse> {
+        let r = options.body (response)?;
+
+        if let Some (num_str) = options.strip_keyword (r, "?Baud,") {
+            let num = u32::from_str (num_str).chain_err (|| ErrorKind::ResponseParse)?;
+
+            match BaudRate::from_bits_per_second (num) {
+                Some (rate) => Ok (BaudRateResponse (rate)),
+                None        => Err (ErrorKind::ResponseParse.into ())
+            }
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for BaudRateResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "baud rate is {}", self.0)
+    }
+}
+
+/// Response from the "Sleep" command, which puts the device in low-power mode
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SleepResponse;
+
+impl SleepResponse {
+    /// Parses the `*SL` indication which the device emits when it goes
+    /// to sleep.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<SleepResponse> {
+        match StatusToken::parse_with_options (response, options)? {
+            StatusToken::Sleeping => Ok (SleepResponse),
+            _ => Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for SleepResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "device is sleeping")
+    }
+}
+
+/// Indication from the device that it has woken up from sleep
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WakeResponse;
+
+impl WakeResponse {
+    /// Parses the `*WA` indication which the device emits when it wakes
+    /// up.  The device may emit a spurious byte while waking up before the
+    /// actual indication; this is ignored.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<WakeResponse> {
+        let token = match response.split_first () {
+            Some ((&b'*', _)) => response,
+            Some ((_, rest))  => rest,
+            None              => return Err (ErrorKind::MalformedResponse.into ())
+        };
+
+        match StatusToken::parse_with_options (token, options)? {
+            StatusToken::Waking => Ok (WakeResponse),
+            _ => Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for WakeResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "device woke up")
+    }
+}
+
+/// Maximum length of the name which can be assigned to the device
+pub const MAX_DEVICE_NAME_LEN: usize = 16;
+
+// Device names are printable ASCII without spaces, and can't contain commas
+// since those separate the fields of responses.
+fn is_valid_device_name (name: &str) -> bool {
+    name.len () <= MAX_DEVICE_NAME_LEN
+        && name.bytes ().all (|b| b.is_ascii_graphic () && b != b',')
+}
+
+/// Response from the "Name,?" command to query the device's name
+#[derive(Debug, Clone, PartialEq)]
+pub struct NameResponse (pub String);
+
+impl NameResponse {
+    /// Parses the result of the "Name,?" command to query the name which
+    /// the user assigned to the device.  The name is empty if none has
+    /// been set.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<NameResponse> {
+        Ok (NameResponse::from (NameResponseRef::parse_with_options (response, options)?))
+    }
+}
+
+impl fmt::Display for NameResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{}", NameResponseRef (&self.0))
+    }
+}
+
+/// Like `NameResponse`, but borrowing the name from the response instead
+/// of allocating a `String` for it
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct NameResponseRef<'a> (pub &'a str);
+
+impl<'a> NameResponseRef<'a> {
+    /// Parses the result of the "Name,?" command to query the name which
+    /// the user assigned to the device.  The name is empty if none has
+    /// been set.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse (response: &'a [u8]) -> Result<NameResponseRef<'a>> {
+        NameResponseRef::parse_with_options (response, &ParseOptions::default ())
+    }
+
+    /// Like `parse()`, with the leniencies in `options`.
+    pub fn parse_with_options (response: &'a [u8], options: &ParseOptions) -> Result<NameResponseRef<'a>> {
+        let r = options.body (response)?;
+
+        if let Some (name) = options.strip_keyword (r, "?Name,") {
+            if is_valid_device_name (name) {
+                Ok (NameResponseRef (name))
+            } else {
+                Err (ErrorKind::ResponseParse.into ())
+            }
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for NameResponseRef<'a> {
+    type Error = ::errors::Error;
+
+    fn try_from (response: &'a [u8]) -> Result<NameResponseRef<'a>> {
+        NameResponseRef::parse (response)
+    }
+}
+
+impl<'a> From<NameResponseRef<'a>> for NameResponse {
+    fn from (response: NameResponseRef<'a>) -> NameResponse {
+        NameResponse (response.0.to_string ())
+    }
+}
+
+impl<'a> fmt::Display for NameResponseRef<'a> {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty () {
+            write! (f, "device has no name")
+        } else {
+            write! (f, "device name is \"{}\"", self.0)
+        }
+    }
+}
+
+/// Response from the "Import" command, which loads a piece of calibration
+/// data previously obtained with the "Export" command
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ImportResponse {
+    /// The device accepted the calibration string
+    Accepted,
+    /// The device rejected the calibration string with `*ER`
+    Rejected
+}
+
+impl ImportResponse {
+    /// Parses the result of the "Import" command.  Each string from
+    /// `CalibrationExport::strings()` is sent with a separate command,
+    /// and each one is acknowledged by the device.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<ImportResponse> {
+        let r = options.body (response)?;
+
+        match r {
+            r if options.matches (r, "*ER") => Ok (ImportResponse::Rejected),
+            _ if is_acknowledgment (r, options) => Ok (ImportResponse::Accepted),
+            _ => Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for ImportResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ImportResponse::Accepted => write! (f, "calibration data accepted"),
+            ImportResponse::Rejected => write! (f, "calibration data rejected")
+        }
+    }
+}
+
+/// Response from the "Find" command, which makes the device blink its LED
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FindResponse;
+
+impl FindResponse {
+    /// Parses the acknowledgment of the "Find" command.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<FindResponse> {
+        let r = options.body (response)?;
+
+        if is_acknowledgment (r, options) {
+            Ok (FindResponse)
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for FindResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "blinking LED to find device")
+    }
+}
+
+/// Lowest temperature in Celsius at which the probe can be calibrated
+pub const MIN_CALIBRATION_TEMPERATURE: f64 = -126.0;
+
+/// Highest temperature in Celsius at which the probe can be calibrated
+pub const MAX_CALIBRATION_TEMPERATURE: f64 = 1254.0;
+
+// The pH and EC circuits take the compensation temperature in Celsius; we
+// accept the range of the EZO RTD's probe, so that its readings can be
+// passed directly.
+#[cfg(any(feature = "ph", feature = "ec", feature = "do_"))]
+pub(crate) fn validate_compensation_temperature (temperature: f64) -> Result<f64> {
+    if (MIN_CALIBRATION_TEMPERATURE ..= MAX_CALIBRATION_TEMPERATURE).contains (&temperature) {
+        Ok (temperature)
+    } else {
+        Err (ErrorKind::InvalidCommandParameter ("compensation temperature is out of range").into ())
+    }
+}
+
+/// A 7-bit I2C address which can be assigned to the device.
+///
+/// Addresses 0x00-0x07 and 0x78-0x7F are reserved by the I2C specification,
+/// so only 0x08-0x77 are valid.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct I2cAddress (u8);
+
+impl I2cAddress {
+    /// Validates an I2C address.
+    pub fn new (address: u8) -> Result<I2cAddress> {
+        if (0x08 ..= 0x77).contains (&address) {
+            Ok (I2cAddress (address))
+        } else {
+            Err (ErrorKind::InvalidCommandParameter ("I2C address must be between 0x08 and 0x77").into ())
+        }
+    }
+
+    pub fn value (&self) -> u8 {
+        self.0
+    }
+}
+
+impl fmt::Display for I2cAddress {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "0x{:02x}", self.0)
+    }
+}
+
+/// A name which can be assigned to the device with "Name,n"; an empty name
+/// clears it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceName (String);
+
+impl DeviceName {
+    /// Validates a device name; it can have up to 16 printable ASCII
+    /// characters, without spaces or commas.
+    pub fn new (name: &str) -> Result<DeviceName> {
+        if is_valid_device_name (name) {
+            Ok (DeviceName (name.to_string ()))
+        } else {
+            Err (ErrorKind::InvalidCommandParameter ("name must be up to 16 printable ASCII characters without spaces or commas").into ())
+        }
+    }
+
+    pub fn as_str (&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for DeviceName {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{}", self.0)
+    }
+}
+
+/// A piece of calibration data which can be loaded with "Import,n", as
+/// obtained from the "Export" command
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalibrationData (pub(crate) String);
+
+impl CalibrationData {
+    /// Validates a piece of calibration data; it must be non-empty
+    /// printable ASCII without spaces or asterisks, like the strings
+    /// from "Export".
+    pub fn new (data: &str) -> Result<CalibrationData> {
+        if is_valid_calibration_data (data) {
+            Ok (CalibrationData (data.to_string ()))
+        } else {
+            Err (ErrorKind::InvalidCommandParameter ("calibration data must be printable ASCII without spaces or asterisks").into ())
+        }
+    }
+
+    pub fn as_str (&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for CalibrationData {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{}", self.0)
+    }
+}
+
+/// Commands which all the EZO circuits understand in the same way
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum CommonCommand {
+    /// "Cal,clear", delete the calibration data
+    CalibrationClear,
+    /// "Cal,?", query the calibration state
+    CalibrationQuery,
+    /// "Export,?", query the size of the calibration export
+    ExportInfo,
+    /// "Export", get the next piece of calibration data
+    Export,
+    /// "Import,n", load a piece of calibration data
+    Import (CalibrationData),
+    /// "I", query the device information
+    DeviceInfo,
+    /// "Status", query the restart reason and supply voltage
+    Status,
+    /// "L,1" or "L,0", turn the LED on or off
+    SetLed (LedState),
+    /// "L,?", query the LED state
+    LedQuery,
+    /// "Plock,1" or "Plock,0", lock or unlock the protocol
+    SetProtocolLock (ProtocolLockState),
+    /// "Plock,?", query the protocol lock
+    ProtocolLockQuery,
+    /// "Sleep", enter low-power mode
+    Sleep,
+    /// "Find", blink the LED to locate the device
+    Find,
+    /// "Factory", reset the device to its factory settings
+    Factory,
+    /// "Baud,n", switch to UART mode at the given baud rate
+    Baud (BaudRate),
+    /// "Baud,?", query the UART baud rate
+    BaudQuery,
+    /// "I2C,n", change the device's I2C address; the device reboots afterwards
+    I2cAddress (I2cAddress),
+    /// "Name,n", set the device's name; an empty name clears it
+    SetName (DeviceName),
+    /// "Name,?", query the device's name
+    NameQuery
+}
+
+impl CommonCommand {
+    /// Creates a command to set the device's name.  The name can have up
+    /// to 16 printable ASCII characters, without spaces or commas.
+    pub fn set_name (name: &str) -> Result<CommonCommand> {
+        Ok (CommonCommand::SetName (DeviceName::new (name)?))
+    }
+
+    /// Creates a command to load a piece of calibration data, as obtained
+    /// from the "Export" command.
+    pub fn import (data: &str) -> Result<CommonCommand> {
+        Ok (CommonCommand::Import (CalibrationData::new (data)?))
+    }
+
+    /// Parses a command string like "Find" or "L,?", in the same way as
+    /// `Command::parse()`.
+    pub fn parse (command: &[u8]) -> Result<CommonCommand> {
+        match CommonCommand::from_split (&SplitCommand::new (command)?)? {
+            Some (command) => Ok (command),
+            None           => Err (ErrorKind::CommandParse.into ())
+        }
+    }
+
+    // Returns None if the command is not one of the common ones, so that
+    // the device-specific parsers can try their own commands.
+    pub(crate) fn from_split (split: &SplitCommand) -> Result<Option<CommonCommand>> {
+        let command = match (split.name.as_str (), split.keyword.as_deref ()) {
+            ("cal", Some ("clear"))  => CommonCommand::CalibrationClear,
+            ("cal", Some ("?"))      => CommonCommand::CalibrationQuery,
+            ("export", Some ("?"))   => CommonCommand::ExportInfo,
+            ("export", None)         => CommonCommand::Export,
+            ("import", Some (_))     => CommonCommand::import (split.arg.unwrap ())?,
+            ("i", None)              => CommonCommand::DeviceInfo,
+            ("status", None)         => CommonCommand::Status,
+            ("l", Some ("0"))        => CommonCommand::SetLed (LedState::Off),
+            ("l", Some ("1"))        => CommonCommand::SetLed (LedState::On),
+            ("l", Some ("?"))        => CommonCommand::LedQuery,
+            ("plock", Some ("0"))    => CommonCommand::SetProtocolLock (ProtocolLockState::Unlocked),
+            ("plock", Some ("1"))    => CommonCommand::SetProtocolLock (ProtocolLockState::Locked),
+            ("plock", Some ("?"))    => CommonCommand::ProtocolLockQuery,
+            ("sleep", None)          => CommonCommand::Sleep,
+            ("find", None)           => CommonCommand::Find,
+            ("factory", None)        => CommonCommand::Factory,
+            ("baud", Some ("?"))     => CommonCommand::BaudQuery,
+
+            ("baud", Some (n)) => match BaudRate::from_bits_per_second (parse_number (n)?) {
+                Some (rate) => CommonCommand::Baud (rate),
+                None        => return Err (ErrorKind::CommandParse.into ())
+            },
+
+            ("i2c", Some (n))        => CommonCommand::I2cAddress (I2cAddress::new (parse_number (n)?)?),
+            ("name", Some ("?"))     => CommonCommand::NameQuery,
+            ("name", Some (_))       => CommonCommand::set_name (split.arg.unwrap ())?,
+            _                        => return Ok (None)
+        };
+
+        Ok (Some (command))
+    }
+
+    /// Returns how long to wait after sending this command before reading
+    /// the device's response over I2C.
+    pub fn delay (&self) -> Duration {
+        Duration::from_millis (300)
+    }
+
+    // Whether the device responds to this command with data, rather than
+    // just acknowledging it
+    #[cfg(feature = "flo")]
+    pub(crate) fn responds_with_data (&self) -> bool {
+        match *self {
+            CommonCommand::CalibrationQuery
+                | CommonCommand::ExportInfo
+                | CommonCommand::Export
+                | CommonCommand::DeviceInfo
+                | CommonCommand::Status
+                | CommonCommand::LedQuery
+                | CommonCommand::ProtocolLockQuery
+                | CommonCommand::BaudQuery
+                | CommonCommand::NameQuery => true,
+
+            _ => false
+        }
+    }
+
+    /// Returns the bytes to send to the device for this command.  In I2C
+    /// mode these are sent as-is; in UART mode, the caller must append a
+    /// carriage return.
+    pub fn encode (&self) -> Vec<u8> {
+        let s = match *self {
+            CommonCommand::CalibrationClear        => "Cal,clear".to_string (),
+            CommonCommand::CalibrationQuery        => "Cal,?".to_string (),
+            CommonCommand::ExportInfo              => "Export,?".to_string (),
+            CommonCommand::Export                  => "Export".to_string (),
+            CommonCommand::Import (ref data)       => format! ("Import,{}", data),
+            CommonCommand::DeviceInfo              => "I".to_string (),
+            CommonCommand::Status                  => "Status".to_string (),
+            CommonCommand::SetLed (LedState::Off)  => "L,0".to_string (),
+            CommonCommand::SetLed (LedState::On)   => "L,1".to_string (),
+            CommonCommand::LedQuery                => "L,?".to_string (),
+
+            CommonCommand::SetProtocolLock (ProtocolLockState::Unlocked) => "Plock,0".to_string (),
+            CommonCommand::SetProtocolLock (ProtocolLockState::Locked)   => "Plock,1".to_string (),
+            CommonCommand::ProtocolLockQuery       => "Plock,?".to_string (),
+
+            CommonCommand::Sleep                   => "Sleep".to_string (),
+            CommonCommand::Find                    => "Find".to_string (),
+            CommonCommand::Factory                 => "Factory".to_string (),
+            CommonCommand::Baud (rate)             => format! ("Baud,{}", rate.bits_per_second ()),
+            CommonCommand::BaudQuery               => "Baud,?".to_string (),
+            CommonCommand::I2cAddress (address)    => format! ("I2C,{}", address.value ()),
+            CommonCommand::SetName (ref name)      => format! ("Name,{}", name),
+            CommonCommand::NameQuery               => "Name,?".to_string ()
+        };
+
+        s.into_bytes ()
+    }
+}
+
+impl fmt::Display for CommonCommand {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CommonCommand::CalibrationClear        => write! (f, "clear calibration"),
+            CommonCommand::CalibrationQuery        => write! (f, "query calibration state"),
+            CommonCommand::ExportInfo              => write! (f, "query calibration export size"),
+            CommonCommand::Export                  => write! (f, "export calibration data"),
+            CommonCommand::Import (ref data)       => write! (f, "import calibration data {}", data),
+            CommonCommand::DeviceInfo              => write! (f, "query device information"),
+            CommonCommand::Status                  => write! (f, "query device status"),
+            CommonCommand::SetLed (state)          => write! (f, "turn LED {}", state),
+            CommonCommand::LedQuery                => write! (f, "query LED state"),
+
+            CommonCommand::SetProtocolLock (ProtocolLockState::Unlocked) => write! (f, "unlock protocol"),
+            CommonCommand::SetProtocolLock (ProtocolLockState::Locked)   => write! (f, "lock protocol"),
+            CommonCommand::ProtocolLockQuery       => write! (f, "query protocol lock"),
+
+            CommonCommand::Sleep                   => write! (f, "go to sleep"),
+            CommonCommand::Find                    => write! (f, "blink LED to find device"),
+            CommonCommand::Factory                 => write! (f, "reset to factory settings"),
+            CommonCommand::Baud (rate)             => write! (f, "switch to UART mode at {}", rate),
+            CommonCommand::BaudQuery               => write! (f, "query baud rate"),
+            CommonCommand::I2cAddress (address)    => write! (f, "change I2C address to {}", address),
+
+            CommonCommand::SetName (ref name) if name.as_str ().is_empty () => write! (f, "clear device name"),
+            CommonCommand::SetName (ref name)      => write! (f, "set device name to \"{}\"", name),
+            CommonCommand::NameQuery               => write! (f, "query device name")
+        }
+    }
+}
+
+response_parse! (DeviceInfoResponse, DeviceStatusResponse, CalibrationStateResponse,
+                 LedStateResponse, ProtocolLockResponse, ExportInfoResponse,
+                 ExportStringResponse, BaudRateResponse, SleepResponse, WakeResponse,
+                 NameResponse, ImportResponse, FindResponse);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {AlarmConfigResponse, ParseOptions, ResponseParse};
+
+    #[test]
+    fn parses_device_info_response () {
+        let response = "?I,RTD,2.01\0".as_bytes ();
+        assert_eq! (DeviceInfoResponse::parse (response).unwrap (),
+                    DeviceInfoResponse {
+                        device_type: DeviceType::Rtd,
+                        firmware: FirmwareVersion { major: 2, minor: 1, minor_digits: 2 }
+                    });
+
+        let response = "?I,pH,1.10\0".as_bytes ();
+        assert_eq! (DeviceInfoResponse::parse (response).unwrap (),
+                    DeviceInfoResponse {
+                        device_type: DeviceType::Ph,
+                        firmware: FirmwareVersion { major: 1, minor: 10, minor_digits: 2 }
+                    });
+    }
+
+    #[test]
+    fn firmware_version_keeps_its_digits () {
+        assert_eq! (FirmwareVersion::parse ("1.98").unwrap ().to_string (), "1.98");
+        assert_eq! (FirmwareVersion::parse ("2.01").unwrap ().to_string (), "2.01");
+        assert_eq! (FirmwareVersion::parse ("1.5").unwrap (),
+                    FirmwareVersion { major: 1, minor: 5, minor_digits: 1 });
+        assert_eq! (FirmwareVersion::parse ("1.5").unwrap ().to_string (), "1.5");
+
+        assert! (FirmwareVersion::parse ("1.").is_err ());
+        assert! (FirmwareVersion::parse ("1.+5").is_err ());
+    }
+
+    #[test]
+    fn parsing_invalid_device_info_response_yields_error () {
+        let response = "\0".as_bytes ();
+        assert! (DeviceInfoResponse::parse (response).is_err ());
+
+        let response = "?I,XYZ,2.01\0".as_bytes ();
+        assert! (DeviceInfoResponse::parse (response).is_err ());
+
+        let response = "?I,RTD\0".as_bytes ();
+        assert! (DeviceInfoResponse::parse (response).is_err ());
+
+        let response = "?I,RTD,2\0".as_bytes ();
+        assert! (DeviceInfoResponse::parse (response).is_err ());
+
+        let response = "?I,RTD,2.01,\0".as_bytes ();
+        assert! (DeviceInfoResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_device_status_response () {
+        let response = "?Status,P,1.5\0".as_bytes ();
+        assert_eq! (DeviceStatusResponse::parse (response).unwrap (),
+                    DeviceStatusResponse {
+                        restart_reason: RestartReason::PoweredOff,
+                        vcc_voltage: 1.5
+                    });
+
+        let response = "?Status,S,1.5\0".as_bytes ();
+        assert_eq! (DeviceStatusResponse::parse (response).unwrap (),
+                    DeviceStatusResponse {
+                        restart_reason: RestartReason::SoftwareReset,
+                        vcc_voltage: 1.5
+                    });
+
+        let response = "?Status,B,1.5\0".as_bytes ();
+        assert_eq! (DeviceStatusResponse::parse (response).unwrap (),
+                    DeviceStatusResponse {
+                        restart_reason: RestartReason::BrownOut,
+                        vcc_voltage: 1.5
+                    });
+
+        let response = "?Status,W,1.5\0".as_bytes ();
+        assert_eq! (DeviceStatusResponse::parse (response).unwrap (),
+                    DeviceStatusResponse {
+                        restart_reason: RestartReason::Watchdog,
+                        vcc_voltage: 1.5
+                    });
+
+        let response = "?Status,U,1.5\0".as_bytes ();
+        assert_eq! (DeviceStatusResponse::parse (response).unwrap (),
+                    DeviceStatusResponse {
+                        restart_reason: RestartReason::Unknown,
+                        vcc_voltage: 1.5
+                    });
+
+        let response = "?Status,P,5.038\0".as_bytes ();
+        assert_eq! (DeviceStatusResponse::parse (response).unwrap (),
+                    DeviceStatusResponse {
+                        restart_reason: RestartReason::PoweredOff,
+                        vcc_voltage: 5.038
+                    });
+    }
+
+    #[test]
+    fn parsing_invalid_device_status_response_yields_error () {
+        let response = "\0".as_bytes ();
+        assert! (DeviceStatusResponse::parse (response).is_err ());
+
+        let response = "?Status,X,\0".as_bytes ();
+        assert! (DeviceStatusResponse::parse (response).is_err ());
+
+        let response = "?Status,P\0".as_bytes ();
+        assert! (DeviceStatusResponse::parse (response).is_err ());
+
+        let response = "?Status,P,\0".as_bytes ();
+        assert! (DeviceStatusResponse::parse (response).is_err ());
+
+        let response = "?Status,P,1.5,\0".as_bytes ();
+        assert! (DeviceStatusResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_calibration_state_response () {
+        let response = "?Cal,0\0".as_bytes ();
+        assert_eq! (CalibrationStateResponse::parse (response).unwrap (),
+                    CalibrationStateResponse (CalibrationState::Uncalibrated));
+
+        let response = "?Cal,1\0".as_bytes ();
+        assert_eq! (CalibrationStateResponse::parse (response).unwrap (),
+                    CalibrationStateResponse (CalibrationState::Calibrated));
+    }
+
+    #[test]
+    fn parsing_invalid_calibration_state_response_yields_error () {
+        let response = "\0".as_bytes ();
+        assert! (CalibrationStateResponse::parse (response).is_err ());
+
+        let response = "?Cal,\0".as_bytes ();
+        assert! (CalibrationStateResponse::parse (response).is_err ());
+
+        let response = "?Cal,2\0".as_bytes ();
+        assert! (CalibrationStateResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_led_state_response () {
+        let response = "?L,0\0".as_bytes ();
+        assert_eq! (LedStateResponse::parse (response).unwrap (),
+                    LedStateResponse (LedState::Off));
+
+        let response = "?L,1\0".as_bytes ();
+        assert_eq! (LedStateResponse::parse (response).unwrap (),
+                    LedStateResponse (LedState::On));
+    }
+
+    #[test]
+    fn parsing_invalid_led_state_response_yields_error () {
+        let response = "\0".as_bytes ();
+        assert! (LedStateResponse::parse (response).is_err ());
+
+        let response = "?L,\0".as_bytes ();
+        assert! (LedStateResponse::parse (response).is_err ());
+
+        let response = "?L,2\0".as_bytes ();
+        assert! (LedStateResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_protocol_lock_response () {
+        let response = "?Plock,0\0".as_bytes ();
+        assert_eq! (ProtocolLockResponse::parse (response).unwrap (),
+                    ProtocolLockResponse (ProtocolLockState::Unlocked));
+
+        let response = "?Plock,1\0".as_bytes ();
+        assert_eq! (ProtocolLockResponse::parse (response).unwrap (),
+                    ProtocolLockResponse (ProtocolLockState::Locked));
+    }
+
+    #[test]
+    fn parsing_invalid_protocol_lock_response_yields_error () {
+        let response = "\0".as_bytes ();
+        assert! (ProtocolLockResponse::parse (response).is_err ());
+
+        let response = "?Plock,\0".as_bytes ();
+        assert! (ProtocolLockResponse::parse (response).is_err ());
+
+        let response = "?Plock,2\0".as_bytes ();
+        assert! (ProtocolLockResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_export_info_response () {
+        let response = "10,120\0".as_bytes ();
+        assert_eq! (ExportInfoResponse::parse (response).unwrap (),
+                    ExportInfoResponse {
+                        num_strings: 10,
+                        num_bytes: 120
+                    });
+    }
+
+    #[test]
+    fn parsing_invalid_export_info_response_yields_error () {
+        let response = "\0".as_bytes ();
+        assert! (ExportInfoResponse::parse (response).is_err ());
+
+        let response = "10\0".as_bytes ();
+        assert! (ExportInfoResponse::parse (response).is_err ());
+
+        let response = "10,x\0".as_bytes ();
+        assert! (ExportInfoResponse::parse (response).is_err ());
+
+        let response = "10,120,\0".as_bytes ();
+        assert! (ExportInfoResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_export_string_response () {
+        let response = "596F75206172\0".as_bytes ();
+        assert_eq! (ExportStringResponse::parse (response).unwrap (),
+                    ExportStringResponse::Data ("596F75206172".to_string ()));
+
+        let response = "*DONE\0".as_bytes ();
+        assert_eq! (ExportStringResponse::parse (response).unwrap (),
+                    ExportStringResponse::Done);
+    }
+
+    #[test]
+    fn parsing_invalid_export_string_response_yields_error () {
+        let response = "\0".as_bytes ();
+        assert! (ExportStringResponse::parse (response).is_err ());
+
+        let response = "*OK\0".as_bytes ();
+        assert! (ExportStringResponse::parse (response).is_err ());
+
+        let response = "59 6F\0".as_bytes ();
+        assert! (ExportStringResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_borrowed_export_string_response () {
+        let response = "596F75206172\0".as_bytes ();
+        let parsed = ExportStringResponseRef::parse (response).unwrap ();
+        assert_eq! (parsed, ExportStringResponseRef::Data ("596F75206172"));
+        assert_eq! (ExportStringResponse::from (parsed),
+                    ExportStringResponse::Data ("596F75206172".to_string ()));
+
+        if let ExportStringResponseRef::Data (s) = parsed {
+            assert_eq! (s.as_ptr (), response.as_ptr ());
+        }
+
+        let response = "*DONE\0".as_bytes ();
+        assert_eq! (ExportStringResponseRef::try_from (response).unwrap (),
+                    ExportStringResponseRef::Done);
+
+        let response = "59 6F\0".as_bytes ();
+        assert! (ExportStringResponseRef::parse (response).is_err ());
+    }
+
+    #[test]
+    fn accumulates_calibration_export () {
+        let mut export = CalibrationExport::new ();
+
+        assert! (!export.push (ExportStringResponse::parse ("596F75206172\0".as_bytes ()).unwrap ()).unwrap ());
+        assert! (!export.push (ExportStringResponse::parse ("652061206B69\0".as_bytes ()).unwrap ()).unwrap ());
+        assert! (export.push (ExportStringResponse::parse ("*DONE\0".as_bytes ()).unwrap ()).unwrap ());
+
+        assert! (export.is_done ());
+        assert_eq! (export.into_bytes ().unwrap (), b"596F75206172652061206B69".to_vec ());
+    }
+
+    #[test]
+    fn calibration_export_out_of_sequence_yields_error () {
+        let mut export = CalibrationExport::new ();
+        export.push (ExportStringResponse::Data ("596F75206172".to_string ())).unwrap ();
+        assert! (export.clone ().into_bytes ().is_err ());
+
+        export.push (ExportStringResponse::Done).unwrap ();
+        assert! (export.push (ExportStringResponse::Data ("652061206B69".to_string ())).is_err ());
+        assert! (export.push (ExportStringResponse::Done).is_err ());
+    }
+
+    #[test]
+    fn parses_baud_rate_response () {
+        let response = "?Baud,300\0".as_bytes ();
+        assert_eq! (BaudRateResponse::parse (response).unwrap (),
+                    BaudRateResponse (BaudRate::Bps300));
+
+        let response = "?Baud,9600\0".as_bytes ();
+        assert_eq! (BaudRateResponse::parse (response).unwrap (),
+                    BaudRateResponse (BaudRate::Bps9600));
+
+        let response = "?Baud,115200\0".as_bytes ();
+        assert_eq! (BaudRateResponse::parse (response).unwrap (),
+                    BaudRateResponse (BaudRate::Bps115200));
+    }
+
+    #[test]
+    fn parsing_invalid_baud_rate_response_yields_error () {
+        let response = "?Baud,\0".as_bytes ();
+        assert! (BaudRateResponse::parse (response).is_err ());
+
+        let response = "?Baud,4800\0".as_bytes ();
+        assert! (BaudRateResponse::parse (response).is_err ());
+
+        let response = "?Baud,230400\0".as_bytes ();
+        assert! (BaudRateResponse::parse (response).is_err ());
+
+        let response = "?Baud,fast\0".as_bytes ();
+        assert! (BaudRateResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_sleep_response () {
+        let response = "*SL\0".as_bytes ();
+        assert_eq! (SleepResponse::parse (response).unwrap (), SleepResponse);
+    }
+
+    #[test]
+    fn parsing_invalid_sleep_response_yields_error () {
+        let response = "\0".as_bytes ();
+        assert! (SleepResponse::parse (response).is_err ());
+
+        let response = "*OK\0".as_bytes ();
+        assert! (SleepResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_wake_response () {
+        let response = "*WA\0".as_bytes ();
+        assert_eq! (WakeResponse::parse (response).unwrap (), WakeResponse);
+
+        let response = b"\xff*WA\0";
+        assert_eq! (WakeResponse::parse (response).unwrap (), WakeResponse);
+
+        let response = "\0*WA\0".as_bytes ();
+        assert_eq! (WakeResponse::parse (response).unwrap (), WakeResponse);
+    }
+
+    #[test]
+    fn parsing_invalid_wake_response_yields_error () {
+        let response = "".as_bytes ();
+        assert! (WakeResponse::parse (response).is_err ());
+
+        let response = "*SL\0".as_bytes ();
+        assert! (WakeResponse::parse (response).is_err ());
+
+        let response = b"\xff\xff*WA\0";
+        assert! (WakeResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_name_response () {
+        let response = "?Name,mydevice\0".as_bytes ();
+        assert_eq! (NameResponse::parse (response).unwrap (),
+                    NameResponse ("mydevice".to_string ()));
+
+        let response = "?Name,tank_3-left\0".as_bytes ();
+        assert_eq! (NameResponse::parse (response).unwrap (),
+                    NameResponse ("tank_3-left".to_string ()));
+
+        let response = "?Name,\0".as_bytes ();
+        assert_eq! (NameResponse::parse (response).unwrap (),
+                    NameResponse ("".to_string ()));
+    }
+
+    #[test]
+    fn parsing_invalid_name_response_yields_error () {
+        let response = "\0".as_bytes ();
+        assert! (NameResponse::parse (response).is_err ());
+
+        let response = "?Name,my device\0".as_bytes ();
+        assert! (NameResponse::parse (response).is_err ());
+
+        let response = "?Name,a,b\0".as_bytes ();
+        assert! (NameResponse::parse (response).is_err ());
+
+        let response = "?Name,seventeen_chars__\0".as_bytes ();
+        assert! (NameResponse::parse (response).is_err ());
+
+        let response = "?Name,caf\u{e9}\0".as_bytes ();
+        assert! (NameResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_borrowed_name_response () {
+        let response = "?Name,mydevice\0".as_bytes ();
+        let parsed = NameResponseRef::parse (response).unwrap ();
+        assert_eq! (parsed, NameResponseRef ("mydevice"));
+        assert_eq! (parsed.0.as_ptr (), response[6..].as_ptr ());
+        assert_eq! (NameResponse::from (parsed), NameResponse ("mydevice".to_string ()));
+
+        assert_eq! (NameResponseRef::try_from ("?Name,\0".as_bytes ()).unwrap (), NameResponseRef (""));
+
+        let response = "?Name,my device\0".as_bytes ();
+        assert! (NameResponseRef::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_import_response () {
+        let response = "\0".as_bytes ();
+        assert_eq! (ImportResponse::parse (response).unwrap (), ImportResponse::Accepted);
+
+        let response = "*OK\r".as_bytes ();
+        assert_eq! (ImportResponse::parse (response).unwrap (), ImportResponse::Accepted);
+
+        let response = "*ER\r".as_bytes ();
+        assert_eq! (ImportResponse::parse (response).unwrap (), ImportResponse::Rejected);
+    }
+
+    #[test]
+    fn parsing_invalid_import_response_yields_error () {
+        let response = "".as_bytes ();
+        assert! (ImportResponse::parse (response).is_err ());
+
+        let response = "*DONE\0".as_bytes ();
+        assert! (ImportResponse::parse (response).is_err ());
+
+        let response = "?I,RTD,2.01\0".as_bytes ();
+        assert! (ImportResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_find_response () {
+        let response = "\0".as_bytes ();
+        assert_eq! (FindResponse::parse (response).unwrap (), FindResponse);
+
+        let response = "*OK\r".as_bytes ();
+        assert_eq! (FindResponse::parse (response).unwrap (), FindResponse);
+    }
+
+    #[test]
+    fn parsing_invalid_find_response_yields_error () {
+        let response = "".as_bytes ();
+        assert! (FindResponse::parse (response).is_err ());
+
+        let response = "*ER\r".as_bytes ();
+        assert! (FindResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn converts_temperature_to_celsius () {
+        assert_eq! (Temperature::Celsius (25.0).to_celsius (), 25.0);
+        assert! ((Temperature::Kelvin (298.15).to_celsius () - 25.0).abs () < 1e-9);
+        assert! ((Temperature::Fahrenheit (77.0).to_celsius () - 25.0).abs () < 1e-9);
+    }
+
+    fn parse_generic<T: ResponseParse> (response: &str) -> Result<T> {
+        T::parse (response.as_bytes ())
+    }
+
+    #[test]
+    fn parses_responses_generically () {
+        assert_eq! (parse_generic::<LedStateResponse> ("?L,1\0").unwrap (), LedStateResponse (LedState::On));
+        assert_eq! (parse_generic::<StatusToken> ("*OK\r").unwrap (), StatusToken::Ok);
+        assert_eq! (parse_generic::<AlarmConfigResponse<u32>> ("?Alarm,1500,100,1\0").unwrap (),
+                    AlarmConfigResponse {
+                        threshold: 1500,
+                        tolerance: 100,
+                        enabled: true
+                    });
+
+        assert! (parse_generic::<BaudRateResponse> ("?L,1\0").is_err ());
+    }
+
+    #[test]
+    fn converts_responses () {
+        use std::convert::TryFrom;
+
+        assert_eq! (LedStateResponse::try_from ("?L,0\0".as_bytes ()).unwrap (), LedStateResponse (LedState::Off));
+        assert! (LedStateResponse::try_from ("?L,0".as_bytes ()).is_err ());
+
+        assert_eq! ("?Baud,9600".parse::<BaudRateResponse> ().unwrap (), BaudRateResponse (BaudRate::Bps9600));
+        assert_eq! ("?Alarm,20.5,1,0".parse::<AlarmConfigResponse<f64>> ().unwrap (),
+                    AlarmConfigResponse {
+                        threshold: 20.5,
+                        tolerance: 1.0,
+                        enabled: false
+                    });
+
+        assert! ("?Baud,9600\0".parse::<BaudRateResponse> ().is_err ());
+    }
+
+    #[test]
+    fn parses_responses_leniently () {
+        let options = ParseOptions::lenient ();
+
+        assert_eq! (options.parse::<NameResponse> ("?NAME,MyTank\r\n".as_bytes ()).unwrap (),
+                    NameResponse ("MyTank".to_string ()));
+        assert_eq! (options.parse::<LedStateResponse> ("?l,1".as_bytes ()).unwrap (),
+                    LedStateResponse (LedState::On));
+        assert_eq! (options.parse::<DeviceInfoResponse> ("?i,rtd,2.01 ".as_bytes ()).unwrap (),
+                    DeviceInfoResponse {
+                        device_type: DeviceType::Rtd,
+                        firmware: FirmwareVersion { major: 2, minor: 1, minor_digits: 2 }
+                    });
+
+        assert! (options.parse::<NameResponse> ("?Name,my tank".as_bytes ()).is_err ());
+    }
+
+    #[test]
+    fn parses_mixed_case_values_leniently () {
+        let options = ParseOptions::lenient ();
+
+        assert_eq! (options.parse::<DeviceInfoResponse> ("?i,ph,1.98".as_bytes ()).unwrap (),
+                    DeviceInfoResponse {
+                        device_type: DeviceType::Ph,
+                        firmware: FirmwareVersion { major: 1, minor: 98, minor_digits: 2 }
+                    });
+        assert_eq! (options.parse::<DeviceInfoResponse> ("?I,Orp,1.0".as_bytes ()).unwrap (),
+                    DeviceInfoResponse {
+                        device_type: DeviceType::Orp,
+                        firmware: FirmwareVersion { major: 1, minor: 0, minor_digits: 1 }
+                    });
+        assert_eq! (options.parse::<DeviceStatusResponse> ("?status,p,5.038".as_bytes ()).unwrap (),
+                    DeviceStatusResponse {
+                        restart_reason: RestartReason::PoweredOff,
+                        vcc_voltage: 5.038
+                    });
+        assert_eq! (options.parse::<SleepResponse> ("*sl".as_bytes ()).unwrap (),
+                    SleepResponse);
+
+        assert! (DeviceInfoResponse::parse ("?I,ph,1.98\0".as_bytes ()).is_err ());
+        assert! (DeviceStatusResponse::parse ("?Status,p,5.038\0".as_bytes ()).is_err ());
+    }
+}
@@ -0,0 +1,826 @@
+//! Responses and commands for the EZO DO (dissolved oxygen) circuit
+
+use std::fmt;
+use std::time::Duration;
+
+use errors::*;
+use framing::{format_decimal, parse_number, write_output_params, write_reading, SplitCommand};
+use common::validate_compensation_temperature;
+use {parse_field, parse_output_params, CommonCommand, ParseOptions, Temperature};
+
+/// Dissolved oxygen concentration in mg/L
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct MilligramsPerLiter (pub f64);
+
+impl fmt::Display for MilligramsPerLiter {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{} mg/L", self.0)
+    }
+}
+
+/// Dissolved oxygen as a percentage of saturation
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct PercentSaturation (pub f64);
+
+impl fmt::Display for PercentSaturation {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{} %", self.0)
+    }
+}
+
+/// Converts between the two representations of dissolved oxygen, given the
+/// conditions of the water sample.
+///
+/// This computes the concentration of oxygen at 100% saturation with the
+/// Benson and Krause equations, as used by the USGS.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DissolvedOxygenConverter {
+    saturation: MilligramsPerLiter
+}
+
+impl DissolvedOxygenConverter {
+    /// Creates a converter for water at the given temperature, salinity in
+    /// parts per thousand, and atmospheric pressure.
+    pub fn new (temperature: &Temperature, salinity: f64, pressure: Kilopascals) -> DissolvedOxygenConverter {
+        let t = temperature.to_celsius ();
+        let k = t + 273.15;
+
+        let ln_freshwater = -139.34411 + 1.575701e5 / k - 6.642308e7 / k.powi (2)
+            + 1.243800e10 / k.powi (3) - 8.621949e11 / k.powi (4);
+        let ln_salinity = salinity * (1.7674e-2 - 10.754 / k + 2140.7 / k.powi (2));
+        let at_sea_level = (ln_freshwater - ln_salinity).exp ();
+
+        let atmospheres = pressure.0 / 101.325;
+        let water_vapor = (11.8571 - 3840.70 / k - 216961.0 / k.powi (2)).exp ();
+        let theta = 0.000975 - 1.426e-5 * t + 6.436e-8 * t * t;
+
+        let pressure_factor = atmospheres * (1.0 - water_vapor / atmospheres) * (1.0 - theta * atmospheres)
+            / ((1.0 - water_vapor) * (1.0 - theta));
+
+        DissolvedOxygenConverter {
+            saturation: MilligramsPerLiter (at_sea_level * pressure_factor)
+        }
+    }
+
+    /// Returns the concentration of oxygen at 100% saturation.
+    pub fn saturation (&self) -> MilligramsPerLiter {
+        self.saturation
+    }
+
+    pub fn to_percent_saturation (&self, concentration: MilligramsPerLiter) -> PercentSaturation {
+        PercentSaturation (concentration.0 / self.saturation.0 * 100.0)
+    }
+
+    pub fn to_milligrams_per_liter (&self, saturation: PercentSaturation) -> MilligramsPerLiter {
+        MilligramsPerLiter (saturation.0 / 100.0 * self.saturation.0)
+    }
+}
+
+/// Which parameters the DO circuit includes in its readings.  The circuit
+/// always reports the enabled parameters in the order of the fields here.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct OutputParams {
+    /// "mg", concentration in mg/L
+    pub milligrams_per_liter: bool,
+    /// "%", percentage of saturation
+    pub percent_saturation: bool
+}
+
+impl OutputParams {
+    /// All of the parameters enabled.
+    pub fn all () -> OutputParams {
+        OutputParams {
+            milligrams_per_liter: true,
+            percent_saturation: true
+        }
+    }
+}
+
+impl fmt::Display for OutputParams {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_output_params (f, &[("mg", self.milligrams_per_liter),
+                                  ("%", self.percent_saturation)])
+    }
+}
+
+/// Response from the "R" command to take a reading.  Only the parameters
+/// which are enabled in the circuit's output configuration are present.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct DoResponse {
+    pub milligrams_per_liter: Option<MilligramsPerLiter>,
+    pub percent_saturation: Option<PercentSaturation>
+}
+
+impl DoResponse {
+    /// Parses the result of the "R" or "RT,n" commands to take a reading,
+    /// given the parameters which are enabled in the circuit, as reported
+    /// by "O,?".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse (response: &[u8], params: &OutputParams) -> Result<DoResponse> {
+        DoResponse::parse_with_options (response, params, &ParseOptions::default ())
+    }
+
+    /// Like `parse()`, with the leniencies in `options`.
+    pub fn parse_with_options (response: &[u8], params: &OutputParams, options: &ParseOptions)
+                               -> Result<DoResponse> {
+        let r = options.body (response)?;
+        let mut split = r.split (',');
+
+        let mut field = |enabled| -> Result<Option<f64>> {
+            if enabled {
+                Ok (Some (parse_field (split.next ())?))
+            } else {
+                Ok (None)
+            }
+        };
+
+        let milligrams_per_liter = field (params.milligrams_per_liter)?.map (MilligramsPerLiter);
+        let percent_saturation = field (params.percent_saturation)?.map (PercentSaturation);
+
+        if split.next ().is_some () {
+            return Err (ErrorKind::ResponseParse.into ());
+        }
+
+        Ok (DoResponse {
+            milligrams_per_liter,
+            percent_saturation
+        })
+    }
+}
+
+impl fmt::Display for DoResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_reading (f, &[("concentration", self.milligrams_per_liter.as_ref ().map (|v| v as _)),
+                            ("saturation", self.percent_saturation.as_ref ().map (|v| v as _))])
+    }
+}
+
+/// Response from the "O,?" command to query which parameters the DO
+/// circuit includes in its readings
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct OutputParamsResponse (pub OutputParams);
+
+impl OutputParamsResponse {
+    /// Parses the result of the "O,?" command, like "?O,%,mg".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<OutputParamsResponse> {
+        let enabled = parse_output_params (response, &["mg", "%"], options)?;
+
+        Ok (OutputParamsResponse (OutputParams {
+            milligrams_per_liter: enabled[0],
+            percent_saturation: enabled[1]
+        }))
+    }
+}
+
+impl fmt::Display for OutputParamsResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "output parameters are {}", self.0)
+    }
+}
+
+/// Unit in which the DO circuit's salinity compensation is expressed
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SalinityUnit {
+    /// "us", conductivity in μS/cm
+    MicroSiemens,
+    /// "ppt", salinity in parts per thousand
+    PartsPerThousand
+}
+
+impl fmt::Display for SalinityUnit {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let unit = match *self {
+            SalinityUnit::MicroSiemens     => "μS/cm",
+            SalinityUnit::PartsPerThousand => "ppt"
+        };
+
+        write! (f, "{}", unit)
+    }
+}
+
+/// Response from the "S,?" command to query the salinity which the DO
+/// circuit uses to compensate its readings.  Note that unlike the RTD
+/// circuit's "S" command, this has nothing to do with temperature scales.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SalinityCompensationResponse {
+    pub value: f64,
+    pub unit: SalinityUnit
+}
+
+impl SalinityCompensationResponse {
+    /// Parses the result of the "S,?" command, like "?S,50000,us".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<SalinityCompensationResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?S,") {
+            let mut split = rest.split (',');
+
+            let value = parse_field (split.next ())?;
+
+            let unit = match split.next () {
+                Some (u) if options.matches (u, "us")  => SalinityUnit::MicroSiemens,
+                Some (u) if options.matches (u, "ppt") => SalinityUnit::PartsPerThousand,
+                _                                      => return Err (ErrorKind::ResponseParse.into ())
+            };
+
+            if split.next ().is_some () {
+                return Err (ErrorKind::ResponseParse.into ());
+            }
+
+            Ok (SalinityCompensationResponse {
+                value,
+                unit
+            })
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for SalinityCompensationResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "salinity compensation is {} {}", self.value, self.unit)
+    }
+}
+
+/// Pressure in kilopascals
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Kilopascals (pub f64);
+
+impl fmt::Display for Kilopascals {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{} kPa", self.0)
+    }
+}
+
+/// Response from the "P,?" command to query the atmospheric pressure which
+/// the DO circuit uses to compensate its readings
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PressureCompensationResponse (pub Kilopascals);
+
+impl PressureCompensationResponse {
+    /// Parses the result of the "P,?" command.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<PressureCompensationResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?P,") {
+            Ok (PressureCompensationResponse (Kilopascals (parse_field (Some (rest))?)))
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for PressureCompensationResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "pressure compensation is {}", self.0)
+    }
+}
+
+/// Calibration of the dissolved oxygen probe
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum DoCalibrationState {
+    Uncalibrated,
+    /// Calibrated to atmospheric oxygen
+    Atmospheric,
+    /// Calibrated to atmospheric oxygen and to a zero dissolved oxygen solution
+    AtmosphericAndZero
+}
+
+impl fmt::Display for DoCalibrationState {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DoCalibrationState::Uncalibrated       => write! (f, "uncalibrated"),
+            DoCalibrationState::Atmospheric        => write! (f, "calibrated to atmospheric oxygen"),
+            DoCalibrationState::AtmosphericAndZero => write! (f, "calibrated to atmospheric and zero dissolved oxygen")
+        }
+    }
+}
+
+/// Response from the "Cal,?" command to query the DO circuit's calibration
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CalibrationStateResponse (pub DoCalibrationState);
+
+impl CalibrationStateResponse {
+    /// Parses the result of the "Cal,?" command to query how the DO
+    /// circuit has been calibrated.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<CalibrationStateResponse> {
+        let r = options.body (response)?;
+
+        match options.strip_keyword (r, "?Cal,") {
+            Some ("0") => Ok (CalibrationStateResponse (DoCalibrationState::Uncalibrated)),
+            Some ("1") => Ok (CalibrationStateResponse (DoCalibrationState::Atmospheric)),
+            Some ("2") => Ok (CalibrationStateResponse (DoCalibrationState::AtmosphericAndZero)),
+            _ => Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for CalibrationStateResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "device is {}", self.0)
+    }
+}
+
+/// A parameter which the DO circuit can include in its readings
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OutputParameter {
+    /// "mg", concentration in mg/L
+    MilligramsPerLiter,
+    /// "%", percentage of saturation
+    PercentSaturation
+}
+
+impl OutputParameter {
+    fn code (&self) -> &'static str {
+        match *self {
+            OutputParameter::MilligramsPerLiter => "mg",
+            OutputParameter::PercentSaturation  => "%"
+        }
+    }
+
+    fn from_code (code: &str, options: &ParseOptions) -> Option<OutputParameter> {
+        match code {
+            c if options.matches (c, "mg") => Some (OutputParameter::MilligramsPerLiter),
+            c if options.matches (c, "%")  => Some (OutputParameter::PercentSaturation),
+            _                              => None
+        }
+    }
+}
+
+impl fmt::Display for OutputParameter {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            OutputParameter::MilligramsPerLiter => "concentration",
+            OutputParameter::PercentSaturation  => "saturation"
+        };
+
+        write! (f, "{}", name)
+    }
+}
+
+/// Minimum atmospheric pressure for compensation, in kPa
+pub const MIN_PRESSURE_COMPENSATION: f64 = 30.39;
+
+/// Maximum atmospheric pressure for compensation, in kPa
+pub const MAX_PRESSURE_COMPENSATION: f64 = 3039.75;
+
+/// Commands which can be sent to the EZO DO circuit
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Command {
+    /// "R", take a reading
+    Read,
+    /// "Cal", calibrate to atmospheric oxygen
+    Calibrate,
+    /// "Cal,0", calibrate to a zero dissolved oxygen solution
+    CalibrateZero,
+    /// "S,n" or "S,n,ppt", set the salinity used to compensate readings
+    SetSalinityCompensation (f64, SalinityUnit),
+    /// "S,?", query the salinity compensation
+    SalinityCompensationQuery,
+    /// "P,n", set the atmospheric pressure used to compensate readings
+    SetPressureCompensation (Kilopascals),
+    /// "P,?", query the pressure compensation
+    PressureCompensationQuery,
+    /// "O,param,n", enable or disable a parameter in readings
+    SetOutput (OutputParameter, bool),
+    /// "O,?", query which parameters are enabled in readings
+    OutputQuery,
+    /// "T,n", set the temperature in Celsius used to compensate readings
+    SetCompensationTemperature (f64),
+    /// "RT,n", set the compensation temperature in Celsius and take a
+    /// reading, in a single round trip
+    ReadCompensated (f64),
+    /// "T,?", query the compensation temperature
+    CompensationTemperatureQuery,
+    /// A command which is common to all the EZO circuits
+    Common (CommonCommand)
+}
+
+impl Command {
+    /// Creates a command to set the salinity compensation, which must not
+    /// be negative.
+    pub fn set_salinity_compensation (value: f64, unit: SalinityUnit) -> Result<Command> {
+        if value >= 0.0 && value.is_finite () {
+            Ok (Command::SetSalinityCompensation (value, unit))
+        } else {
+            Err (ErrorKind::InvalidCommandParameter ("salinity compensation must not be negative").into ())
+        }
+    }
+
+    /// Creates a command to set the pressure compensation, which must be
+    /// between `MIN_PRESSURE_COMPENSATION` and `MAX_PRESSURE_COMPENSATION`.
+    pub fn set_pressure_compensation (pressure: Kilopascals) -> Result<Command> {
+        if (MIN_PRESSURE_COMPENSATION ..= MAX_PRESSURE_COMPENSATION).contains (&pressure.0) {
+            Ok (Command::SetPressureCompensation (pressure))
+        } else {
+            Err (ErrorKind::InvalidCommandParameter ("pressure compensation is out of range").into ())
+        }
+    }
+
+    /// Creates a command to set the compensation temperature in Celsius.
+    /// This must be within the range of the EZO RTD's probe, so that its
+    /// readings can be passed directly.
+    pub fn set_compensation_temperature (temperature: f64) -> Result<Command> {
+        Ok (Command::SetCompensationTemperature (validate_compensation_temperature (temperature)?))
+    }
+
+    /// Creates a command to set the compensation temperature in Celsius and
+    /// take a reading at the same time.  The temperature has the same range
+    /// as for `set_compensation_temperature()`.
+    pub fn read_compensated (temperature: f64) -> Result<Command> {
+        Ok (Command::ReadCompensated (validate_compensation_temperature (temperature)?))
+    }
+
+    /// Parses a command string as it would be sent to the device, like
+    /// "Cal,0" or "S,37.5,ppt", in the same way as `saiba_parser::Command::parse()`.
+    pub fn parse (command: &[u8]) -> Result<Command> {
+        let split = SplitCommand::new (command)?;
+
+        if let Some (common) = CommonCommand::from_split (&split)? {
+            return Ok (Command::Common (common));
+        }
+
+        let mut args = split.keyword.as_ref ().map (|k| k.splitn (2, ','));
+        let first = args.as_mut ().and_then (|a| a.next ());
+        let second = args.as_mut ().and_then (|a| a.next ());
+
+        let command = match (split.name.as_str (), first, second) {
+            ("r", None, None)                 => Command::Read,
+            ("cal", None, None)               => Command::Calibrate,
+            ("cal", Some ("0"), None)         => Command::CalibrateZero,
+            ("s", Some ("?"), None)           => Command::SalinityCompensationQuery,
+
+            ("s", Some (n), None) | ("s", Some (n), Some ("us")) =>
+                Command::set_salinity_compensation (parse_number (n)?, SalinityUnit::MicroSiemens)?,
+
+            ("s", Some (n), Some ("ppt"))     => Command::set_salinity_compensation (parse_number (n)?, SalinityUnit::PartsPerThousand)?,
+            ("p", Some ("?"), None)           => Command::PressureCompensationQuery,
+            ("p", Some (n), None)             => Command::set_pressure_compensation (Kilopascals (parse_number (n)?))?,
+            ("o", Some ("?"), None)           => Command::OutputQuery,
+
+            ("o", Some (p), Some (n)) => {
+                let param = OutputParameter::from_code (p, &split.options ()).ok_or (ErrorKind::CommandParse)?;
+
+                match n {
+                    "0" => Command::SetOutput (param, false),
+                    "1" => Command::SetOutput (param, true),
+                    _   => return Err (ErrorKind::CommandParse.into ())
+                }
+            },
+
+            ("rt", Some (n), None)            => Command::read_compensated (parse_number (n)?)?,
+            ("t", Some ("?"), None)           => Command::CompensationTemperatureQuery,
+            ("t", Some (n), None)             => Command::set_compensation_temperature (parse_number (n)?)?,
+            _                                 => return Err (ErrorKind::CommandParse.into ())
+        };
+
+        Ok (command)
+    }
+
+    /// Returns how long to wait after sending this command before reading
+    /// the device's response over I2C.
+    pub fn delay (&self) -> Duration {
+        match *self {
+            Command::Read
+                | Command::ReadCompensated (_) => Duration::from_millis (600),
+
+            Command::Calibrate
+                | Command::CalibrateZero => Duration::from_millis (1300),
+
+            Command::Common (ref common) => common.delay (),
+            _                            => Duration::from_millis (300)
+        }
+    }
+
+    /// Returns the bytes to send to the device for this command.  In I2C
+    /// mode these are sent as-is; in UART mode, the caller must append a
+    /// carriage return.
+    pub fn encode (&self) -> Vec<u8> {
+        let s = match *self {
+            Command::Read                               => "R".to_string (),
+            Command::Calibrate                          => "Cal".to_string (),
+            Command::CalibrateZero                      => "Cal,0".to_string (),
+
+            Command::SetSalinityCompensation (n, SalinityUnit::MicroSiemens) =>
+                format! ("S,{}", format_decimal (n)),
+
+            Command::SetSalinityCompensation (n, SalinityUnit::PartsPerThousand) =>
+                format! ("S,{},ppt", format_decimal (n)),
+
+            Command::SalinityCompensationQuery          => "S,?".to_string (),
+            Command::SetPressureCompensation (p)        => format! ("P,{}", format_decimal (p.0)),
+            Command::PressureCompensationQuery          => "P,?".to_string (),
+            Command::SetOutput (param, enabled)         => format! ("O,{},{}", param.code (), if enabled { 1 } else { 0 }),
+            Command::OutputQuery                        => "O,?".to_string (),
+            Command::SetCompensationTemperature (t)     => format! ("T,{}", format_decimal (t)),
+            Command::ReadCompensated (t)                => format! ("RT,{}", format_decimal (t)),
+            Command::CompensationTemperatureQuery       => "T,?".to_string (),
+            Command::Common (ref common)                => return common.encode ()
+        };
+
+        s.into_bytes ()
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Command::Read                              => write! (f, "take a reading"),
+            Command::Calibrate                         => write! (f, "calibrate to atmospheric oxygen"),
+            Command::CalibrateZero                     => write! (f, "calibrate to zero dissolved oxygen"),
+            Command::SetSalinityCompensation (n, unit) => write! (f, "set salinity compensation to {} {}", format_decimal (n), unit),
+            Command::SalinityCompensationQuery         => write! (f, "query salinity compensation"),
+            Command::SetPressureCompensation (p)       => write! (f, "set pressure compensation to {}", p),
+            Command::PressureCompensationQuery         => write! (f, "query pressure compensation"),
+            Command::SetOutput (param, true)           => write! (f, "enable {} in readings", param),
+            Command::SetOutput (param, false)          => write! (f, "disable {} in readings", param),
+            Command::OutputQuery                       => write! (f, "query output parameters"),
+            Command::SetCompensationTemperature (t)    => write! (f, "set compensation temperature to {} °C", format_decimal (t)),
+            Command::ReadCompensated (t)               => write! (f, "take a reading compensated at {} °C", format_decimal (t)),
+            Command::CompensationTemperatureQuery      => write! (f, "query compensation temperature"),
+            Command::Common (ref common)               => common.fmt (f)
+        }
+    }
+}
+
+impl From<CommonCommand> for Command {
+    fn from (c: CommonCommand) -> Command {
+        Command::Common (c)
+    }
+}
+
+response_parse! (OutputParamsResponse, SalinityCompensationResponse,
+                 PressureCompensationResponse, CalibrationStateResponse);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_do_response () {
+        let response = "8.26,102.3\0".as_bytes ();
+        assert_eq! (DoResponse::parse (response, &OutputParams::all ()).unwrap (),
+                    DoResponse {
+                        milligrams_per_liter: Some (MilligramsPerLiter (8.26)),
+                        percent_saturation: Some (PercentSaturation (102.3))
+                    });
+
+        let params = OutputParams {
+            percent_saturation: true,
+            ..OutputParams::default ()
+        };
+
+        let response = "102.3\r".as_bytes ();
+        assert_eq! (DoResponse::parse (response, &params).unwrap (),
+                    DoResponse {
+                        milligrams_per_liter: None,
+                        percent_saturation: Some (PercentSaturation (102.3))
+                    });
+    }
+
+    #[test]
+    fn parsing_invalid_do_response_yields_error () {
+        let response = "8.26\0".as_bytes ();
+        assert! (DoResponse::parse (response, &OutputParams::all ()).is_err ());
+
+        let response = "8.26,102.3,1\0".as_bytes ();
+        assert! (DoResponse::parse (response, &OutputParams::all ()).is_err ());
+
+        let response = "8.26,\0".as_bytes ();
+        assert! (DoResponse::parse (response, &OutputParams::all ()).is_err ());
+    }
+
+    #[test]
+    fn displays_do_units () {
+        assert_eq! (MilligramsPerLiter (8.26).to_string (), "8.26 mg/L");
+        assert_eq! (PercentSaturation (102.3).to_string (), "102.3 %");
+    }
+
+    #[test]
+    fn parses_output_params_response () {
+        let response = "?O,%,mg\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams::all ()));
+
+        let response = "?O,mg\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams {
+                        milligrams_per_liter: true,
+                        percent_saturation: false
+                    }));
+
+        let response = "?O,No output\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams::default ()));
+    }
+
+    #[test]
+    fn parsing_invalid_output_params_response_yields_error () {
+        let response = "?O,\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+
+        let response = "?O,%,%\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+
+        let response = "?O,EC\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_salinity_compensation_response () {
+        let response = "?S,50000,us\0".as_bytes ();
+        assert_eq! (SalinityCompensationResponse::parse (response).unwrap (),
+                    SalinityCompensationResponse {
+                        value: 50000.0,
+                        unit: SalinityUnit::MicroSiemens
+                    });
+
+        let response = "?S,37.5,ppt\0".as_bytes ();
+        assert_eq! (SalinityCompensationResponse::parse (response).unwrap (),
+                    SalinityCompensationResponse {
+                        value: 37.5,
+                        unit: SalinityUnit::PartsPerThousand
+                    });
+    }
+
+    #[test]
+    fn parses_mixed_case_salinity_compensation_response_leniently () {
+        let options = ParseOptions::lenient ();
+
+        assert_eq! (options.parse::<SalinityCompensationResponse> ("?s,50000,US".as_bytes ()).unwrap (),
+                    SalinityCompensationResponse {
+                        value: 50000.0,
+                        unit: SalinityUnit::MicroSiemens
+                    });
+
+        let response = "?S,50000,US\0".as_bytes ();
+        assert! (SalinityCompensationResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parsing_invalid_salinity_compensation_response_yields_error () {
+        let response = "?S,50000\0".as_bytes ();
+        assert! (SalinityCompensationResponse::parse (response).is_err ());
+
+        let response = "?S,50000,mg\0".as_bytes ();
+        assert! (SalinityCompensationResponse::parse (response).is_err ());
+
+        let response = "?S,37.5,ppt,1\0".as_bytes ();
+        assert! (SalinityCompensationResponse::parse (response).is_err ());
+
+        let response = "?S,c\0".as_bytes ();
+        assert! (SalinityCompensationResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_pressure_compensation_response () {
+        let response = "?P,101.3\0".as_bytes ();
+        assert_eq! (PressureCompensationResponse::parse (response).unwrap (),
+                    PressureCompensationResponse (Kilopascals (101.3)));
+
+        let response = "?P,\0".as_bytes ();
+        assert! (PressureCompensationResponse::parse (response).is_err ());
+
+        let response = "?P,high\0".as_bytes ();
+        assert! (PressureCompensationResponse::parse (response).is_err ());
+
+        let response = "101.3\0".as_bytes ();
+        assert! (PressureCompensationResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_calibration_state_response () {
+        let response = "?Cal,0\0".as_bytes ();
+        assert_eq! (CalibrationStateResponse::parse (response).unwrap (),
+                    CalibrationStateResponse (DoCalibrationState::Uncalibrated));
+
+        let response = "?Cal,1\0".as_bytes ();
+        assert_eq! (CalibrationStateResponse::parse (response).unwrap (),
+                    CalibrationStateResponse (DoCalibrationState::Atmospheric));
+
+        let response = "?Cal,2\0".as_bytes ();
+        assert_eq! (CalibrationStateResponse::parse (response).unwrap (),
+                    CalibrationStateResponse (DoCalibrationState::AtmosphericAndZero));
+    }
+
+    #[test]
+    fn parsing_invalid_calibration_state_response_yields_error () {
+        let response = "?Cal,\0".as_bytes ();
+        assert! (CalibrationStateResponse::parse (response).is_err ());
+
+        let response = "?Cal,3\0".as_bytes ();
+        assert! (CalibrationStateResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn encodes_commands () {
+        let commands = [(Command::Read, "R"),
+                        (Command::Calibrate, "Cal"),
+                        (Command::CalibrateZero, "Cal,0"),
+                        (Command::Common (CommonCommand::CalibrationClear), "Cal,clear"),
+                        (Command::set_salinity_compensation (50000.0, SalinityUnit::MicroSiemens).unwrap (), "S,50000.0"),
+                        (Command::set_salinity_compensation (37.5, SalinityUnit::PartsPerThousand).unwrap (), "S,37.5,ppt"),
+                        (Command::SalinityCompensationQuery, "S,?"),
+                        (Command::set_pressure_compensation (Kilopascals (101.3)).unwrap (), "P,101.3"),
+                        (Command::PressureCompensationQuery, "P,?"),
+                        (Command::SetOutput (OutputParameter::MilligramsPerLiter, false), "O,mg,0"),
+                        (Command::SetOutput (OutputParameter::PercentSaturation, true), "O,%,1"),
+                        (Command::OutputQuery, "O,?"),
+                        (Command::set_compensation_temperature (19.5).unwrap (), "T,19.5"),
+                        (Command::read_compensated (12.25).unwrap (), "RT,12.25"),
+                        (Command::CompensationTemperatureQuery, "T,?"),
+                        (CommonCommand::Find.into (), "Find")];
+
+        for &(ref command, encoded) in commands.iter () {
+            assert_eq! (command.encode (), encoded.as_bytes ());
+            assert_eq! (&Command::parse (encoded.as_bytes ()).unwrap (), command);
+        }
+
+        assert_eq! (Command::parse ("S,50000,us\0".as_bytes ()).unwrap (),
+                    Command::SetSalinityCompensation (50000.0, SalinityUnit::MicroSiemens));
+        assert_eq! (Command::parse ("O,MG,1\0".as_bytes ()).unwrap (),
+                    Command::SetOutput (OutputParameter::MilligramsPerLiter, true));
+    }
+
+    #[test]
+    fn invalid_commands_yield_error () {
+        assert! (Command::set_salinity_compensation (-1.0, SalinityUnit::PartsPerThousand).is_err ());
+        assert! (Command::set_pressure_compensation (Kilopascals (10.0)).is_err ());
+        assert! (Command::set_pressure_compensation (Kilopascals (5000.0)).is_err ());
+        assert! (Command::set_compensation_temperature (2000.0).is_err ());
+        assert! (Command::read_compensated (2000.0).is_err ());
+
+        assert! (Command::parse ("Cal,1".as_bytes ()).is_err ());
+        assert! (Command::parse ("S,37.5,psu".as_bytes ()).is_err ());
+        assert! (Command::parse ("O,EC,1".as_bytes ()).is_err ());
+        assert! (Command::parse ("P,".as_bytes ()).is_err ());
+    }
+
+    #[test]
+    fn commands_have_processing_delay () {
+        assert_eq! (Command::Read.delay (), Duration::from_millis (600));
+        assert_eq! (Command::read_compensated (25.0).unwrap ().delay (), Duration::from_millis (600));
+        assert_eq! (Command::Calibrate.delay (), Duration::from_millis (1300));
+        assert_eq! (Command::OutputQuery.delay (), Duration::from_millis (300));
+    }
+
+    #[test]
+    fn converts_dissolved_oxygen () {
+        let converter = DissolvedOxygenConverter::new (&Temperature::Celsius (25.0), 0.0, Kilopascals (101.325));
+        assert! ((converter.saturation ().0 - 8.26).abs () < 0.01);
+
+        let converter = DissolvedOxygenConverter::new (&Temperature::Fahrenheit (68.0), 35.0, Kilopascals (101.325));
+        assert! ((converter.saturation ().0 - 7.39).abs () < 0.01);
+
+        let low_pressure = DissolvedOxygenConverter::new (&Temperature::Fahrenheit (68.0), 35.0, Kilopascals (80.0));
+        assert! (low_pressure.saturation () < converter.saturation ());
+
+        let percent = converter.to_percent_saturation (MilligramsPerLiter (3.7));
+        assert! ((percent.0 - 50.0).abs () < 0.1);
+
+        let mg = converter.to_milligrams_per_liter (percent);
+        assert! ((mg.0 - 3.7).abs () < 1e-9);
+    }
+
+    #[test]
+    fn displays_responses () {
+        let response = DoResponse::parse ("8.26,102.3\0".as_bytes (), &OutputParams::all ()).unwrap ();
+        assert_eq! (response.to_string (), "concentration 8.26 mg/L, saturation 102.3 %");
+        assert_eq! (OutputParamsResponse (OutputParams::all ()).to_string (), "output parameters are mg, %");
+        assert_eq! (SalinityCompensationResponse::parse ("?S,50000,us\0".as_bytes ()).unwrap ().to_string (),
+                    "salinity compensation is 50000 μS/cm");
+        assert_eq! (PressureCompensationResponse::parse ("?P,101.3\0".as_bytes ()).unwrap ().to_string (),
+                    "pressure compensation is 101.3 kPa");
+        assert_eq! (CalibrationStateResponse (DoCalibrationState::Atmospheric).to_string (),
+                    "device is calibrated to atmospheric oxygen");
+    }
+
+    #[test]
+    fn displays_commands () {
+        assert_eq! (Command::CalibrateZero.to_string (), "calibrate to zero dissolved oxygen");
+        assert_eq! (Command::SetSalinityCompensation (35.0, SalinityUnit::PartsPerThousand).to_string (),
+                    "set salinity compensation to 35.0 ppt");
+        assert_eq! (Command::SetOutput (OutputParameter::PercentSaturation, true).to_string (),
+                    "enable saturation in readings");
+        assert_eq! (Command::Common (CommonCommand::Find).to_string (), "blink LED to find device");
+    }
+}
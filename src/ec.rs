@@ -0,0 +1,993 @@
+//! Responses and commands for the EZO EC (conductivity) circuit
+
+use std::fmt;
+use std::time::Duration;
+
+use errors::*;
+use framing::{format_decimal, parse_number, write_output_params, write_reading, SplitCommand};
+use common::validate_compensation_temperature;
+use {parse_field, parse_output_params, CommonCommand, ParseOptions, Temperature};
+
+/// Which parameters the EC circuit includes in its readings.  The circuit
+/// always reports the enabled parameters in the order of the fields here.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct OutputParams {
+    /// "EC", conductivity in μS/cm
+    pub conductivity: bool,
+    /// "TDS", total dissolved solids in ppm
+    pub total_dissolved_solids: bool,
+    /// "S", salinity in PSU
+    pub salinity: bool,
+    /// "SG", specific gravity
+    pub specific_gravity: bool
+}
+
+impl OutputParams {
+    /// All of the parameters enabled, which is the circuit's default.
+    pub fn all () -> OutputParams {
+        OutputParams {
+            conductivity: true,
+            total_dissolved_solids: true,
+            salinity: true,
+            specific_gravity: true
+        }
+    }
+}
+
+impl fmt::Display for OutputParams {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_output_params (f, &[("EC", self.conductivity),
+                                  ("TDS", self.total_dissolved_solids),
+                                  ("S", self.salinity),
+                                  ("SG", self.specific_gravity)])
+    }
+}
+
+/// Conductivity in μS/cm
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct MicroSiemens (pub f64);
+
+impl MicroSiemens {
+    pub fn from_millisiemens (ms: f64) -> MicroSiemens {
+        MicroSiemens (ms * 1000.0)
+    }
+
+    pub fn to_millisiemens (&self) -> f64 {
+        self.0 / 1000.0
+    }
+}
+
+impl fmt::Display for MicroSiemens {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{} μS/cm", self.0)
+    }
+}
+
+/// Total dissolved solids in ppm
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Tds (pub f64);
+
+impl fmt::Display for Tds {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{} ppm", self.0)
+    }
+}
+
+/// Practical salinity in PSU
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Salinity (pub f64);
+
+impl fmt::Display for Salinity {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{} PSU", self.0)
+    }
+}
+
+/// Specific gravity, relative to pure water
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct SpecificGravity (pub f64);
+
+impl fmt::Display for SpecificGravity {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{}", self.0)
+    }
+}
+
+/// Response from the "R" command to take a reading.  Only the parameters
+/// which are enabled in the circuit's output configuration are present.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct EcResponse {
+    pub conductivity: Option<MicroSiemens>,
+    pub total_dissolved_solids: Option<Tds>,
+    pub salinity: Option<Salinity>,
+    pub specific_gravity: Option<SpecificGravity>
+}
+
+impl EcResponse {
+    /// Parses the result of the "R" or "RT,n" commands to take a reading,
+    /// given the parameters which are enabled in the circuit, as reported
+    /// by "O,?".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse (response: &[u8], params: &OutputParams) -> Result<EcResponse> {
+        EcResponse::parse_with_options (response, params, &ParseOptions::default ())
+    }
+
+    /// Like `parse()`, with the leniencies in `options`.
+    pub fn parse_with_options (response: &[u8], params: &OutputParams, options: &ParseOptions)
+                               -> Result<EcResponse> {
+        let r = options.body (response)?;
+        let mut split = r.split (',');
+
+        let mut field = |enabled| -> Result<Option<f64>> {
+            if enabled {
+                Ok (Some (parse_field (split.next ())?))
+            } else {
+                Ok (None)
+            }
+        };
+
+        let conductivity = field (params.conductivity)?.map (MicroSiemens);
+        let total_dissolved_solids = field (params.total_dissolved_solids)?.map (Tds);
+        let salinity = field (params.salinity)?.map (Salinity);
+        let specific_gravity = field (params.specific_gravity)?.map (SpecificGravity);
+
+        if split.next ().is_some () {
+            return Err (ErrorKind::ResponseParse.into ());
+        }
+
+        Ok (EcResponse {
+            conductivity,
+            total_dissolved_solids,
+            salinity,
+            specific_gravity
+        })
+    }
+}
+
+impl fmt::Display for EcResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_reading (f, &[("conductivity", self.conductivity.as_ref ().map (|v| v as _)),
+                            ("TDS", self.total_dissolved_solids.as_ref ().map (|v| v as _)),
+                            ("salinity", self.salinity.as_ref ().map (|v| v as _)),
+                            ("specific gravity", self.specific_gravity.as_ref ().map (|v| v as _))])
+    }
+}
+
+/// Response from the "O,?" command to query which parameters the EC
+/// circuit includes in its readings
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct OutputParamsResponse (pub OutputParams);
+
+impl OutputParamsResponse {
+    /// Parses the result of the "O,?" command, like "?O,EC,TDS,S,SG".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<OutputParamsResponse> {
+        let enabled = parse_output_params (response, &["EC", "TDS", "S", "SG"], options)?;
+
+        Ok (OutputParamsResponse (OutputParams {
+            conductivity: enabled[0],
+            total_dissolved_solids: enabled[1],
+            salinity: enabled[2],
+            specific_gravity: enabled[3]
+        }))
+    }
+}
+
+impl fmt::Display for OutputParamsResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "output parameters are {}", self.0)
+    }
+}
+
+/// Cell constant of a conductivity probe, between 0.1 and 10
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct ProbeK (f64);
+
+impl ProbeK {
+    /// Validates a probe's K value; it must be between 0.1 and 10.
+    pub fn new (k: f64) -> Result<ProbeK> {
+        if (0.1 ..= 10.0).contains (&k) {
+            Ok (ProbeK (k))
+        } else {
+            Err (ErrorKind::ValueOutOfRange ("probe K must be between 0.1 and 10").into ())
+        }
+    }
+
+    pub fn value (&self) -> f64 {
+        self.0
+    }
+}
+
+impl fmt::Display for ProbeK {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "K {}", self.0)
+    }
+}
+
+/// Response from the "K,?" command to query the probe's K value
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ProbeKResponse (pub ProbeK);
+
+impl ProbeKResponse {
+    /// Parses the result of the "K,?" command.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<ProbeKResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?K,") {
+            let k = ProbeK::new (parse_field (Some (rest))?).chain_err (|| ErrorKind::ResponseParse)?;
+            Ok (ProbeKResponse (k))
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for ProbeKResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "probe is {}", self.0)
+    }
+}
+
+/// Factor used to convert conductivity to total dissolved solids, between
+/// 0.01 and 1.00
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct TdsFactor (f64);
+
+impl TdsFactor {
+    /// Validates a TDS conversion factor; it must be between 0.01 and 1.00.
+    pub fn new (factor: f64) -> Result<TdsFactor> {
+        if (0.01 ..= 1.0).contains (&factor) {
+            Ok (TdsFactor (factor))
+        } else {
+            Err (ErrorKind::ValueOutOfRange ("TDS factor must be between 0.01 and 1.00").into ())
+        }
+    }
+
+    pub fn value (&self) -> f64 {
+        self.0
+    }
+}
+
+impl fmt::Display for TdsFactor {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{}", self.0)
+    }
+}
+
+/// Response from the "TDS,?" command to query the TDS conversion factor
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TdsFactorResponse (pub TdsFactor);
+
+impl TdsFactorResponse {
+    /// Parses the result of the "TDS,?" command.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<TdsFactorResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?TDS,") {
+            let factor = TdsFactor::new (parse_field (Some (rest))?).chain_err (|| ErrorKind::ResponseParse)?;
+            Ok (TdsFactorResponse (factor))
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for TdsFactorResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "TDS factor is {}", self.0)
+    }
+}
+
+/// Number of points at which the conductivity probe has been calibrated.
+/// The dry calibration is not counted.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum EcCalibrationState {
+    Uncalibrated,
+    OnePoint,
+    TwoPoint
+}
+
+impl fmt::Display for EcCalibrationState {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EcCalibrationState::Uncalibrated => write! (f, "uncalibrated"),
+            EcCalibrationState::OnePoint     => write! (f, "calibrated at one point"),
+            EcCalibrationState::TwoPoint     => write! (f, "calibrated at two points")
+        }
+    }
+}
+
+/// Response from the "Cal,?" command to query the EC circuit's calibration
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CalibrationStateResponse (pub EcCalibrationState);
+
+impl CalibrationStateResponse {
+    /// Parses the result of the "Cal,?" command to query how many
+    /// calibration points the EC circuit has.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<CalibrationStateResponse> {
+        let r = options.body (response)?;
+
+        match options.strip_keyword (r, "?Cal,") {
+            Some ("0") => Ok (CalibrationStateResponse (EcCalibrationState::Uncalibrated)),
+            Some ("1") => Ok (CalibrationStateResponse (EcCalibrationState::OnePoint)),
+            Some ("2") => Ok (CalibrationStateResponse (EcCalibrationState::TwoPoint)),
+            _ => Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for CalibrationStateResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "device is {}", self.0)
+    }
+}
+
+/// Response from the "T,?" command to query the temperature which the
+/// circuit uses to compensate its readings.  The circuit always uses Celsius.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CompensationTemperatureResponse (pub Temperature);
+
+impl CompensationTemperatureResponse {
+    /// Parses the result of the "T,?" command.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<CompensationTemperatureResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?T,") {
+            let val = parse_field (Some (rest))?;
+            Ok (CompensationTemperatureResponse (Temperature::Celsius (val)))
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+
+    /// Whether the compensation temperature is within `tolerance` degrees
+    /// Celsius of a temperature in any scale, for example a reading from
+    /// the EZO RTD circuit.
+    pub fn matches (&self, temperature: &Temperature, tolerance: f64) -> bool {
+        (self.0.to_celsius () - temperature.to_celsius ()).abs () <= tolerance
+    }
+}
+
+impl fmt::Display for CompensationTemperatureResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "compensation temperature is {}", self.0)
+    }
+}
+
+/// Computes the total dissolved solids from a conductivity.
+pub fn tds_from_conductivity (conductivity: MicroSiemens, factor: TdsFactor) -> Tds {
+    Tds (conductivity.0 * factor.0)
+}
+
+// Conductivity of standard seawater (salinity 35 at 15 °C) in μS/cm
+const STANDARD_SEAWATER_CONDUCTIVITY: f64 = 42914.0;
+
+fn polynomial (coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter ().rev ().fold (0.0, |acc, c| acc * x + c)
+}
+
+/// Computes the practical salinity from a conductivity measured at the
+/// given temperature, using the Practical Salinity Scale of 1978 at
+/// atmospheric pressure.
+pub fn salinity_from_conductivity (conductivity: MicroSiemens, temperature: &Temperature) -> Salinity {
+    const A: [f64; 6] = [0.0080, -0.1692, 25.3851, 14.0941, -7.0261, 2.7081];
+    const B: [f64; 6] = [0.0005, -0.0056, -0.0066, -0.0375, 0.0636, -0.0144];
+    const C: [f64; 5] = [0.6766097, 2.00564e-2, 1.104259e-4, -6.9698e-7, 1.0031e-9];
+
+    let t = temperature.to_celsius ();
+    let ratio = conductivity.0 / STANDARD_SEAWATER_CONDUCTIVITY / polynomial (&C, t);
+    let sqrt_ratio = ratio.sqrt ();
+
+    let delta = (t - 15.0) / (1.0 + 0.0162 * (t - 15.0)) * polynomial (&B, sqrt_ratio);
+
+    Salinity (polynomial (&A, sqrt_ratio) + delta)
+}
+
+// Density of seawater in kg/m³ at atmospheric pressure, from the UNESCO
+// 1981 equation of state
+fn seawater_density (salinity: f64, t: f64) -> f64 {
+    const WATER: [f64; 6] = [999.842594, 6.793952e-2, -9.095290e-3, 1.001685e-4, -1.120083e-6, 6.536332e-9];
+    const B: [f64; 5] = [0.824493, -4.0899e-3, 7.6438e-5, -8.2467e-7, 5.3875e-9];
+    const C: [f64; 3] = [-5.72466e-3, 1.0227e-4, -1.6546e-6];
+    const D: f64 = 4.8314e-4;
+
+    polynomial (&WATER, t)
+        + salinity * polynomial (&B, t)
+        + salinity.powf (1.5) * polynomial (&C, t)
+        + D * salinity * salinity
+}
+
+/// Computes the specific gravity of seawater with the given practical
+/// salinity, relative to pure water at the same temperature.
+pub fn specific_gravity_from_salinity (salinity: Salinity, temperature: &Temperature) -> SpecificGravity {
+    let t = temperature.to_celsius ();
+    SpecificGravity (seawater_density (salinity.0, t) / seawater_density (0.0, t))
+}
+
+/// Computes the specific gravity from a conductivity measured at the given
+/// temperature; see `salinity_from_conductivity()`.
+pub fn specific_gravity_from_conductivity (conductivity: MicroSiemens, temperature: &Temperature) -> SpecificGravity {
+    specific_gravity_from_salinity (salinity_from_conductivity (conductivity, temperature), temperature)
+}
+
+/// A parameter which the EC circuit can include in its readings
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OutputParameter {
+    /// "EC", conductivity
+    Conductivity,
+    /// "TDS", total dissolved solids
+    TotalDissolvedSolids,
+    /// "S", salinity
+    Salinity,
+    /// "SG", specific gravity
+    SpecificGravity
+}
+
+impl OutputParameter {
+    fn code (&self) -> &'static str {
+        match *self {
+            OutputParameter::Conductivity         => "EC",
+            OutputParameter::TotalDissolvedSolids => "TDS",
+            OutputParameter::Salinity             => "S",
+            OutputParameter::SpecificGravity      => "SG"
+        }
+    }
+
+    fn from_code (code: &str, options: &ParseOptions) -> Option<OutputParameter> {
+        match code {
+            c if options.matches (c, "EC")  => Some (OutputParameter::Conductivity),
+            c if options.matches (c, "TDS") => Some (OutputParameter::TotalDissolvedSolids),
+            c if options.matches (c, "S")   => Some (OutputParameter::Salinity),
+            c if options.matches (c, "SG")  => Some (OutputParameter::SpecificGravity),
+            _                               => None
+        }
+    }
+}
+
+impl fmt::Display for OutputParameter {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            OutputParameter::Conductivity         => "conductivity",
+            OutputParameter::TotalDissolvedSolids => "total dissolved solids",
+            OutputParameter::Salinity             => "salinity",
+            OutputParameter::SpecificGravity      => "specific gravity"
+        };
+
+        write! (f, "{}", name)
+    }
+}
+
+/// Commands which can be sent to the EZO EC circuit
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Command {
+    /// "R", take a reading
+    Read,
+    /// "Cal,dry", calibrate with the probe dry; this must be done first
+    CalibrateDry,
+    /// "Cal,n", single point calibration at a conductivity in μS/cm
+    Calibrate (f64),
+    /// "Cal,low,n", calibrate the low point of a two point calibration
+    CalibrateLow (f64),
+    /// "Cal,high,n", calibrate the high point of a two point calibration
+    CalibrateHigh (f64),
+    /// "K,n", set the probe's K value
+    SetProbeK (ProbeK),
+    /// "K,?", query the probe's K value
+    ProbeKQuery,
+    /// "O,param,n", enable or disable a parameter in readings
+    SetOutput (OutputParameter, bool),
+    /// "O,?", query which parameters are enabled in readings
+    OutputQuery,
+    /// "TDS,n", set the TDS conversion factor
+    SetTdsFactor (TdsFactor),
+    /// "TDS,?", query the TDS conversion factor
+    TdsFactorQuery,
+    /// "T,n", set the temperature in Celsius used to compensate readings
+    SetCompensationTemperature (f64),
+    /// "RT,n", set the compensation temperature in Celsius and take a
+    /// reading, in a single round trip
+    ReadCompensated (f64),
+    /// "T,?", query the compensation temperature
+    CompensationTemperatureQuery,
+    /// A command which is common to all the EZO circuits
+    Common (CommonCommand)
+}
+
+fn validate_conductivity (conductivity: f64) -> Result<f64> {
+    if conductivity > 0.0 && conductivity.is_finite () {
+        Ok (conductivity)
+    } else {
+        Err (ErrorKind::InvalidCommandParameter ("calibration conductivity must be positive").into ())
+    }
+}
+
+impl Command {
+    /// Creates a command for single point calibration at the given
+    /// conductivity in μS/cm, which must be positive.
+    pub fn calibrate (conductivity: f64) -> Result<Command> {
+        Ok (Command::Calibrate (validate_conductivity (conductivity)?))
+    }
+
+    /// Creates a command to calibrate the low point at the given
+    /// conductivity in μS/cm, which must be positive.
+    pub fn calibrate_low (conductivity: f64) -> Result<Command> {
+        Ok (Command::CalibrateLow (validate_conductivity (conductivity)?))
+    }
+
+    /// Creates a command to calibrate the high point at the given
+    /// conductivity in μS/cm, which must be positive.
+    pub fn calibrate_high (conductivity: f64) -> Result<Command> {
+        Ok (Command::CalibrateHigh (validate_conductivity (conductivity)?))
+    }
+
+    /// Creates a command to set the probe's K value, which must be
+    /// between 0.1 and 10.
+    pub fn set_probe_k (k: f64) -> Result<Command> {
+        let k = ProbeK::new (k).chain_err (|| ErrorKind::InvalidCommandParameter ("probe K must be between 0.1 and 10"))?;
+        Ok (Command::SetProbeK (k))
+    }
+
+    /// Creates a command to set the TDS conversion factor, which must be
+    /// between 0.01 and 1.00.
+    pub fn set_tds_factor (factor: f64) -> Result<Command> {
+        let factor = TdsFactor::new (factor).chain_err (|| ErrorKind::InvalidCommandParameter ("TDS factor must be between 0.01 and 1.00"))?;
+        Ok (Command::SetTdsFactor (factor))
+    }
+
+    /// Creates a command to set the compensation temperature in Celsius.
+    /// This must be within the range of the EZO RTD's probe, so that its
+    /// readings can be passed directly.
+    pub fn set_compensation_temperature (temperature: f64) -> Result<Command> {
+        Ok (Command::SetCompensationTemperature (validate_compensation_temperature (temperature)?))
+    }
+
+    /// Creates a command to set the compensation temperature in Celsius and
+    /// take a reading at the same time.  The temperature has the same range
+    /// as for `set_compensation_temperature()`.
+    pub fn read_compensated (temperature: f64) -> Result<Command> {
+        Ok (Command::ReadCompensated (validate_compensation_temperature (temperature)?))
+    }
+
+    /// Parses a command string as it would be sent to the device, like
+    /// "Cal,low,12880" or "O,TDS,0", in the same way as `saiba_parser::Command::parse()`.
+    pub fn parse (command: &[u8]) -> Result<Command> {
+        let split = SplitCommand::new (command)?;
+
+        if let Some (common) = CommonCommand::from_split (&split)? {
+            return Ok (Command::Common (common));
+        }
+
+        let mut args = split.keyword.as_ref ().map (|k| k.splitn (2, ','));
+        let first = args.as_mut ().and_then (|a| a.next ());
+        let second = args.as_mut ().and_then (|a| a.next ());
+
+        let command = match (split.name.as_str (), first, second) {
+            ("r", None, None)                 => Command::Read,
+            ("cal", Some ("dry"), None)       => Command::CalibrateDry,
+            ("cal", Some ("low"), Some (n))   => Command::calibrate_low (parse_number (n)?)?,
+            ("cal", Some ("high"), Some (n))  => Command::calibrate_high (parse_number (n)?)?,
+            ("cal", Some (n), None)           => Command::calibrate (parse_number (n)?)?,
+            ("k", Some ("?"), None)           => Command::ProbeKQuery,
+            ("k", Some (n), None)             => Command::set_probe_k (parse_number (n)?)?,
+            ("o", Some ("?"), None)           => Command::OutputQuery,
+
+            ("o", Some (p), Some (n)) => {
+                let param = OutputParameter::from_code (p, &split.options ()).ok_or (ErrorKind::CommandParse)?;
+
+                match n {
+                    "0" => Command::SetOutput (param, false),
+                    "1" => Command::SetOutput (param, true),
+                    _   => return Err (ErrorKind::CommandParse.into ())
+                }
+            },
+
+            ("tds", Some ("?"), None)         => Command::TdsFactorQuery,
+            ("tds", Some (n), None)           => Command::set_tds_factor (parse_number (n)?)?,
+            ("rt", Some (n), None)            => Command::read_compensated (parse_number (n)?)?,
+            ("t", Some ("?"), None)           => Command::CompensationTemperatureQuery,
+            ("t", Some (n), None)             => Command::set_compensation_temperature (parse_number (n)?)?,
+            _                                 => return Err (ErrorKind::CommandParse.into ())
+        };
+
+        Ok (command)
+    }
+
+    /// Returns how long to wait after sending this command before reading
+    /// the device's response over I2C.
+    pub fn delay (&self) -> Duration {
+        match *self {
+            Command::Read
+                | Command::ReadCompensated (_)
+                | Command::CalibrateDry
+                | Command::Calibrate (_)
+                | Command::CalibrateLow (_)
+                | Command::CalibrateHigh (_) => Duration::from_millis (600),
+
+            Command::Common (ref common) => common.delay (),
+            _                            => Duration::from_millis (300)
+        }
+    }
+
+    /// Returns the bytes to send to the device for this command.  In I2C
+    /// mode these are sent as-is; in UART mode, the caller must append a
+    /// carriage return.
+    pub fn encode (&self) -> Vec<u8> {
+        let s = match *self {
+            Command::Read                               => "R".to_string (),
+            Command::CalibrateDry                       => "Cal,dry".to_string (),
+            Command::Calibrate (n)                      => format! ("Cal,{}", format_decimal (n)),
+            Command::CalibrateLow (n)                   => format! ("Cal,low,{}", format_decimal (n)),
+            Command::CalibrateHigh (n)                  => format! ("Cal,high,{}", format_decimal (n)),
+            Command::SetProbeK (k)                      => format! ("K,{}", format_decimal (k.0)),
+            Command::ProbeKQuery                        => "K,?".to_string (),
+            Command::SetOutput (param, enabled)         => format! ("O,{},{}", param.code (), if enabled { 1 } else { 0 }),
+            Command::OutputQuery                        => "O,?".to_string (),
+            Command::SetTdsFactor (factor)              => format! ("TDS,{}", format_decimal (factor.0)),
+            Command::TdsFactorQuery                     => "TDS,?".to_string (),
+            Command::SetCompensationTemperature (t)     => format! ("T,{}", format_decimal (t)),
+            Command::ReadCompensated (t)                => format! ("RT,{}", format_decimal (t)),
+            Command::CompensationTemperatureQuery       => "T,?".to_string (),
+            Command::Common (ref common)                => return common.encode ()
+        };
+
+        s.into_bytes ()
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Command::Read                           => write! (f, "take a reading"),
+            Command::CalibrateDry                   => write! (f, "calibrate with the probe dry"),
+            Command::Calibrate (n)                  => write! (f, "calibrate at {}", MicroSiemens (n)),
+            Command::CalibrateLow (n)               => write! (f, "calibrate low point at {}", MicroSiemens (n)),
+            Command::CalibrateHigh (n)              => write! (f, "calibrate high point at {}", MicroSiemens (n)),
+            Command::SetProbeK (k)                  => write! (f, "set probe to {}", k),
+            Command::ProbeKQuery                    => write! (f, "query probe K"),
+            Command::SetOutput (param, true)        => write! (f, "enable {} in readings", param),
+            Command::SetOutput (param, false)       => write! (f, "disable {} in readings", param),
+            Command::OutputQuery                    => write! (f, "query output parameters"),
+            Command::SetTdsFactor (factor)          => write! (f, "set TDS factor to {}", factor),
+            Command::TdsFactorQuery                 => write! (f, "query TDS factor"),
+            Command::SetCompensationTemperature (t) => write! (f, "set compensation temperature to {} °C", format_decimal (t)),
+            Command::ReadCompensated (t)            => write! (f, "take a reading compensated at {} °C", format_decimal (t)),
+            Command::CompensationTemperatureQuery   => write! (f, "query compensation temperature"),
+            Command::Common (ref common)            => common.fmt (f)
+        }
+    }
+}
+
+impl From<CommonCommand> for Command {
+    fn from (c: CommonCommand) -> Command {
+        Command::Common (c)
+    }
+}
+
+response_parse! (OutputParamsResponse, ProbeKResponse, TdsFactorResponse,
+                 CalibrationStateResponse, CompensationTemperatureResponse);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ec_response () {
+        let response = "1413,740,0.74,1.00\0".as_bytes ();
+        assert_eq! (EcResponse::parse (response, &OutputParams::all ()).unwrap (),
+                    EcResponse {
+                        conductivity: Some (MicroSiemens (1413.0)),
+                        total_dissolved_solids: Some (Tds (740.0)),
+                        salinity: Some (Salinity (0.74)),
+                        specific_gravity: Some (SpecificGravity (1.0))
+                    });
+
+        let params = OutputParams {
+            conductivity: true,
+            salinity: true,
+            ..OutputParams::default ()
+        };
+
+        let response = "1413,0.74\r".as_bytes ();
+        assert_eq! (EcResponse::parse (response, &params).unwrap (),
+                    EcResponse {
+                        conductivity: Some (MicroSiemens (1413.0)),
+                        salinity: Some (Salinity (0.74)),
+                        ..EcResponse::default ()
+                    });
+    }
+
+    #[test]
+    fn parsing_invalid_ec_response_yields_error () {
+        let response = "1413,740,0.74\0".as_bytes ();
+        assert! (EcResponse::parse (response, &OutputParams::all ()).is_err ());
+
+        let response = "1413,740,0.74,1.00,1\0".as_bytes ();
+        assert! (EcResponse::parse (response, &OutputParams::all ()).is_err ());
+
+        let response = "1413,x,0.74,1.00\0".as_bytes ();
+        assert! (EcResponse::parse (response, &OutputParams::all ()).is_err ());
+
+        let response = "1413\0".as_bytes ();
+        assert! (EcResponse::parse (response, &OutputParams::default ()).is_err ());
+    }
+
+    #[test]
+    fn parses_output_params_response () {
+        let response = "?O,EC,TDS,S,SG\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams::all ()));
+
+        let response = "?O,EC,S\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams {
+                        conductivity: true,
+                        salinity: true,
+                        ..OutputParams::default ()
+                    }));
+
+        let response = "?O,No output\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams::default ()));
+    }
+
+    #[test]
+    fn parses_output_params_response_regardless_of_case_leniently () {
+        let options = ParseOptions::lenient ();
+
+        let response = "?O,ec,Tds,s,sG\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse_with_options (response, &options).unwrap (),
+                    OutputParamsResponse (OutputParams::all ()));
+        assert! (OutputParamsResponse::parse (response).is_err ());
+
+        let response = "?O,EC,ec\0".as_bytes ();
+        assert! (OutputParamsResponse::parse_with_options (response, &options).is_err ());
+    }
+
+    #[test]
+    fn parsing_invalid_output_params_response_yields_error () {
+        let response = "?O,\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+
+        let response = "?O,EC,pH\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+
+        let response = "?O,EC,EC\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+
+        let response = "EC,TDS\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_probe_k_response () {
+        let response = "?K,1.0\0".as_bytes ();
+        assert_eq! (ProbeKResponse::parse (response).unwrap ().0.value (), 1.0);
+
+        let response = "?K,0.1\0".as_bytes ();
+        assert_eq! (ProbeKResponse::parse (response).unwrap ().0.value (), 0.1);
+
+        let response = "?K,10\r".as_bytes ();
+        assert_eq! (ProbeKResponse::parse (response).unwrap ().0.value (), 10.0);
+    }
+
+    #[test]
+    fn parsing_invalid_probe_k_response_yields_error () {
+        let response = "?K,\0".as_bytes ();
+        assert! (ProbeKResponse::parse (response).is_err ());
+
+        let response = "?K,0.05\0".as_bytes ();
+        assert! (ProbeKResponse::parse (response).is_err ());
+
+        let response = "?K,11\0".as_bytes ();
+        assert! (ProbeKResponse::parse (response).is_err ());
+
+        let response = "1.0\0".as_bytes ();
+        assert! (ProbeKResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_tds_factor_response () {
+        let response = "?TDS,0.54\0".as_bytes ();
+        assert_eq! (TdsFactorResponse::parse (response).unwrap ().0.value (), 0.54);
+
+        let response = "?TDS,1.00\r".as_bytes ();
+        assert_eq! (TdsFactorResponse::parse (response).unwrap ().0.value (), 1.0);
+    }
+
+    #[test]
+    fn parsing_invalid_tds_factor_response_yields_error () {
+        let response = "?TDS,\0".as_bytes ();
+        assert! (TdsFactorResponse::parse (response).is_err ());
+
+        let response = "?TDS,0.001\0".as_bytes ();
+        assert! (TdsFactorResponse::parse (response).is_err ());
+
+        let response = "?TDS,1.5\0".as_bytes ();
+        assert! (TdsFactorResponse::parse (response).is_err ());
+
+        let response = "?K,0.54\0".as_bytes ();
+        assert! (TdsFactorResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_calibration_state_response () {
+        let response = "?Cal,0\0".as_bytes ();
+        assert_eq! (CalibrationStateResponse::parse (response).unwrap (),
+                    CalibrationStateResponse (EcCalibrationState::Uncalibrated));
+
+        let response = "?Cal,1\0".as_bytes ();
+        assert_eq! (CalibrationStateResponse::parse (response).unwrap (),
+                    CalibrationStateResponse (EcCalibrationState::OnePoint));
+
+        let response = "?Cal,2\0".as_bytes ();
+        assert_eq! (CalibrationStateResponse::parse (response).unwrap (),
+                    CalibrationStateResponse (EcCalibrationState::TwoPoint));
+    }
+
+    #[test]
+    fn parsing_invalid_calibration_state_response_yields_error () {
+        let response = "?Cal,\0".as_bytes ();
+        assert! (CalibrationStateResponse::parse (response).is_err ());
+
+        let response = "?Cal,3\0".as_bytes ();
+        assert! (CalibrationStateResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn encodes_commands () {
+        let commands = [(Command::Read, "R"),
+                        (Command::CalibrateDry, "Cal,dry"),
+                        (Command::calibrate (84.0).unwrap (), "Cal,84.0"),
+                        (Command::calibrate_low (12880.0).unwrap (), "Cal,low,12880.0"),
+                        (Command::calibrate_high (80000.0).unwrap (), "Cal,high,80000.0"),
+                        (Command::set_probe_k (0.1).unwrap (), "K,0.1"),
+                        (Command::ProbeKQuery, "K,?"),
+                        (Command::SetOutput (OutputParameter::TotalDissolvedSolids, false), "O,TDS,0"),
+                        (Command::SetOutput (OutputParameter::SpecificGravity, true), "O,SG,1"),
+                        (Command::OutputQuery, "O,?"),
+                        (Command::set_tds_factor (0.54).unwrap (), "TDS,0.54"),
+                        (Command::TdsFactorQuery, "TDS,?"),
+                        (Command::set_compensation_temperature (25.0).unwrap (), "T,25.0"),
+                        (Command::read_compensated (25.0).unwrap (), "RT,25.0"),
+                        (Command::CompensationTemperatureQuery, "T,?"),
+                        (Command::Common (CommonCommand::CalibrationQuery), "Cal,?"),
+                        (CommonCommand::Find.into (), "Find")];
+
+        for &(ref command, encoded) in commands.iter () {
+            assert_eq! (command.encode (), encoded.as_bytes ());
+            assert_eq! (&Command::parse (encoded.as_bytes ()).unwrap (), command);
+        }
+
+        assert_eq! (Command::parse ("o,ec,0\0".as_bytes ()).unwrap (),
+                    Command::SetOutput (OutputParameter::Conductivity, false));
+    }
+
+    #[test]
+    fn invalid_commands_yield_error () {
+        assert! (Command::calibrate (0.0).is_err ());
+        assert! (Command::calibrate_low (-1.0).is_err ());
+        assert! (Command::calibrate_high (f64::INFINITY).is_err ());
+        assert! (Command::set_probe_k (20.0).is_err ());
+        assert! (Command::set_tds_factor (0.0).is_err ());
+        assert! (Command::set_compensation_temperature (-300.0).is_err ());
+        assert! (Command::read_compensated (-300.0).is_err ());
+
+        assert! (Command::parse ("Cal,low".as_bytes ()).is_err ());
+        assert! (Command::parse ("Cal,wet".as_bytes ()).is_err ());
+        assert! (Command::parse ("O,pH,1".as_bytes ()).is_err ());
+        assert! (Command::parse ("O,EC,2".as_bytes ()).is_err ());
+        assert! (Command::parse ("K,0.01".as_bytes ()).is_err ());
+        assert! (Command::parse ("Slope,?".as_bytes ()).is_err ());
+    }
+
+    #[test]
+    fn commands_have_processing_delay () {
+        assert_eq! (Command::Read.delay (), Duration::from_millis (600));
+        assert_eq! (Command::read_compensated (25.0).unwrap ().delay (), Duration::from_millis (600));
+        assert_eq! (Command::CalibrateDry.delay (), Duration::from_millis (600));
+        assert_eq! (Command::OutputQuery.delay (), Duration::from_millis (300));
+        assert_eq! (Command::Common (CommonCommand::Status).delay (), Duration::from_millis (300));
+    }
+
+    #[test]
+    fn parses_compensation_temperature_response () {
+        let response = "?T,25.0\0".as_bytes ();
+        assert_eq! (CompensationTemperatureResponse::parse (response).unwrap (),
+                    CompensationTemperatureResponse (Temperature::Celsius (25.0)));
+
+        let response = "?T,\0".as_bytes ();
+        assert! (CompensationTemperatureResponse::parse (response).is_err ());
+
+        let response = "?K,25.0\0".as_bytes ();
+        assert! (CompensationTemperatureResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn compares_compensation_temperature () {
+        let compensation = CompensationTemperatureResponse (Temperature::Celsius (25.0));
+
+        assert! (compensation.matches (&Temperature::Celsius (25.05), 0.1));
+        assert! (compensation.matches (&Temperature::Kelvin (298.15), 0.1));
+        assert! (compensation.matches (&Temperature::Fahrenheit (77.0), 0.1));
+
+        assert! (!compensation.matches (&Temperature::Celsius (26.0), 0.5));
+        assert! (!compensation.matches (&Temperature::Fahrenheit (25.0), 0.5));
+    }
+
+    #[test]
+    fn converts_conductivity () {
+        let factor = TdsFactor::new (0.5).unwrap ();
+        assert_eq! (tds_from_conductivity (MicroSiemens (1413.0), factor), Tds (706.5));
+
+        let salinity = salinity_from_conductivity (MicroSiemens (42914.0), &Temperature::Celsius (15.0));
+        assert! ((salinity.0 - 35.0).abs () < 1e-3);
+
+        // Standard seawater at 25 °C is about 53.09 mS/cm
+        let salinity = salinity_from_conductivity (MicroSiemens::from_millisiemens (53.087), &Temperature::Fahrenheit (77.0));
+        assert! ((salinity.0 - 35.0).abs () < 0.05);
+
+        assert_eq! (specific_gravity_from_salinity (Salinity (0.0), &Temperature::Celsius (20.0)), SpecificGravity (1.0));
+
+        let sg = specific_gravity_from_conductivity (MicroSiemens (42914.0), &Temperature::Celsius (15.0));
+        assert! ((sg.0 - 1.0265).abs () < 1e-3);
+    }
+
+    #[test]
+    fn displays_conductivity_units () {
+        assert_eq! (MicroSiemens (1413.0).to_string (), "1413 μS/cm");
+        assert_eq! (MicroSiemens (12880.0).to_millisiemens (), 12.88);
+        assert_eq! (Tds (740.0).to_string (), "740 ppm");
+        assert_eq! (Salinity (0.74).to_string (), "0.74 PSU");
+        assert_eq! (SpecificGravity (1.0).to_string (), "1");
+    }
+
+    #[test]
+    fn displays_responses () {
+        let response = EcResponse::parse ("1413,740,0.74,1.00\0".as_bytes (), &OutputParams::all ()).unwrap ();
+        assert_eq! (response.to_string (),
+                    "conductivity 1413 μS/cm, TDS 740 ppm, salinity 0.74 PSU, specific gravity 1");
+        assert_eq! (EcResponse::default ().to_string (), "empty reading");
+
+        let params = OutputParams {
+            conductivity: true,
+            salinity: true,
+            ..OutputParams::default ()
+        };
+
+        assert_eq! (OutputParamsResponse (params).to_string (), "output parameters are EC, S");
+        assert_eq! (OutputParamsResponse (OutputParams::default ()).to_string (), "output parameters are no output");
+        assert_eq! (ProbeKResponse::parse ("?K,1.0\0".as_bytes ()).unwrap ().to_string (), "probe is K 1");
+        assert_eq! (TdsFactorResponse::parse ("?TDS,0.54\0".as_bytes ()).unwrap ().to_string (), "TDS factor is 0.54");
+        assert_eq! (CalibrationStateResponse (EcCalibrationState::OnePoint).to_string (),
+                    "device is calibrated at one point");
+    }
+
+    #[test]
+    fn displays_commands () {
+        assert_eq! (Command::CalibrateDry.to_string (), "calibrate with the probe dry");
+        assert_eq! (Command::calibrate_low (12880.0).unwrap ().to_string (), "calibrate low point at 12880 μS/cm");
+        assert_eq! (Command::SetOutput (OutputParameter::TotalDissolvedSolids, false).to_string (),
+                    "disable total dissolved solids in readings");
+        assert_eq! (Command::set_tds_factor (0.5).unwrap ().to_string (), "set TDS factor to 0.5");
+        assert_eq! (Command::Common (CommonCommand::Find).to_string (), "blink LED to find device");
+    }
+}
@@ -1,14 +1,89 @@
+// error_chain 0.10 does not let us put attributes on the ErrorKind it
+// generates, so unlike the other public enums it cannot be #[non_exhaustive].
+// The hidden __Nonexhaustive variant stands in for the attribute: matches on
+// ErrorKind outside the crate need a wildcard arm, and adding a kind is not a
+// breaking change.
+#![allow(clippy::manual_non_exhaustive)]
+
 error_chain! {
     errors {
-        // The response is not nul-terminated, or it is not valid ASCII/UTF-8
+        // The response is not nul- or CR-terminated, or it is not valid ASCII/UTF-8
         MalformedResponse {
             description ("malformed response")
-            display ("response is not a valid nul-terminated UTF-8 string")
+            display ("response is not a valid nul- or CR-terminated UTF-8 string")
         }
 
         // The response could not be parsed
         ResponseParse {
             description ("could not parse response")
         }
+
+        // The RTD reported its sentinel value for a disconnected or shorted probe
+        ProbeFault {
+            description ("probe fault")
+            display ("the temperature probe is disconnected or shorted")
+        }
+
+        // The device replied with an I2C response code of 2
+        DeviceSyntaxError {
+            description ("the device did not understand the command")
+        }
+
+        // The device replied with an I2C response code of 254
+        StillProcessing {
+            description ("the device is still processing the command")
+        }
+
+        // The device replied with an I2C response code of 255
+        NoData {
+            description ("the device has no data to send")
+        }
+
+        // The first byte of an I2C response is not a known response code
+        UnknownResponseCode (code: u8) {
+            description ("unknown I2C response code")
+            display ("unknown I2C response code {}", code)
+        }
+
+        // A command string could not be parsed
+        CommandParse {
+            description ("could not parse command")
+        }
+
+        // A value was constructed outside of its valid range
+        ValueOutOfRange (reason: &'static str) {
+            description ("value out of range")
+            display ("value out of range: {}", reason)
+        }
+
+        // A command was constructed with a parameter outside its valid range
+        InvalidCommandParameter (reason: &'static str) {
+            description ("invalid command parameter")
+            display ("invalid command parameter: {}", reason)
+        }
+
+        // The device rejected a piece of calibration data while importing it
+        ImportRejected {
+            description ("the device rejected the calibration data")
+        }
+
+        // A calibration export was used before it was complete, or got data after it was done
+        ExportSequence {
+            description ("calibration export out of sequence")
+            display ("calibration export is incomplete or received data after *DONE")
+        }
+
+        // A calibration session got a response after it was done
+        CalibrationSequence {
+            description ("calibration out of sequence")
+            display ("calibration session received a response after it was done")
+        }
+
+        // Stand-in for #[non_exhaustive]; it is never constructed, and it
+        // should not be matched on
+        #[doc(hidden)]
+        __Nonexhaustive {
+            description ("this variant is not part of the public API")
+        }
     }
 }
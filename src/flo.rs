@@ -0,0 +1,800 @@
+//! Responses and commands for the EZO FLO (flow meter) circuit
+
+use std::fmt;
+use std::time::Duration;
+
+use errors::*;
+use framing::{format_decimal, parse_flag, parse_number, write_output_params, write_reading, SplitCommand};
+use {is_acknowledgment, parse_field, parse_output_params, str_from_response, CommonCommand, ParseOptions};
+
+/// A volume as the circuit reports it: liters multiplied by the
+/// circuit's conversion factor, which is 1 unless set with "CF,n"
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Volume (pub f64);
+
+impl Volume {
+    /// Converts a volume reported with the given conversion factor, as
+    /// returned by "CF,?", back into liters.
+    pub fn to_liters (&self, factor: f64) -> f64 {
+        self.0 / factor
+    }
+}
+
+impl fmt::Display for Volume {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{} volume units", self.0)
+    }
+}
+
+/// A flow rate in liters per unit of the circuit's time base, which is
+/// minutes by default
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct FlowRate (pub f64);
+
+impl FlowRate {
+    /// Converts a flow rate reported with the given time base into liters
+    /// per minute.
+    pub fn to_liters_per_minute (&self, time_base: TimeBase) -> f64 {
+        match time_base {
+            TimeBase::Second => self.0 * 60.0,
+            TimeBase::Minute => self.0,
+            TimeBase::Hour   => self.0 / 60.0
+        }
+    }
+}
+
+impl fmt::Display for FlowRate {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{} L per time unit", self.0)
+    }
+}
+
+/// Which parameters the FLO circuit includes in its readings.  The circuit
+/// always reports the enabled parameters in the order of the fields here.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct OutputParams {
+    /// "TV", total volume
+    pub total_volume: bool,
+    /// "FR", flow rate
+    pub flow_rate: bool
+}
+
+impl OutputParams {
+    /// All of the parameters enabled.
+    pub fn all () -> OutputParams {
+        OutputParams {
+            total_volume: true,
+            flow_rate: true
+        }
+    }
+}
+
+impl fmt::Display for OutputParams {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_output_params (f, &[("TV", self.total_volume),
+                                  ("FR", self.flow_rate)])
+    }
+}
+
+/// Response from the "R" command to take a reading.  Only the parameters
+/// which are enabled in the circuit's output configuration are present.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct FloResponse {
+    /// Volume accumulated since the totalizer was last cleared, scaled by
+    /// the circuit's conversion factor
+    pub total_volume: Option<Volume>,
+    pub flow_rate: Option<FlowRate>
+}
+
+impl FloResponse {
+    /// Parses the result of the "R" command to take a reading, given the
+    /// parameters which are enabled in the circuit, as reported by "O,?".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse (response: &[u8], params: &OutputParams) -> Result<FloResponse> {
+        FloResponse::parse_with_options (response, params, &ParseOptions::default ())
+    }
+
+    /// Like `parse()`, with the leniencies in `options`.
+    pub fn parse_with_options (response: &[u8], params: &OutputParams, options: &ParseOptions)
+                               -> Result<FloResponse> {
+        let r = options.body (response)?;
+        let mut split = r.split (',');
+
+        let mut field = |enabled| -> Result<Option<f64>> {
+            if enabled {
+                Ok (Some (parse_field (split.next ())?))
+            } else {
+                Ok (None)
+            }
+        };
+
+        let total_volume = field (params.total_volume)?.map (Volume);
+        let flow_rate = field (params.flow_rate)?.map (FlowRate);
+
+        if split.next ().is_some () {
+            return Err (ErrorKind::ResponseParse.into ());
+        }
+
+        Ok (FloResponse {
+            total_volume,
+            flow_rate
+        })
+    }
+}
+
+impl fmt::Display for FloResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_reading (f, &[("total volume", self.total_volume.as_ref ().map (|v| v as _)),
+                            ("flow rate", self.flow_rate.as_ref ().map (|v| v as _))])
+    }
+}
+
+/// Response from the "O,?" command to query which parameters are enabled
+/// in readings
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct OutputParamsResponse (pub OutputParams);
+
+impl OutputParamsResponse {
+    /// Parses the result of the "O,?" command, like "?O,TV,FR".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<OutputParamsResponse> {
+        let enabled = parse_output_params (response, &["TV", "FR"], options)?;
+
+        Ok (OutputParamsResponse (OutputParams {
+            total_volume: enabled[0],
+            flow_rate: enabled[1]
+        }))
+    }
+}
+
+impl fmt::Display for OutputParamsResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "output parameters are {}", self.0)
+    }
+}
+
+/// Flow meter model whose calibration profile the circuit uses
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FlowMeter {
+    /// "1/4", Atlas Scientific's 1/4" flow meter
+    QuarterInch,
+    /// "1/2", Atlas Scientific's 1/2" flow meter
+    HalfInch,
+    /// "3/4", Atlas Scientific's 3/4" flow meter
+    ThreeQuarterInch,
+    /// "K", a meter with a custom K factor
+    Custom
+}
+
+impl FlowMeter {
+    fn code (&self) -> &'static str {
+        match *self {
+            FlowMeter::QuarterInch      => "1/4",
+            FlowMeter::HalfInch         => "1/2",
+            FlowMeter::ThreeQuarterInch => "3/4",
+            FlowMeter::Custom           => "K"
+        }
+    }
+
+    fn from_code (code: &str, options: &ParseOptions) -> Option<FlowMeter> {
+        match code {
+            c if options.matches (c, "1/4") => Some (FlowMeter::QuarterInch),
+            c if options.matches (c, "1/2") => Some (FlowMeter::HalfInch),
+            c if options.matches (c, "3/4") => Some (FlowMeter::ThreeQuarterInch),
+            c if options.matches (c, "K")   => Some (FlowMeter::Custom),
+            _                               => None
+        }
+    }
+}
+
+impl fmt::Display for FlowMeter {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FlowMeter::Custom => write! (f, "custom flow meter"),
+            meter             => write! (f, "{}\" flow meter", meter.code ())
+        }
+    }
+}
+
+/// Response from the "Set,?" command to query which flow meter the circuit
+/// is configured for
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FlowMeterResponse (pub FlowMeter);
+
+impl FlowMeterResponse {
+    /// Parses the result of the "Set,?" command, like "?Set,1/2".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<FlowMeterResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?Set,") {
+            match FlowMeter::from_code (rest, options) {
+                Some (meter) => Ok (FlowMeterResponse (meter)),
+                None         => Err (ErrorKind::ResponseParse.into ())
+            }
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for FlowMeterResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "circuit is set for a {}", self.0)
+    }
+}
+
+/// Response from the "CF,?" command to query the factor by which the
+/// circuit multiplies volumes, to report them in units other than liters
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ConversionFactorResponse (pub f64);
+
+impl ConversionFactorResponse {
+    /// Parses the result of the "CF,?" command, like "?CF,0.264".  The
+    /// factor must be positive.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<ConversionFactorResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?CF,") {
+            let factor: f64 = parse_field (Some (rest))?;
+
+            if factor > 0.0 && factor.is_finite () {
+                Ok (ConversionFactorResponse (factor))
+            } else {
+                Err (ErrorKind::ResponseParse.into ())
+            }
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for ConversionFactorResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "volume conversion factor is {}", self.0)
+    }
+}
+
+/// A parameter which the FLO circuit can include in its readings
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OutputParameter {
+    /// "TV", total volume
+    TotalVolume,
+    /// "FR", flow rate
+    FlowRate
+}
+
+impl OutputParameter {
+    fn code (&self) -> &'static str {
+        match *self {
+            OutputParameter::TotalVolume => "TV",
+            OutputParameter::FlowRate    => "FR"
+        }
+    }
+
+    fn from_code (code: &str, options: &ParseOptions) -> Option<OutputParameter> {
+        match code {
+            c if options.matches (c, "TV") => Some (OutputParameter::TotalVolume),
+            c if options.matches (c, "FR") => Some (OutputParameter::FlowRate),
+            _                              => None
+        }
+    }
+}
+
+impl fmt::Display for OutputParameter {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            OutputParameter::TotalVolume => "total volume",
+            OutputParameter::FlowRate    => "flow rate"
+        };
+
+        write! (f, "{}", name)
+    }
+}
+
+/// Unit of time for the flow rate in readings
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TimeBase {
+    /// "s", liters per second
+    Second,
+    /// "m", liters per minute
+    Minute,
+    /// "h", liters per hour
+    Hour
+}
+
+impl TimeBase {
+    fn code (&self) -> &'static str {
+        match *self {
+            TimeBase::Second => "s",
+            TimeBase::Minute => "m",
+            TimeBase::Hour   => "h"
+        }
+    }
+
+    fn from_code (code: &str, options: &ParseOptions) -> Option<TimeBase> {
+        match code {
+            c if options.matches (c, "s") => Some (TimeBase::Second),
+            c if options.matches (c, "m") => Some (TimeBase::Minute),
+            c if options.matches (c, "h") => Some (TimeBase::Hour),
+            _                             => None
+        }
+    }
+}
+
+impl fmt::Display for TimeBase {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            TimeBase::Second => "second",
+            TimeBase::Minute => "minute",
+            TimeBase::Hour   => "hour"
+        };
+
+        write! (f, "{}", name)
+    }
+}
+
+/// Response from the "Frp,?" command to query the time base of the flow rate
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimeBaseResponse (pub TimeBase);
+
+impl TimeBaseResponse {
+    /// Parses the result of the "Frp,?" command, like "?Frp,m".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<TimeBaseResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?Frp,") {
+            match TimeBase::from_code (rest, options) {
+                Some (time_base) => Ok (TimeBaseResponse (time_base)),
+                None             => Err (ErrorKind::ResponseParse.into ())
+            }
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for TimeBaseResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "flow rate is in liters per {}", self.0)
+    }
+}
+
+/// Commands which can be sent to the EZO FLO circuit
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Command {
+    /// "R", take a reading
+    Read,
+    /// "Clear", reset the totalizer to zero
+    ClearTotalVolume,
+    /// "CF,n", set the volume conversion factor
+    SetConversionFactor (f64),
+    /// "CF,?", query the volume conversion factor
+    ConversionFactorQuery,
+    /// "Set,type", select the calibration profile of a flow meter model
+    SetFlowMeter (FlowMeter),
+    /// "Set,?", query the flow meter model
+    FlowMeterQuery,
+    /// "O,param,n", enable or disable a parameter in readings
+    SetOutput (OutputParameter, bool),
+    /// "O,?", query which parameters are enabled in readings
+    OutputQuery,
+    /// "Frp,unit", set the time base of the flow rate
+    SetTimeBase (TimeBase),
+    /// "Frp,?", query the time base of the flow rate
+    TimeBaseQuery,
+    /// A command which is common to all the EZO circuits
+    Common (CommonCommand)
+}
+
+impl Command {
+    /// Creates a command to set the volume conversion factor, which must
+    /// be positive.
+    pub fn set_conversion_factor (factor: f64) -> Result<Command> {
+        if factor > 0.0 && factor.is_finite () {
+            Ok (Command::SetConversionFactor (factor))
+        } else {
+            Err (ErrorKind::InvalidCommandParameter ("conversion factor must be positive").into ())
+        }
+    }
+
+    /// Creates a command to select a flow meter model.  `FlowMeter::Custom`
+    /// cannot be selected this way; it results from setting a custom K factor.
+    pub fn set_flow_meter (meter: FlowMeter) -> Result<Command> {
+        if meter == FlowMeter::Custom {
+            Err (ErrorKind::InvalidCommandParameter ("a custom flow meter cannot be selected with \"Set\"").into ())
+        } else {
+            Ok (Command::SetFlowMeter (meter))
+        }
+    }
+
+    /// Parses a command string as it would be sent to the device, like
+    /// "Clear" or "O,TV,0", in the same way as `saiba_parser::Command::parse()`.
+    pub fn parse (command: &[u8]) -> Result<Command> {
+        let split = SplitCommand::new (command)?;
+
+        if let Some (common) = CommonCommand::from_split (&split)? {
+            return Ok (Command::Common (common));
+        }
+
+        let mut args = split.keyword.as_ref ().map (|k| k.splitn (2, ','));
+        let first = args.as_mut ().and_then (|a| a.next ());
+        let second = args.as_mut ().and_then (|a| a.next ());
+
+        let command = match (split.name.as_str (), first, second) {
+            ("r", None, None)                   => Command::Read,
+            ("clear", None, None)               => Command::ClearTotalVolume,
+            ("cf", Some ("?"), None)            => Command::ConversionFactorQuery,
+            ("cf", Some (n), None)              => Command::set_conversion_factor (parse_number (n)?)?,
+            ("set", Some ("?"), None)           => Command::FlowMeterQuery,
+
+            ("set", Some (m), None) => {
+                let meter = FlowMeter::from_code (m, &split.options ()).ok_or (ErrorKind::CommandParse)?;
+                Command::set_flow_meter (meter)?
+            },
+
+            ("o", Some ("?"), None)             => Command::OutputQuery,
+
+            ("o", Some (p), Some (n)) => {
+                let param = OutputParameter::from_code (p, &split.options ()).ok_or (ErrorKind::CommandParse)?;
+                Command::SetOutput (param, parse_flag (n)?)
+            },
+
+            ("frp", Some ("?"), None)           => Command::TimeBaseQuery,
+            ("frp", Some (t), None)             => Command::SetTimeBase (TimeBase::from_code (t, &split.options ()).ok_or (ErrorKind::CommandParse)?),
+            _                                   => return Err (ErrorKind::CommandParse.into ())
+        };
+
+        Ok (command)
+    }
+
+    /// Returns how long to wait after sending this command before reading
+    /// the device's response over I2C.
+    pub fn delay (&self) -> Duration {
+        match *self {
+            Command::Common (ref common) => common.delay (),
+            _                            => Duration::from_millis (300)
+        }
+    }
+
+    /// Returns the bytes to send to the device for this command.  In I2C
+    /// mode these are sent as-is; in UART mode, the caller must append a
+    /// carriage return.
+    pub fn encode (&self) -> Vec<u8> {
+        let s = match *self {
+            Command::Read                               => "R".to_string (),
+            Command::ClearTotalVolume                   => "Clear".to_string (),
+            Command::SetConversionFactor (factor)       => format! ("CF,{}", format_decimal (factor)),
+            Command::ConversionFactorQuery              => "CF,?".to_string (),
+            Command::SetFlowMeter (meter)               => format! ("Set,{}", meter.code ()),
+            Command::FlowMeterQuery                     => "Set,?".to_string (),
+            Command::SetOutput (param, enabled)         => format! ("O,{},{}", param.code (), if enabled { 1 } else { 0 }),
+            Command::OutputQuery                        => "O,?".to_string (),
+            Command::SetTimeBase (time_base)            => format! ("Frp,{}", time_base.code ()),
+            Command::TimeBaseQuery                      => "Frp,?".to_string (),
+            Command::Common (ref common)                => return common.encode ()
+        };
+
+        s.into_bytes ()
+    }
+
+    /// Parses the device's acknowledgment of a command which changes its
+    /// configuration, like "Clear" or "CF,n".  It is an error to call this
+    /// for "R" or for queries, which respond with data.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_acknowledgment (&self, response: &[u8]) -> Result<()> {
+        match *self {
+            Command::Read
+                | Command::ConversionFactorQuery
+                | Command::FlowMeterQuery
+                | Command::OutputQuery
+                | Command::TimeBaseQuery => return Err (ErrorKind::ResponseParse.into ()),
+
+            Command::Common (ref common) if common.responds_with_data () =>
+                return Err (ErrorKind::ResponseParse.into ()),
+
+            _ => ()
+        }
+
+        if is_acknowledgment (str_from_response (response)?, &ParseOptions::default ()) {
+            Ok (())
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Command::Read                         => write! (f, "take a reading"),
+            Command::ClearTotalVolume             => write! (f, "clear total volume"),
+            Command::SetConversionFactor (factor) => write! (f, "set volume conversion factor to {}", format_decimal (factor)),
+            Command::ConversionFactorQuery        => write! (f, "query volume conversion factor"),
+            Command::SetFlowMeter (meter)         => write! (f, "set circuit for a {}", meter),
+            Command::FlowMeterQuery               => write! (f, "query flow meter"),
+            Command::SetOutput (param, true)      => write! (f, "enable {} in readings", param),
+            Command::SetOutput (param, false)     => write! (f, "disable {} in readings", param),
+            Command::OutputQuery                  => write! (f, "query output parameters"),
+            Command::SetTimeBase (time_base)      => write! (f, "set flow rate to liters per {}", time_base),
+            Command::TimeBaseQuery                => write! (f, "query flow rate time base"),
+            Command::Common (ref common)          => common.fmt (f)
+        }
+    }
+}
+
+impl From<CommonCommand> for Command {
+    fn from (c: CommonCommand) -> Command {
+        Command::Common (c)
+    }
+}
+
+response_parse! (OutputParamsResponse, FlowMeterResponse, ConversionFactorResponse,
+                 TimeBaseResponse);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use LedState;
+
+    #[test]
+    fn parses_flo_response () {
+        let response = "125.5,3.25\0".as_bytes ();
+        assert_eq! (FloResponse::parse (response, &OutputParams::all ()).unwrap (),
+                    FloResponse {
+                        total_volume: Some (Volume (125.5)),
+                        flow_rate: Some (FlowRate (3.25))
+                    });
+
+        let params = OutputParams {
+            flow_rate: true,
+            ..OutputParams::default ()
+        };
+
+        let response = "3.25\r".as_bytes ();
+        assert_eq! (FloResponse::parse (response, &params).unwrap (),
+                    FloResponse {
+                        total_volume: None,
+                        flow_rate: Some (FlowRate (3.25))
+                    });
+    }
+
+    #[test]
+    fn parsing_invalid_flo_response_yields_error () {
+        let response = "125.5\0".as_bytes ();
+        assert! (FloResponse::parse (response, &OutputParams::all ()).is_err ());
+
+        let response = "125.5,3.25,1\0".as_bytes ();
+        assert! (FloResponse::parse (response, &OutputParams::all ()).is_err ());
+
+        let response = "125.5,fast\0".as_bytes ();
+        assert! (FloResponse::parse (response, &OutputParams::all ()).is_err ());
+    }
+
+    #[test]
+    fn parses_output_params_response () {
+        let response = "?O,TV,FR\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams::all ()));
+
+        let response = "?O,FR\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams {
+                        total_volume: false,
+                        flow_rate: true
+                    }));
+
+        let response = "?O,No output\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams::default ()));
+    }
+
+    #[test]
+    fn parsing_invalid_output_params_response_yields_error () {
+        let response = "?O,\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+
+        let response = "?O,TV,TV\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+
+        let response = "?O,TV,HUM\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+
+        let response = "TV,FR\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_flow_meter_response () {
+        let meters = [("?Set,1/4\0", FlowMeter::QuarterInch),
+                      ("?Set,1/2\0", FlowMeter::HalfInch),
+                      ("?Set,3/4\r", FlowMeter::ThreeQuarterInch),
+                      ("?Set,K\0", FlowMeter::Custom)];
+
+        for &(response, meter) in meters.iter () {
+            assert_eq! (FlowMeterResponse::parse (response.as_bytes ()).unwrap (),
+                        FlowMeterResponse (meter));
+        }
+    }
+
+    #[test]
+    fn parsing_invalid_flow_meter_response_yields_error () {
+        let response = "?Set,\0".as_bytes ();
+        assert! (FlowMeterResponse::parse (response).is_err ());
+
+        let response = "?Set,1\0".as_bytes ();
+        assert! (FlowMeterResponse::parse (response).is_err ());
+
+        let response = "1/2\0".as_bytes ();
+        assert! (FlowMeterResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_conversion_factor_response () {
+        let response = "?CF,1\0".as_bytes ();
+        assert_eq! (ConversionFactorResponse::parse (response).unwrap (), ConversionFactorResponse (1.0));
+
+        let response = "?CF,0.264\r".as_bytes ();
+        assert_eq! (ConversionFactorResponse::parse (response).unwrap (), ConversionFactorResponse (0.264));
+
+        let response = "?CF,\0".as_bytes ();
+        assert! (ConversionFactorResponse::parse (response).is_err ());
+
+        let response = "?CF,0\0".as_bytes ();
+        assert! (ConversionFactorResponse::parse (response).is_err ());
+
+        let response = "?CF,-1\0".as_bytes ();
+        assert! (ConversionFactorResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_time_base_response () {
+        let time_bases = [("?Frp,s\0", TimeBase::Second),
+                          ("?Frp,m\0", TimeBase::Minute),
+                          ("?Frp,h\r", TimeBase::Hour)];
+
+        for &(response, time_base) in time_bases.iter () {
+            assert_eq! (TimeBaseResponse::parse (response.as_bytes ()).unwrap (),
+                        TimeBaseResponse (time_base));
+        }
+    }
+
+    #[test]
+    fn parsing_invalid_time_base_response_yields_error () {
+        let response = "?Frp,d\0".as_bytes ();
+        assert! (TimeBaseResponse::parse (response).is_err ());
+
+        let response = "?Set,m\0".as_bytes ();
+        assert! (TimeBaseResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_codes_in_any_case_only_leniently () {
+        let options = ParseOptions::lenient ();
+
+        let response = "?Set,k\0".as_bytes ();
+        assert_eq! (FlowMeterResponse::parse_with_options (response, &options).unwrap (),
+                    FlowMeterResponse (FlowMeter::Custom));
+        assert! (FlowMeterResponse::parse (response).is_err ());
+
+        let response = "?Frp,M\0".as_bytes ();
+        assert_eq! (TimeBaseResponse::parse_with_options (response, &options).unwrap (),
+                    TimeBaseResponse (TimeBase::Minute));
+        assert! (TimeBaseResponse::parse (response).is_err ());
+
+        assert_eq! (Command::parse ("Frp,M\0".as_bytes ()).unwrap (),
+                    Command::SetTimeBase (TimeBase::Minute));
+    }
+
+    #[test]
+    fn converts_flow_rate_to_liters_per_minute () {
+        assert_eq! (FlowRate (0.5).to_liters_per_minute (TimeBase::Second), 30.0);
+        assert_eq! (FlowRate (2.5).to_liters_per_minute (TimeBase::Minute), 2.5);
+        assert_eq! (FlowRate (90.0).to_liters_per_minute (TimeBase::Hour), 1.5);
+    }
+
+    #[test]
+    fn converts_volume_to_liters () {
+        assert_eq! (Volume (125.5).to_liters (1.0), 125.5);
+        assert_eq! (Volume (50.0).to_liters (0.25), 200.0);
+    }
+
+    #[test]
+    fn encodes_commands () {
+        let commands = [(Command::Read, "R"),
+                        (Command::ClearTotalVolume, "Clear"),
+                        (Command::set_conversion_factor (0.264).unwrap (), "CF,0.264"),
+                        (Command::ConversionFactorQuery, "CF,?"),
+                        (Command::set_flow_meter (FlowMeter::HalfInch).unwrap (), "Set,1/2"),
+                        (Command::FlowMeterQuery, "Set,?"),
+                        (Command::SetOutput (OutputParameter::TotalVolume, false), "O,TV,0"),
+                        (Command::SetOutput (OutputParameter::FlowRate, true), "O,FR,1"),
+                        (Command::OutputQuery, "O,?"),
+                        (Command::SetTimeBase (TimeBase::Hour), "Frp,h"),
+                        (Command::TimeBaseQuery, "Frp,?"),
+                        (CommonCommand::Status.into (), "Status")];
+
+        for &(ref command, encoded) in commands.iter () {
+            assert_eq! (command.encode (), encoded.as_bytes ());
+            assert_eq! (&Command::parse (encoded.as_bytes ()).unwrap (), command);
+        }
+    }
+
+    #[test]
+    fn invalid_commands_yield_error () {
+        assert! (Command::set_conversion_factor (0.0).is_err ());
+        assert! (Command::set_flow_meter (FlowMeter::Custom).is_err ());
+
+        assert! (Command::parse ("Set,K".as_bytes ()).is_err ());
+        assert! (Command::parse ("Set,1".as_bytes ()).is_err ());
+        assert! (Command::parse ("Frp,d".as_bytes ()).is_err ());
+        assert! (Command::parse ("O,TDS,1".as_bytes ()).is_err ());
+        assert! (Command::parse ("Clear,1".as_bytes ()).is_err ());
+    }
+
+    #[test]
+    fn parses_acknowledgments () {
+        assert! (Command::ClearTotalVolume.parse_acknowledgment ("\0".as_bytes ()).is_ok ());
+        assert! (Command::SetTimeBase (TimeBase::Second).parse_acknowledgment ("*OK\r".as_bytes ()).is_ok ());
+
+        assert! (Command::ClearTotalVolume.parse_acknowledgment ("125.5\0".as_bytes ()).is_err ());
+        assert! (Command::Read.parse_acknowledgment ("\0".as_bytes ()).is_err ());
+        assert! (Command::OutputQuery.parse_acknowledgment ("\0".as_bytes ()).is_err ());
+    }
+
+    #[test]
+    fn common_queries_are_not_acknowledgments () {
+        assert! (Command::Common (CommonCommand::Find).parse_acknowledgment ("\0".as_bytes ()).is_ok ());
+        assert! (Command::Common (CommonCommand::SetLed (LedState::Off))
+                 .parse_acknowledgment ("*OK\r".as_bytes ()).is_ok ());
+
+        let queries = [CommonCommand::Status, CommonCommand::DeviceInfo, CommonCommand::NameQuery,
+                       CommonCommand::CalibrationQuery, CommonCommand::Export];
+
+        for query in queries.iter () {
+            assert! (Command::Common (query.clone ()).parse_acknowledgment ("\0".as_bytes ()).is_err ());
+        }
+    }
+
+    #[test]
+    fn displays_responses () {
+        let response = FloResponse::parse ("125.5,2.25\0".as_bytes (), &OutputParams::all ()).unwrap ();
+        assert_eq! (response.to_string (), "total volume 125.5 volume units, flow rate 2.25 L per time unit");
+        assert_eq! (OutputParamsResponse (OutputParams::all ()).to_string (), "output parameters are TV, FR");
+        assert_eq! (FlowMeterResponse::parse ("?Set,1/2\0".as_bytes ()).unwrap ().to_string (),
+                    "circuit is set for a 1/2\" flow meter");
+        assert_eq! (FlowMeterResponse (FlowMeter::Custom).to_string (), "circuit is set for a custom flow meter");
+        assert_eq! (ConversionFactorResponse::parse ("?CF,0.264\0".as_bytes ()).unwrap ().to_string (),
+                    "volume conversion factor is 0.264");
+        assert_eq! (TimeBaseResponse::parse ("?Frp,h\0".as_bytes ()).unwrap ().to_string (),
+                    "flow rate is in liters per hour");
+    }
+
+    #[test]
+    fn displays_commands () {
+        assert_eq! (Command::ClearTotalVolume.to_string (), "clear total volume");
+        assert_eq! (Command::SetFlowMeter (FlowMeter::ThreeQuarterInch).to_string (),
+                    "set circuit for a 3/4\" flow meter");
+        assert_eq! (Command::SetOutput (OutputParameter::FlowRate, false).to_string (), "disable flow rate in readings");
+        assert_eq! (Command::SetTimeBase (TimeBase::Second).to_string (), "set flow rate to liters per second");
+        assert_eq! (Command::Common (CommonCommand::Find).to_string (), "blink LED to find device");
+    }
+}
@@ -0,0 +1,616 @@
+//! Framing which is shared by the responses and commands of all the EZO
+//! circuits: terminators, fields, status tokens and I2C response codes
+
+use std::fmt;
+use std::str;
+#[cfg(feature = "common")]
+use std::str::FromStr;
+
+use errors::*;
+
+/// A response type which can be parsed from the device's response alone.
+///
+/// All the response types implement this, except for the readings whose
+/// format depends on the device's configuration, like `TemperatureResponse`
+/// which needs to know the temperature scale, or the readings of the
+/// multi-parameter circuits which need to know their `OutputParams`.
+/// Those have `parse()` and `parse_with_options()` functions with extra
+/// arguments instead.
+///
+/// The types which implement this also implement `TryFrom<&[u8]>` in the
+/// same way, and `FromStr` for a response without its terminating nul or
+/// carriage return, like "?L,1".
+pub trait ResponseParse: Sized {
+    /// Parses the device's response strictly, as the datasheets describe it.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    fn parse (response: &[u8]) -> Result<Self> {
+        Self::parse_with_options (response, &ParseOptions::default ())
+    }
+
+    /// Parses the device's response with the leniencies in `options`.
+    fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<Self>;
+}
+
+// Implements a strict parse(), ResponseParse, TryFrom<&[u8]> and FromStr for
+// response types in terms of their own parse_with_options()
+macro_rules! response_parse {
+    ($($name:ident),*) => {
+        $(
+            impl $name {
+                /// Parses the device's response strictly; this is
+                /// `parse_with_options()` with the default options.
+                pub fn parse (response: &[u8]) -> ::errors::Result<$name> {
+                    $name::parse_with_options (response, &::ParseOptions::default ())
+                }
+            }
+
+            impl ::ResponseParse for $name {
+                fn parse_with_options (response: &[u8], options: &::ParseOptions) -> ::errors::Result<$name> {
+                    $name::parse_with_options (response, options)
+                }
+            }
+
+            impl<'a> ::std::convert::TryFrom<&'a [u8]> for $name {
+                type Error = ::errors::Error;
+
+                fn try_from (response: &'a [u8]) -> ::errors::Result<$name> {
+                    $name::parse (response)
+                }
+            }
+
+            impl ::std::str::FromStr for $name {
+                type Err = ::errors::Error;
+
+                fn from_str (s: &str) -> ::errors::Result<$name> {
+                    $name::parse (&::framing::terminate (s))
+                }
+            }
+        )*
+    }
+}
+
+// Response strings as obtained from FromStr don't have the terminator
+// which parse() expects, so this adds a nul.
+pub(crate) fn terminate (s: &str) -> Vec<u8> {
+    let mut response = Vec::with_capacity (s.len () + 1);
+    response.extend_from_slice (s.as_bytes ());
+    response.push (b'\0');
+    response
+}
+
+// Takes in a slice of bytes, and validates that they are terminated with a nul
+// (I2C mode) or a carriage return (UART mode), and that they are valid UTF-8/ASCII
+#[cfg(any(feature = "flo", feature = "ph"))]
+pub(crate) fn str_from_response (response: &[u8]) -> Result <&str> {
+    ParseOptions::default ().body (response)
+}
+
+// Parses one of the comma-separated fields of a response, as obtained
+// from str::split(); a missing field is a parse error.
+#[cfg(feature = "common")]
+pub(crate) fn parse_field<T> (field: Option<&str>) -> Result<T>
+    where T: FromStr, T::Err: ::std::error::Error + Send + 'static
+{
+    match field {
+        Some (s) => T::from_str (s).chain_err (|| ErrorKind::ResponseParse),
+        None     => Err (ErrorKind::ResponseParse.into ())
+    }
+}
+
+// Parses the response to the "O,?" command of the multi-parameter circuits,
+// like "?O,EC,TDS,S,SG" or "?O,No output".  Returns whether each of the
+// parameters in `names` is enabled; it is an error for one to be unknown or
+// repeated.
+#[cfg(any(feature = "co2", feature = "do_", feature = "ec", feature = "flo", feature = "hum",
+          feature = "o2", feature = "rgb"))]
+pub(crate) fn parse_output_params (response: &[u8], names: &[&str], options: &ParseOptions) -> Result<Vec<bool>> {
+    let r = options.body (response)?;
+
+    let rest = match options.strip_keyword (r, "?O,") {
+        Some (rest) => rest,
+        None        => return Err (ErrorKind::ResponseParse.into ())
+    };
+
+    let mut enabled = vec! [false; names.len ()];
+
+    if options.matches (rest, "No output") {
+        return Ok (enabled);
+    }
+
+    for field in rest.split (',') {
+        match names.iter ().position (|name| options.matches (field, name)) {
+            Some (i) if !enabled[i] => enabled[i] = true,
+            _ => return Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+
+    Ok (enabled)
+}
+
+// Writes the names of the enabled output parameters like "EC, TDS", or "no
+// output" if none are enabled, for the Display of the OutputParams types.
+#[cfg(any(feature = "co2", feature = "do_", feature = "ec", feature = "flo", feature = "hum",
+          feature = "o2", feature = "rgb"))]
+pub(crate) fn write_output_params (f: &mut fmt::Formatter, params: &[(&str, bool)]) -> fmt::Result {
+    let mut enabled = params.iter ().filter (|p| p.1).map (|p| p.0);
+
+    match enabled.next () {
+        Some (first) => write! (f, "{}", first)?,
+        None => return write! (f, "no output")
+    }
+
+    for name in enabled {
+        write! (f, ", {}", name)?;
+    }
+
+    Ok (())
+}
+
+// Writes the fields which are present in a multi-parameter reading, like
+// "conductivity 1413 μS/cm, salinity 0.74 PSU", given each one's name.
+#[cfg(any(feature = "co2", feature = "do_", feature = "ec", feature = "flo", feature = "hum",
+          feature = "o2", feature = "rgb"))]
+pub(crate) fn write_reading (f: &mut fmt::Formatter, fields: &[(&str, Option<&dyn fmt::Display>)]) -> fmt::Result {
+    let mut present = fields.iter ().filter_map (|&(name, value)| value.map (|v| (name, v)));
+
+    match present.next () {
+        Some ((name, value)) => write! (f, "{} {}", name, value)?,
+        None => return write! (f, "empty reading")
+    }
+
+    for (name, value) in present {
+        write! (f, ", {} {}", name, value)?;
+    }
+
+    Ok (())
+}
+
+/// Status tokens which the device emits in UART mode
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum StatusToken {
+    /// "*OK", the command was understood
+    Ok,
+    /// "*ER", the command was not understood
+    Error,
+    /// "*OV", the supply voltage is too high
+    OverVoltage,
+    /// "*UV", the supply voltage is too low
+    UnderVoltage,
+    /// "*RS", the device is resetting
+    Reset,
+    /// "*RE", the device has booted up and is ready
+    Ready,
+    /// "*SL", the device is going to sleep
+    Sleeping,
+    /// "*WA", the device has woken up
+    Waking
+}
+
+impl StatusToken {
+    /// Parses one of the asterisk-prefixed status lines which the device
+    /// emits in UART mode.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<StatusToken> {
+        let r = options.body (response)?;
+
+        match r {
+            r if options.matches (r, "*OK") => Ok (StatusToken::Ok),
+            r if options.matches (r, "*ER") => Ok (StatusToken::Error),
+            r if options.matches (r, "*OV") => Ok (StatusToken::OverVoltage),
+            r if options.matches (r, "*UV") => Ok (StatusToken::UnderVoltage),
+            r if options.matches (r, "*RS") => Ok (StatusToken::Reset),
+            r if options.matches (r, "*RE") => Ok (StatusToken::Ready),
+            r if options.matches (r, "*SL") => Ok (StatusToken::Sleeping),
+            r if options.matches (r, "*WA") => Ok (StatusToken::Waking),
+            _ => Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for StatusToken {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            StatusToken::Ok           => "OK",
+            StatusToken::Error        => "error",
+            StatusToken::OverVoltage  => "over-voltage",
+            StatusToken::UnderVoltage => "under-voltage",
+            StatusToken::Reset        => "resetting",
+            StatusToken::Ready        => "ready",
+            StatusToken::Sleeping     => "sleeping",
+            StatusToken::Waking       => "waking up"
+        };
+
+        write! (f, "{}", s)
+    }
+}
+
+/// Takes in a buffer from a fixed-length I2C read, which has the response
+/// followed by padding bytes, and returns the nul-terminated response
+/// without the padding.  The padding may be any number of 0x00 or 0xFF bytes.
+pub fn strip_padding (response: &[u8]) -> Result<&[u8]> {
+    let len = match response.iter ().position (|&b| b == 0) {
+        Some (pos) => pos + 1,
+        None       => return Err (ErrorKind::MalformedResponse.into ())
+    };
+
+    let (terminated, padding) = response.split_at (len);
+
+    if padding.iter ().all (|&b| b == 0x00 || b == 0xff) {
+        Ok (terminated)
+    } else {
+        Err (ErrorKind::MalformedResponse.into ())
+    }
+}
+
+/// A response read from the device over I2C, including the initial status byte
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum RawI2cResponse<'a> {
+    /// Response code 1; contains the payload after the status byte
+    Success (&'a [u8]),
+    /// Response code 2
+    SyntaxError,
+    /// Response code 254; the device needs more time to process the command
+    Pending,
+    /// Response code 255
+    NoData
+}
+
+impl<'a> RawI2cResponse<'a> {
+    /// Parses the status byte of a response read over I2C.  Only a
+    /// successful response carries a payload, which can then be passed to
+    /// the `parse()` functions of the other response types.
+    ///
+    /// The response may be a whole fixed-length read buffer; any padding
+    /// after the payload's nul terminator is removed with `strip_padding()`.
+    pub fn parse (response: &'a [u8]) -> Result<RawI2cResponse<'a>> {
+        match response.split_first () {
+            Some ((&1, payload)) => Ok (RawI2cResponse::Success (strip_padding (payload)?)),
+            Some ((&2, _))       => Ok (RawI2cResponse::SyntaxError),
+            Some ((&254, _))     => Ok (RawI2cResponse::Pending),
+            Some ((&255, _))     => Ok (RawI2cResponse::NoData),
+            Some ((&code, _))    => Err (ErrorKind::UnknownResponseCode (code).into ()),
+            None                 => Err (ErrorKind::MalformedResponse.into ())
+        }
+    }
+
+    /// Returns the payload of a successful response, or an error that
+    /// corresponds to the response code otherwise.
+    pub fn payload (&self) -> Result<&'a [u8]> {
+        match *self {
+            RawI2cResponse::Success (payload) => Ok (payload),
+            RawI2cResponse::SyntaxError       => Err (ErrorKind::DeviceSyntaxError.into ()),
+            RawI2cResponse::Pending           => Err (ErrorKind::StillProcessing.into ()),
+            RawI2cResponse::NoData            => Err (ErrorKind::NoData.into ())
+        }
+    }
+}
+
+impl<'a> fmt::Display for RawI2cResponse<'a> {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RawI2cResponse::Success (payload) => write! (f, "success with {} bytes", payload.len ()),
+            RawI2cResponse::SyntaxError       => write! (f, "syntax error"),
+            RawI2cResponse::Pending           => write! (f, "still processing"),
+            RawI2cResponse::NoData            => write! (f, "no data")
+        }
+    }
+}
+
+/// Options for parsing responses which don't follow the datasheets to the
+/// letter, as happens with some firmware revisions and USB serial adapters.
+/// The `parse()` functions of the response types are strict, and so are
+/// the default options; the `parse_with_options()` functions take these.
+///
+/// Start from `ParseOptions::default ()` or `ParseOptions::lenient ()`, and
+/// turn individual leniencies on or off with the builder methods, like
+/// `ParseOptions::default ().optional_terminator (true)`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct ParseOptions {
+    /// Ignore whitespace around the response, like a "\r\n" line ending
+    pub trim_whitespace: bool,
+    /// Accept a response without its terminating nul or carriage return
+    pub optional_terminator: bool,
+    /// Accept keywords and codes in any case, like "?name,", "*ok" or the
+    /// "ph" of "?i,ph,1.98".  Names and other free-form values keep their
+    /// case.
+    pub ignore_case: bool
+}
+
+impl ParseOptions {
+    /// All of the leniencies enabled.
+    pub fn lenient () -> ParseOptions {
+        ParseOptions {
+            trim_whitespace: true,
+            optional_terminator: true,
+            ignore_case: true
+        }
+    }
+
+    /// Sets whether to ignore whitespace around the response.
+    pub fn trim_whitespace (mut self, trim_whitespace: bool) -> ParseOptions {
+        self.trim_whitespace = trim_whitespace;
+        self
+    }
+
+    /// Sets whether to accept a response without its terminator.
+    pub fn optional_terminator (mut self, optional_terminator: bool) -> ParseOptions {
+        self.optional_terminator = optional_terminator;
+        self
+    }
+
+    /// Sets whether to accept keywords and codes in any case.
+    pub fn ignore_case (mut self, ignore_case: bool) -> ParseOptions {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    /// Parses a response according to the options; this is the same as
+    /// `T::parse_with_options (response, &options)`.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse<T: ResponseParse> (&self, response: &[u8]) -> Result<T> {
+        T::parse_with_options (response, self)
+    }
+
+    // Validates that the response is valid UTF-8/ASCII, and terminated with
+    // a nul or a carriage return unless the terminator is optional.  Returns
+    // the response without its terminator.
+    pub(crate) fn body<'a> (&self, response: &'a [u8]) -> Result<&'a str> {
+        let mut r = str::from_utf8 (response).chain_err (|| ErrorKind::MalformedResponse)?;
+
+        if self.trim_whitespace {
+            r = r.trim_end_matches (&[' ', '\t', '\n'][..]);
+        }
+
+        if r.ends_with ('\0') || r.ends_with ('\r') {
+            r = r.get (..r.len () - 1).unwrap ();
+        } else if !self.optional_terminator {
+            return Err (ErrorKind::MalformedResponse.into ());
+        }
+
+        if r.contains (&['\0', '\r'][..]) {
+            return Err (ErrorKind::MalformedResponse.into ());
+        }
+
+        if self.trim_whitespace {
+            r = r.trim ();
+        }
+
+        Ok (r)
+    }
+
+    // Compares a keyword or code from a response, like "*OK" or "pH", with
+    // the one which the devices send
+    pub(crate) fn matches (&self, s: &str, expected: &str) -> bool {
+        if self.ignore_case {
+            s.eq_ignore_ascii_case (expected)
+        } else {
+            s == expected
+        }
+    }
+
+    // Strips a keyword like "?Cal," from the start of a response, and
+    // returns the rest of it
+    #[cfg(feature = "common")]
+    pub(crate) fn strip_keyword<'a> (&self, r: &'a str, keyword: &str) -> Option<&'a str> {
+        match r.get (..keyword.len ()) {
+            Some (start) if self.matches (start, keyword) => r.get (keyword.len ()..),
+            _ => None
+        }
+    }
+}
+
+// Commands which don't return data are acknowledged with an empty response
+// in I2C mode, or with "*OK" in UART mode.
+#[cfg(feature = "common")]
+pub(crate) fn is_acknowledgment (r: &str, options: &ParseOptions) -> bool {
+    r.is_empty () || options.matches (r, "*OK")
+}
+
+#[cfg(feature = "common")]
+pub(crate) fn parse_number<T: FromStr> (s: &str) -> Result<T>
+    where T::Err: ::std::error::Error + Send + 'static
+{
+    T::from_str (s).chain_err (|| ErrorKind::CommandParse)
+}
+
+// Parses the "0" or "1" argument of commands which turn something off or on
+#[cfg(any(feature = "co2", feature = "flo", feature = "hum", feature = "prs"))]
+pub(crate) fn parse_flag (s: &str) -> Result<bool> {
+    match s {
+        "0" => Ok (false),
+        "1" => Ok (true),
+        _   => Err (ErrorKind::CommandParse.into ())
+    }
+}
+
+// A command string like "Cal,mid,7.00" split at its first comma
+#[cfg(feature = "common")]
+pub(crate) struct SplitCommand<'a> {
+    // Lowercased command name, like "cal"
+    pub name: String,
+    // Rest of the command after the first comma, like "mid,7.00"
+    pub arg: Option<&'a str>,
+    // Lowercased version of arg, for matching keywords
+    pub keyword: Option<String>
+}
+
+#[cfg(feature = "common")]
+impl<'a> SplitCommand<'a> {
+    // Allows a single trailing nul or carriage return
+    pub fn new (command: &'a [u8]) -> Result<SplitCommand<'a>> {
+        let command = match command.split_last () {
+            Some ((&b'\0', c)) | Some ((&b'\r', c)) => c,
+            _ => command
+        };
+
+        let s = str::from_utf8 (command).chain_err (|| ErrorKind::CommandParse)?;
+
+        let mut split = s.splitn (2, ',');
+        let name = split.next ().unwrap ().to_ascii_lowercase ();
+        let arg = split.next ();
+        let keyword = arg.map (|a| a.to_ascii_lowercase ());
+
+        Ok (SplitCommand {
+            name,
+            arg,
+            keyword
+        })
+    }
+
+    // Options for looking up the lowercased name and arguments with the
+    // from_code() functions, which compare with the codes as the devices
+    // send them
+    #[cfg(any(feature = "do_", feature = "ec", feature = "flo", feature = "hum", feature = "prs"))]
+    pub fn options (&self) -> ParseOptions {
+        ParseOptions::default ().ignore_case (true)
+    }
+}
+
+// The firmware wants a plain decimal number, and rejects the command with
+// *ER otherwise.  Since f64's Display never uses an exponent, we only need to
+// bound the precision to the devices' resolution of 0.001, and strip the
+// trailing zeros.
+#[cfg(any(feature = "rtd", feature = "ph", feature = "ec", feature = "do_", feature = "orp",
+          feature = "hum", feature = "prs", feature = "flo", feature = "pmp"))]
+pub(crate) fn format_decimal (value: f64) -> String {
+    let s = format! ("{:.3}", value);
+    let s = s.trim_end_matches ('0');
+    let s = if s.ends_with ('.') {
+        format! ("{}0", s)
+    } else {
+        s.to_string ()
+    };
+
+    if s == "-0.0" {
+        "0.0".to_string ()
+    } else {
+        s
+    }
+}
+
+response_parse! (StatusToken);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_status_token () {
+        let tokens = [("*OK\0", StatusToken::Ok),
+                      ("*ER\0", StatusToken::Error),
+                      ("*OV\0", StatusToken::OverVoltage),
+                      ("*UV\0", StatusToken::UnderVoltage),
+                      ("*RS\0", StatusToken::Reset),
+                      ("*RE\0", StatusToken::Ready),
+                      ("*SL\0", StatusToken::Sleeping),
+                      ("*WA\0", StatusToken::Waking)];
+
+        for &(response, token) in tokens.iter () {
+            assert_eq! (StatusToken::parse (response.as_bytes ()).unwrap (), token);
+        }
+    }
+
+    #[test]
+    fn parsing_invalid_status_token_yields_error () {
+        let response = "\0".as_bytes ();
+        assert! (StatusToken::parse (response).is_err ());
+
+        let response = "*\0".as_bytes ();
+        assert! (StatusToken::parse (response).is_err ());
+
+        let response = "*XX\0".as_bytes ();
+        assert! (StatusToken::parse (response).is_err ());
+
+        let response = "OK\0".as_bytes ();
+        assert! (StatusToken::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_padded_raw_i2c_response () {
+        let response = b"\x01?S,f\0\0\0\xff\xff";
+        assert_eq! (RawI2cResponse::parse (response).unwrap (),
+                    RawI2cResponse::Success ("?S,f\0".as_bytes ()));
+    }
+
+    #[test]
+    fn strips_padding () {
+        assert_eq! (strip_padding (b"?S,f\0").unwrap (), b"?S,f\0");
+        assert_eq! (strip_padding (b"?S,f\0\0\0\0").unwrap (), b"?S,f\0");
+        assert_eq! (strip_padding (b"?S,f\0\xff\xff\0").unwrap (), b"?S,f\0");
+        assert_eq! (strip_padding (b"\0\0").unwrap (), b"\0");
+    }
+
+    #[test]
+    fn stripping_invalid_padding_yields_error () {
+        assert! (strip_padding (b"").is_err ());
+        assert! (strip_padding (b"?S,f").is_err ());
+        assert! (strip_padding (b"?S,f\0garbage").is_err ());
+        assert! (RawI2cResponse::parse (b"\x01?S,f").is_err ());
+    }
+
+    #[test]
+    fn raw_i2c_response_without_payload_yields_error () {
+        match *RawI2cResponse::SyntaxError.payload ().unwrap_err ().kind () {
+            ErrorKind::DeviceSyntaxError => (),
+            _ => panic! ("expected DeviceSyntaxError")
+        }
+
+        match *RawI2cResponse::Pending.payload ().unwrap_err ().kind () {
+            ErrorKind::StillProcessing => (),
+            _ => panic! ("expected StillProcessing")
+        }
+
+        match *RawI2cResponse::NoData.payload ().unwrap_err ().kind () {
+            ErrorKind::NoData => (),
+            _ => panic! ("expected NoData")
+        }
+    }
+
+    #[test]
+    fn parsing_invalid_raw_i2c_response_yields_error () {
+        assert! (RawI2cResponse::parse (&[]).is_err ());
+        assert! (RawI2cResponse::parse (&[0, 0]).is_err ());
+        assert! (RawI2cResponse::parse (&[3, 0]).is_err ());
+    }
+
+    #[test]
+    fn parses_with_default_options () {
+        let options = ParseOptions::default ();
+
+        assert_eq! (options.parse::<StatusToken> ("*OK\r".as_bytes ()).unwrap (), StatusToken::Ok);
+        assert! (options.parse::<StatusToken> ("*OK".as_bytes ()).is_err ());
+        assert! (options.parse::<StatusToken> ("*OK\r\n".as_bytes ()).is_err ());
+        assert! (options.parse::<StatusToken> ("*ok\r".as_bytes ()).is_err ());
+    }
+
+    #[test]
+    fn parses_with_lenient_options () {
+        let options = ParseOptions::lenient ();
+
+        assert_eq! (options.parse::<StatusToken> ("*OK\r\n".as_bytes ()).unwrap (), StatusToken::Ok);
+        assert_eq! (options.parse::<StatusToken> (" *OK \0".as_bytes ()).unwrap (), StatusToken::Ok);
+        assert_eq! (options.parse::<StatusToken> ("*OK".as_bytes ()).unwrap (), StatusToken::Ok);
+        assert_eq! (options.parse::<StatusToken> ("*ok\r".as_bytes ()).unwrap (), StatusToken::Ok);
+        assert_eq! (options.parse::<StatusToken> ("*Wa".as_bytes ()).unwrap (), StatusToken::Waking);
+
+        assert_eq! (options.body ("\t?L,1 \r\n".as_bytes ()).unwrap (), "?L,1");
+
+        assert! (options.parse::<StatusToken> ("*XX".as_bytes ()).is_err ());
+        assert! (options.parse::<StatusToken> ("*OK\0\0".as_bytes ()).is_err ());
+    }
+
+    #[test]
+    fn parses_with_some_options () {
+        let options = ParseOptions::default ().optional_terminator (true);
+
+        assert_eq! (options.parse::<StatusToken> ("*OK".as_bytes ()).unwrap (), StatusToken::Ok);
+        assert! (options.parse::<StatusToken> ("*OK ".as_bytes ()).is_err ());
+        assert! (options.parse::<StatusToken> ("*ok".as_bytes ()).is_err ());
+    }
+}
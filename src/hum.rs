@@ -0,0 +1,512 @@
+//! Responses and commands for the EZO HUM (humidity) sensor
+
+use std::fmt;
+use std::num::ParseFloatError;
+use std::str::FromStr;
+use std::time::Duration;
+
+use errors::*;
+use framing::{format_decimal, parse_flag, parse_number, write_output_params, write_reading, SplitCommand};
+use {parse_field, parse_output_params, CommonCommand, ParseOptions, Temperature};
+
+/// Relative humidity as a percentage
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct RelativeHumidity (pub f64);
+
+impl FromStr for RelativeHumidity {
+    type Err = ParseFloatError;
+
+    fn from_str (s: &str) -> ::std::result::Result<RelativeHumidity, ParseFloatError> {
+        f64::from_str (s).map (RelativeHumidity)
+    }
+}
+
+impl fmt::Display for RelativeHumidity {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{} %RH", self.0)
+    }
+}
+
+/// Which parameters the HUM sensor includes in its readings.  The sensor
+/// always reports the enabled parameters in the order of the fields here.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct OutputParams {
+    /// "HUM", relative humidity
+    pub humidity: bool,
+    /// "T", air temperature in Celsius
+    pub temperature: bool,
+    /// "Dew", dew point in Celsius
+    pub dew_point: bool
+}
+
+impl OutputParams {
+    /// All of the parameters enabled.
+    pub fn all () -> OutputParams {
+        OutputParams {
+            humidity: true,
+            temperature: true,
+            dew_point: true
+        }
+    }
+}
+
+impl fmt::Display for OutputParams {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_output_params (f, &[("HUM", self.humidity),
+                                  ("T", self.temperature),
+                                  ("Dew", self.dew_point)])
+    }
+}
+
+/// Response from the "R" command to take a reading.  Only the parameters
+/// which are enabled in the sensor's output configuration are present.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct HumResponse {
+    pub humidity: Option<RelativeHumidity>,
+    pub temperature: Option<Temperature>,
+    pub dew_point: Option<Temperature>
+}
+
+impl HumResponse {
+    /// Parses the result of the "R" command to take a reading, given the
+    /// parameters which are enabled in the sensor, as reported by "O,?".
+    /// The sensor puts a "Dew" field before the dew point, like
+    /// "48.3,24.1,Dew,12.6"; this is optional here.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse (response: &[u8], params: &OutputParams) -> Result<HumResponse> {
+        HumResponse::parse_with_options (response, params, &ParseOptions::default ())
+    }
+
+    /// Like `parse()`, with the leniencies in `options`.
+    pub fn parse_with_options (response: &[u8], params: &OutputParams, options: &ParseOptions)
+                               -> Result<HumResponse> {
+        let r = options.body (response)?;
+        let mut split = r.split (',').peekable ();
+
+        let humidity = if params.humidity {
+            Some (RelativeHumidity (parse_field (split.next ())?))
+        } else {
+            None
+        };
+
+        let temperature = if params.temperature {
+            Some (Temperature::Celsius (parse_field (split.next ())?))
+        } else {
+            None
+        };
+
+        let dew_point = if params.dew_point {
+            if split.peek ().map_or (false, |s| options.matches (s, "Dew")) {
+                split.next ();
+            }
+
+            Some (Temperature::Celsius (parse_field (split.next ())?))
+        } else {
+            None
+        };
+
+        if split.next ().is_some () {
+            return Err (ErrorKind::ResponseParse.into ());
+        }
+
+        Ok (HumResponse {
+            humidity,
+            temperature,
+            dew_point
+        })
+    }
+}
+
+impl fmt::Display for HumResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_reading (f, &[("humidity", self.humidity.as_ref ().map (|v| v as _)),
+                            ("temperature", self.temperature.as_ref ().map (|v| v as _)),
+                            ("dew point", self.dew_point.as_ref ().map (|v| v as _))])
+    }
+}
+
+/// Response from the "O,?" command to query which parameters the HUM
+/// sensor includes in its readings
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct OutputParamsResponse (pub OutputParams);
+
+impl OutputParamsResponse {
+    /// Parses the result of the "O,?" command, like "?O,HUM,T,Dew".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<OutputParamsResponse> {
+        let enabled = parse_output_params (response, &["HUM", "T", "Dew"], options)?;
+
+        Ok (OutputParamsResponse (OutputParams {
+            humidity: enabled[0],
+            temperature: enabled[1],
+            dew_point: enabled[2]
+        }))
+    }
+}
+
+impl fmt::Display for OutputParamsResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "output parameters are {}", self.0)
+    }
+}
+
+/// Response from the "Alarm,?" command to query the configuration of the
+/// sensor's alarm pin, like "?Alarm,80,5,1"
+pub type AlarmConfigResponse = ::AlarmConfigResponse<RelativeHumidity>;
+
+/// A parameter which the HUM sensor can include in its readings
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OutputParameter {
+    /// "HUM", relative humidity
+    Humidity,
+    /// "T", air temperature
+    Temperature,
+    /// "Dew", dew point
+    DewPoint
+}
+
+impl OutputParameter {
+    fn code (&self) -> &'static str {
+        match *self {
+            OutputParameter::Humidity    => "HUM",
+            OutputParameter::Temperature => "T",
+            OutputParameter::DewPoint    => "Dew"
+        }
+    }
+
+    fn from_code (code: &str, options: &ParseOptions) -> Option<OutputParameter> {
+        match code {
+            c if options.matches (c, "HUM") => Some (OutputParameter::Humidity),
+            c if options.matches (c, "T")   => Some (OutputParameter::Temperature),
+            c if options.matches (c, "Dew") => Some (OutputParameter::DewPoint),
+            _                               => None
+        }
+    }
+}
+
+impl fmt::Display for OutputParameter {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            OutputParameter::Humidity    => "humidity",
+            OutputParameter::Temperature => "temperature",
+            OutputParameter::DewPoint    => "dew point"
+        };
+
+        write! (f, "{}", name)
+    }
+}
+
+/// Commands which can be sent to the EZO HUM sensor
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Command {
+    /// "R", take a reading
+    Read,
+    /// "O,param,n", enable or disable a parameter in readings
+    SetOutput (OutputParameter, bool),
+    /// "O,?", query which parameters are enabled in readings
+    OutputQuery,
+    /// "Alarm,en,n", enable or disable the alarm pin
+    SetAlarmEnabled (bool),
+    /// "Alarm,n", set the relative humidity at which the alarm goes off
+    SetAlarmThreshold (RelativeHumidity),
+    /// "Alarm,tol,n", set how far below the threshold the alarm resets
+    SetAlarmTolerance (RelativeHumidity),
+    /// "Alarm,?", query the alarm configuration
+    AlarmQuery,
+    /// A command which is common to all the EZO circuits
+    Common (CommonCommand)
+}
+
+fn validate_humidity (humidity: RelativeHumidity) -> Result<RelativeHumidity> {
+    if (0.0 ..= 100.0).contains (&humidity.0) {
+        Ok (humidity)
+    } else {
+        Err (ErrorKind::InvalidCommandParameter ("relative humidity must be between 0 and 100").into ())
+    }
+}
+
+impl Command {
+    /// Creates a command to set the alarm threshold, which must be between
+    /// 0 and 100 %RH.
+    pub fn set_alarm_threshold (threshold: RelativeHumidity) -> Result<Command> {
+        Ok (Command::SetAlarmThreshold (validate_humidity (threshold)?))
+    }
+
+    /// Creates a command to set the alarm tolerance, which must be between
+    /// 0 and 100 %RH.
+    pub fn set_alarm_tolerance (tolerance: RelativeHumidity) -> Result<Command> {
+        Ok (Command::SetAlarmTolerance (validate_humidity (tolerance)?))
+    }
+
+    /// Parses a command string as it would be sent to the device, like
+    /// "O,Dew,1" or "Alarm,80", in the same way as `saiba_parser::Command::parse()`.
+    pub fn parse (command: &[u8]) -> Result<Command> {
+        let split = SplitCommand::new (command)?;
+
+        if let Some (common) = CommonCommand::from_split (&split)? {
+            return Ok (Command::Common (common));
+        }
+
+        let mut args = split.keyword.as_ref ().map (|k| k.splitn (2, ','));
+        let first = args.as_mut ().and_then (|a| a.next ());
+        let second = args.as_mut ().and_then (|a| a.next ());
+
+        let command = match (split.name.as_str (), first, second) {
+            ("r", None, None)                   => Command::Read,
+            ("o", Some ("?"), None)             => Command::OutputQuery,
+
+            ("o", Some (p), Some (n)) => {
+                let param = OutputParameter::from_code (p, &split.options ()).ok_or (ErrorKind::CommandParse)?;
+                Command::SetOutput (param, parse_flag (n)?)
+            },
+
+            ("alarm", Some ("en"), Some (n))    => Command::SetAlarmEnabled (parse_flag (n)?),
+            ("alarm", Some ("tol"), Some (n))   => Command::set_alarm_tolerance (RelativeHumidity (parse_number (n)?))?,
+            ("alarm", Some ("?"), None)         => Command::AlarmQuery,
+            ("alarm", Some (n), None)           => Command::set_alarm_threshold (RelativeHumidity (parse_number (n)?))?,
+            _                                   => return Err (ErrorKind::CommandParse.into ())
+        };
+
+        Ok (command)
+    }
+
+    /// Returns how long to wait after sending this command before reading
+    /// the device's response over I2C.
+    pub fn delay (&self) -> Duration {
+        match *self {
+            Command::Common (ref common) => common.delay (),
+            _                            => Duration::from_millis (300)
+        }
+    }
+
+    /// Returns the bytes to send to the device for this command.  In I2C
+    /// mode these are sent as-is; in UART mode, the caller must append a
+    /// carriage return.
+    pub fn encode (&self) -> Vec<u8> {
+        let s = match *self {
+            Command::Read                               => "R".to_string (),
+            Command::SetOutput (param, enabled)         => format! ("O,{},{}", param.code (), if enabled { 1 } else { 0 }),
+            Command::OutputQuery                        => "O,?".to_string (),
+            Command::SetAlarmEnabled (enabled)          => format! ("Alarm,en,{}", if enabled { 1 } else { 0 }),
+            Command::SetAlarmThreshold (rh)             => format! ("Alarm,{}", format_decimal (rh.0)),
+            Command::SetAlarmTolerance (rh)             => format! ("Alarm,tol,{}", format_decimal (rh.0)),
+            Command::AlarmQuery                         => "Alarm,?".to_string (),
+            Command::Common (ref common)                => return common.encode ()
+        };
+
+        s.into_bytes ()
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Command::Read                     => write! (f, "take a reading"),
+            Command::SetOutput (param, true)  => write! (f, "enable {} in readings", param),
+            Command::SetOutput (param, false) => write! (f, "disable {} in readings", param),
+            Command::OutputQuery              => write! (f, "query output parameters"),
+            Command::SetAlarmEnabled (true)   => write! (f, "enable alarm"),
+            Command::SetAlarmEnabled (false)  => write! (f, "disable alarm"),
+            Command::SetAlarmThreshold (rh)   => write! (f, "set alarm threshold to {}", rh),
+            Command::SetAlarmTolerance (rh)   => write! (f, "set alarm tolerance to {}", rh),
+            Command::AlarmQuery               => write! (f, "query alarm configuration"),
+            Command::Common (ref common)      => common.fmt (f)
+        }
+    }
+}
+
+impl From<CommonCommand> for Command {
+    fn from (c: CommonCommand) -> Command {
+        Command::Common (c)
+    }
+}
+
+response_parse! (OutputParamsResponse);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hum_response () {
+        let response = "48.3,24.1,Dew,12.6\0".as_bytes ();
+        assert_eq! (HumResponse::parse (response, &OutputParams::all ()).unwrap (),
+                    HumResponse {
+                        humidity: Some (RelativeHumidity (48.3)),
+                        temperature: Some (Temperature::Celsius (24.1)),
+                        dew_point: Some (Temperature::Celsius (12.6))
+                    });
+
+        let params = OutputParams {
+            humidity: true,
+            dew_point: true,
+            ..OutputParams::default ()
+        };
+
+        let response = "48.3,12.6\r".as_bytes ();
+        assert_eq! (HumResponse::parse (response, &params).unwrap (),
+                    HumResponse {
+                        humidity: Some (RelativeHumidity (48.3)),
+                        temperature: None,
+                        dew_point: Some (Temperature::Celsius (12.6))
+                    });
+
+        let response = "48.3\0".as_bytes ();
+        assert_eq! (HumResponse::parse (response, &OutputParams { humidity: true, ..OutputParams::default () }).unwrap (),
+                    HumResponse {
+                        humidity: Some (RelativeHumidity (48.3)),
+                        ..HumResponse::default ()
+                    });
+    }
+
+    #[test]
+    fn parses_mixed_case_hum_response_leniently () {
+        let options = ParseOptions::lenient ();
+
+        let response = "48.3,24.1,DEW,12.6".as_bytes ();
+        assert_eq! (HumResponse::parse_with_options (response, &OutputParams::all (), &options).unwrap (),
+                    HumResponse {
+                        humidity: Some (RelativeHumidity (48.3)),
+                        temperature: Some (Temperature::Celsius (24.1)),
+                        dew_point: Some (Temperature::Celsius (12.6))
+                    });
+
+        let response = "48.3,24.1,DEW,12.6\0".as_bytes ();
+        assert! (HumResponse::parse (response, &OutputParams::all ()).is_err ());
+    }
+
+    #[test]
+    fn parsing_invalid_hum_response_yields_error () {
+        let response = "48.3,24.1\0".as_bytes ();
+        assert! (HumResponse::parse (response, &OutputParams::all ()).is_err ());
+
+        let response = "48.3,24.1,Dew\0".as_bytes ();
+        assert! (HumResponse::parse (response, &OutputParams::all ()).is_err ());
+
+        let response = "48.3,24.1,Dew,12.6,1\0".as_bytes ();
+        assert! (HumResponse::parse (response, &OutputParams::all ()).is_err ());
+
+        let response = "48.3,Dew,12.6\0".as_bytes ();
+        assert! (HumResponse::parse (response, &OutputParams { humidity: true, temperature: true, dew_point: false }).is_err ());
+    }
+
+    #[test]
+    fn displays_relative_humidity () {
+        assert_eq! (RelativeHumidity (48.3).to_string (), "48.3 %RH");
+    }
+
+    #[test]
+    fn parses_output_params_response () {
+        let response = "?O,HUM,T,Dew\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams::all ()));
+
+        let response = "?O,HUM,Dew\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams {
+                        humidity: true,
+                        temperature: false,
+                        dew_point: true
+                    }));
+
+        let response = "?O,No output\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams::default ()));
+    }
+
+    #[test]
+    fn parsing_invalid_output_params_response_yields_error () {
+        let response = "?O,\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+
+        let response = "?O,HUM,HUM\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+
+        let response = "?O,HUM,EC\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+
+        let response = "HUM,T\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_alarm_config_response () {
+        let response = "?Alarm,80,5,1\0".as_bytes ();
+        assert_eq! (AlarmConfigResponse::parse (response).unwrap (),
+                    AlarmConfigResponse {
+                        threshold: RelativeHumidity (80.0),
+                        tolerance: RelativeHumidity (5.0),
+                        enabled: true
+                    });
+
+        let response = "?Alarm,80,5\0".as_bytes ();
+        assert! (AlarmConfigResponse::parse (response).is_err ());
+
+        let response = "?Alarm,humid,5,0\0".as_bytes ();
+        assert! (AlarmConfigResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn encodes_commands () {
+        let commands = [(Command::Read, "R"),
+                        (Command::SetOutput (OutputParameter::Humidity, true), "O,HUM,1"),
+                        (Command::SetOutput (OutputParameter::Temperature, false), "O,T,0"),
+                        (Command::SetOutput (OutputParameter::DewPoint, true), "O,Dew,1"),
+                        (Command::OutputQuery, "O,?"),
+                        (Command::SetAlarmEnabled (true), "Alarm,en,1"),
+                        (Command::set_alarm_threshold (RelativeHumidity (80.0)).unwrap (), "Alarm,80.0"),
+                        (Command::set_alarm_tolerance (RelativeHumidity (2.5)).unwrap (), "Alarm,tol,2.5"),
+                        (Command::AlarmQuery, "Alarm,?"),
+                        (CommonCommand::Sleep.into (), "Sleep")];
+
+        for &(ref command, encoded) in commands.iter () {
+            assert_eq! (command.encode (), encoded.as_bytes ());
+            assert_eq! (&Command::parse (encoded.as_bytes ()).unwrap (), command);
+        }
+    }
+
+    #[test]
+    fn invalid_commands_yield_error () {
+        assert! (Command::set_alarm_threshold (RelativeHumidity (101.0)).is_err ());
+        assert! (Command::set_alarm_tolerance (RelativeHumidity (-1.0)).is_err ());
+
+        assert! (Command::parse ("O,EC,1".as_bytes ()).is_err ());
+        assert! (Command::parse ("O,HUM,yes".as_bytes ()).is_err ());
+        assert! (Command::parse ("Alarm,en".as_bytes ()).is_err ());
+        assert! (Command::parse ("Cal,50".as_bytes ()).is_err ());
+    }
+
+    #[test]
+    fn commands_have_processing_delay () {
+        assert_eq! (Command::Read.delay (), Duration::from_millis (300));
+        assert_eq! (Command::Common (CommonCommand::Status).delay (), Duration::from_millis (300));
+    }
+
+    #[test]
+    fn displays_responses () {
+        let response = HumResponse::parse ("48.3,24.1,Dew,12.6\0".as_bytes (), &OutputParams::all ()).unwrap ();
+        assert_eq! (response.to_string (), "humidity 48.3 %RH, temperature 24.1 °C, dew point 12.6 °C");
+
+        let params = OutputParams {
+            humidity: true,
+            dew_point: true,
+            ..OutputParams::default ()
+        };
+
+        assert_eq! (OutputParamsResponse (params).to_string (), "output parameters are HUM, Dew");
+    }
+
+    #[test]
+    fn displays_commands () {
+        assert_eq! (Command::SetOutput (OutputParameter::DewPoint, true).to_string (), "enable dew point in readings");
+        assert_eq! (Command::SetAlarmTolerance (RelativeHumidity (5.0)).to_string (), "set alarm tolerance to 5 %RH");
+        assert_eq! (Command::AlarmQuery.to_string (), "query alarm configuration");
+        assert_eq! (Command::Common (CommonCommand::Find).to_string (), "blink LED to find device");
+    }
+}
@@ -0,0 +1,252 @@
+//! Responses and commands for the EZO O2 (gaseous oxygen) sensor
+
+use std::fmt;
+use std::num::ParseFloatError;
+use std::str::FromStr;
+
+use errors::*;
+use framing::{write_output_params, write_reading};
+use {parse_field, parse_output_params, ParseOptions};
+
+/// Oxygen concentration as a percentage
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Percent (pub f64);
+
+impl FromStr for Percent {
+    type Err = ParseFloatError;
+
+    fn from_str (s: &str) -> ::std::result::Result<Percent, ParseFloatError> {
+        f64::from_str (s).map (Percent)
+    }
+}
+
+impl fmt::Display for Percent {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{} %", self.0)
+    }
+}
+
+/// Oxygen concentration in parts per thousand
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct PartsPerThousand (pub f64);
+
+impl fmt::Display for PartsPerThousand {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{} ppt", self.0)
+    }
+}
+
+/// Response from the "Alarm,?" command to query the configuration of the
+/// sensor's alarm pin, like "?Alarm,19.5,0.5,1"
+pub type AlarmConfigResponse = ::AlarmConfigResponse<Percent>;
+
+/// Which parameters the O2 sensor includes in its readings.  The sensor
+/// always reports the enabled parameters in the order of the fields here.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct OutputParams {
+    /// "%", percentage
+    pub percent: bool,
+    /// "ppt", parts per thousand
+    pub parts_per_thousand: bool
+}
+
+impl OutputParams {
+    /// All of the parameters enabled.
+    pub fn all () -> OutputParams {
+        OutputParams {
+            percent: true,
+            parts_per_thousand: true
+        }
+    }
+}
+
+impl fmt::Display for OutputParams {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_output_params (f, &[("%", self.percent),
+                                  ("ppt", self.parts_per_thousand)])
+    }
+}
+
+/// Response from the "R" command to take a reading.  Only the parameters
+/// which are enabled in the sensor's output configuration are present.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct O2Response {
+    pub percent: Option<Percent>,
+    pub parts_per_thousand: Option<PartsPerThousand>
+}
+
+impl O2Response {
+    /// Parses the result of the "R" command to take a reading, given the
+    /// parameters which are enabled in the sensor, as reported by "O,?".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse (response: &[u8], params: &OutputParams) -> Result<O2Response> {
+        O2Response::parse_with_options (response, params, &ParseOptions::default ())
+    }
+
+    /// Like `parse()`, with the leniencies in `options`.
+    pub fn parse_with_options (response: &[u8], params: &OutputParams, options: &ParseOptions)
+                               -> Result<O2Response> {
+        let r = options.body (response)?;
+        let mut split = r.split (',');
+
+        let mut field = |enabled| -> Result<Option<f64>> {
+            if enabled {
+                Ok (Some (parse_field (split.next ())?))
+            } else {
+                Ok (None)
+            }
+        };
+
+        let percent = field (params.percent)?.map (Percent);
+        let parts_per_thousand = field (params.parts_per_thousand)?.map (PartsPerThousand);
+
+        if split.next ().is_some () {
+            return Err (ErrorKind::ResponseParse.into ());
+        }
+
+        Ok (O2Response {
+            percent,
+            parts_per_thousand
+        })
+    }
+}
+
+impl fmt::Display for O2Response {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_reading (f, &[("oxygen", self.percent.as_ref ().map (|v| v as _)),
+                            ("concentration", self.parts_per_thousand.as_ref ().map (|v| v as _))])
+    }
+}
+
+/// Response from the "O,?" command to query which parameters the O2
+/// sensor includes in its readings
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct OutputParamsResponse (pub OutputParams);
+
+impl OutputParamsResponse {
+    /// Parses the result of the "O,?" command, like "?O,%,ppt".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<OutputParamsResponse> {
+        let enabled = parse_output_params (response, &["%", "ppt"], options)?;
+
+        Ok (OutputParamsResponse (OutputParams {
+            percent: enabled[0],
+            parts_per_thousand: enabled[1]
+        }))
+    }
+}
+
+impl fmt::Display for OutputParamsResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "output parameters are {}", self.0)
+    }
+}
+
+response_parse! (OutputParamsResponse);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_o2_response () {
+        let response = "20.95,209.5\0".as_bytes ();
+        assert_eq! (O2Response::parse (response, &OutputParams::all ()).unwrap (),
+                    O2Response {
+                        percent: Some (Percent (20.95)),
+                        parts_per_thousand: Some (PartsPerThousand (209.5))
+                    });
+
+        let params = OutputParams {
+            percent: true,
+            ..OutputParams::default ()
+        };
+
+        let response = "20.95\r".as_bytes ();
+        assert_eq! (O2Response::parse (response, &params).unwrap (),
+                    O2Response {
+                        percent: Some (Percent (20.95)),
+                        parts_per_thousand: None
+                    });
+    }
+
+    #[test]
+    fn parsing_invalid_o2_response_yields_error () {
+        let response = "20.95\0".as_bytes ();
+        assert! (O2Response::parse (response, &OutputParams::all ()).is_err ());
+
+        let response = "20.95,209.5\0".as_bytes ();
+        assert! (O2Response::parse (response, &OutputParams { percent: true, parts_per_thousand: false }).is_err ());
+
+        let response = "20.95,x\0".as_bytes ();
+        assert! (O2Response::parse (response, &OutputParams::all ()).is_err ());
+    }
+
+    #[test]
+    fn parses_alarm_config_response () {
+        let response = "?Alarm,19.5,0.5,1\0".as_bytes ();
+        assert_eq! (AlarmConfigResponse::parse (response).unwrap (),
+                    AlarmConfigResponse {
+                        threshold: Percent (19.5),
+                        tolerance: Percent (0.5),
+                        enabled: true
+                    });
+
+        let response = "?Alarm,19.5,0.5\0".as_bytes ();
+        assert! (AlarmConfigResponse::parse (response).is_err ());
+
+        let response = "?Alarm,low,0.5,1\0".as_bytes ();
+        assert! (AlarmConfigResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn displays_alarm_config_response () {
+        let response = AlarmConfigResponse::parse ("?Alarm,19.5,0.5,1\0".as_bytes ()).unwrap ();
+        assert_eq! (response.to_string (), "alarm is enabled at 19.5 % with a tolerance of 0.5 %");
+
+        let response = AlarmConfigResponse::parse ("?Alarm,19.5,0.5,0\0".as_bytes ()).unwrap ();
+        assert_eq! (response.to_string (), "alarm is disabled at 19.5 % with a tolerance of 0.5 %");
+    }
+
+    #[test]
+    fn parses_output_params_response () {
+        let response = "?O,%,ppt\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams::all ()));
+
+        let response = "?O,ppt\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams {
+                        percent: false,
+                        parts_per_thousand: true
+                    }));
+
+        let response = "?O,No output\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams::default ()));
+    }
+
+    #[test]
+    fn parsing_invalid_output_params_response_yields_error () {
+        let response = "?O,\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+
+        let response = "?O,ppt,ppt\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+
+        let response = "?O,ppm\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn displays_responses () {
+        let response = O2Response::parse ("20.95,209.5\0".as_bytes (), &OutputParams::all ()).unwrap ();
+        assert_eq! (response.to_string (), "oxygen 20.95 %, concentration 209.5 ppt");
+        assert_eq! (OutputParamsResponse (OutputParams::all ()).to_string (), "output parameters are %, ppt");
+        assert_eq! (OutputParamsResponse (OutputParams::default ()).to_string (), "output parameters are no output");
+    }
+}
@@ -0,0 +1,301 @@
+//! Responses and commands for the EZO ORP (oxidation-reduction potential) circuit
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use errors::*;
+use framing::{format_decimal, parse_number, SplitCommand};
+use {CalibrationState, CommonCommand, ParseOptions};
+
+/// A potential in millivolts
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Millivolts (pub f64);
+
+impl fmt::Display for Millivolts {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{} mV", self.0)
+    }
+}
+
+/// Response from the "R" command to take an ORP reading
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct OrpResponse (pub Millivolts);
+
+impl OrpResponse {
+    /// Parses the result of the "R" command to get an ORP reading.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<OrpResponse> {
+        let r = options.body (response)?;
+        let val = f64::from_str (r).chain_err (|| ErrorKind::ResponseParse)?;
+
+        Ok (OrpResponse (Millivolts (val)))
+    }
+}
+
+impl fmt::Display for OrpResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "potential is {}", self.0)
+    }
+}
+
+/// Response from the "Cal,?" command to query the ORP circuit's
+/// calibration.  Like the RTD circuit, it has a single calibration point.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CalibrationStateResponse (pub CalibrationState);
+
+impl CalibrationStateResponse {
+    /// Parses the result of the "Cal,?" command to query whether the ORP
+    /// circuit is calibrated.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<CalibrationStateResponse> {
+        let r = options.body (response)?;
+
+        match options.strip_keyword (r, "?Cal,") {
+            Some ("0") => Ok (CalibrationStateResponse (CalibrationState::Uncalibrated)),
+            Some ("1") => Ok (CalibrationStateResponse (CalibrationState::Calibrated)),
+            _ => Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for CalibrationStateResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "device is {}", self.0)
+    }
+}
+
+/// Minimum potential of an ORP calibration solution, in millivolts
+pub const MIN_CALIBRATION_POTENTIAL: f64 = -2000.0;
+
+/// Maximum potential of an ORP calibration solution, in millivolts
+pub const MAX_CALIBRATION_POTENTIAL: f64 = 2000.0;
+
+/// The "Cal,n" command to calibrate the probe with a reference solution of
+/// a known potential, like 225 mV
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CalibrationCommand (Millivolts);
+
+impl CalibrationCommand {
+    /// Creates a command to calibrate the probe at the given potential,
+    /// which must be between `MIN_CALIBRATION_POTENTIAL` and
+    /// `MAX_CALIBRATION_POTENTIAL`.
+    pub fn new (potential: Millivolts) -> Result<CalibrationCommand> {
+        if (MIN_CALIBRATION_POTENTIAL ..= MAX_CALIBRATION_POTENTIAL).contains (&potential.0) {
+            Ok (CalibrationCommand (potential))
+        } else {
+            Err (ErrorKind::InvalidCommandParameter ("calibration potential must be between -2000 and 2000 mV").into ())
+        }
+    }
+
+    pub fn potential (&self) -> Millivolts {
+        self.0
+    }
+
+    /// Returns the bytes to send to the device for this command.
+    pub fn encode (&self) -> Vec<u8> {
+        Command::from (*self).encode ()
+    }
+}
+
+impl fmt::Display for CalibrationCommand {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Command::from (*self).fmt (f)
+    }
+}
+
+/// Commands which can be sent to the EZO ORP circuit
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Command {
+    /// "R", take an ORP reading
+    Read,
+    /// "Cal,n", calibrate with a reference solution of the given potential
+    Calibrate (Millivolts),
+    /// A command which is common to all the EZO circuits
+    Common (CommonCommand)
+}
+
+impl Command {
+    /// Creates a command to calibrate the probe at the given potential in
+    /// millivolts; see `CalibrationCommand::new()`.
+    pub fn calibrate (potential: f64) -> Result<Command> {
+        Ok (CalibrationCommand::new (Millivolts (potential))?.into ())
+    }
+
+    /// Parses a command string as it would be sent to the device, like
+    /// "Cal,225" or "R", in the same way as `saiba_parser::Command::parse()`.
+    pub fn parse (command: &[u8]) -> Result<Command> {
+        let split = SplitCommand::new (command)?;
+
+        if let Some (common) = CommonCommand::from_split (&split)? {
+            return Ok (Command::Common (common));
+        }
+
+        let command = match (split.name.as_str (), split.keyword.as_deref ()) {
+            ("r", None)        => Command::Read,
+            ("cal", Some (n))  => Command::calibrate (parse_number (n)?)?,
+            _                  => return Err (ErrorKind::CommandParse.into ())
+        };
+
+        Ok (command)
+    }
+
+    /// Returns how long to wait after sending this command before reading
+    /// the device's response over I2C.
+    pub fn delay (&self) -> Duration {
+        match *self {
+            Command::Read
+                | Command::Calibrate (_) => Duration::from_millis (900),
+
+            Command::Common (ref common) => common.delay ()
+        }
+    }
+
+    /// Returns the bytes to send to the device for this command.  In I2C
+    /// mode these are sent as-is; in UART mode, the caller must append a
+    /// carriage return.
+    pub fn encode (&self) -> Vec<u8> {
+        let s = match *self {
+            Command::Read                => "R".to_string (),
+            Command::Calibrate (mv)      => format! ("Cal,{}", format_decimal (mv.0)),
+            Command::Common (ref common) => return common.encode ()
+        };
+
+        s.into_bytes ()
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Command::Read                => write! (f, "take a reading"),
+            Command::Calibrate (mv)      => write! (f, "calibrate at {}", mv),
+            Command::Common (ref common) => common.fmt (f)
+        }
+    }
+}
+
+impl From<CalibrationCommand> for Command {
+    fn from (c: CalibrationCommand) -> Command {
+        Command::Calibrate (c.0)
+    }
+}
+
+impl From<CommonCommand> for Command {
+    fn from (c: CommonCommand) -> Command {
+        Command::Common (c)
+    }
+}
+
+response_parse! (OrpResponse, CalibrationStateResponse);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_orp_response () {
+        let response = "225.3\0".as_bytes ();
+        assert_eq! (OrpResponse::parse (response).unwrap (),
+                    OrpResponse (Millivolts (225.3)));
+
+        let response = "-415.0\r".as_bytes ();
+        assert_eq! (OrpResponse::parse (response).unwrap (),
+                    OrpResponse (Millivolts (-415.0)));
+    }
+
+    #[test]
+    fn parsing_invalid_orp_response_yields_error () {
+        let response = "\0".as_bytes ();
+        assert! (OrpResponse::parse (response).is_err ());
+
+        let response = "225.3mV\0".as_bytes ();
+        assert! (OrpResponse::parse (response).is_err ());
+
+        let response = "225.3,1\0".as_bytes ();
+        assert! (OrpResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn displays_millivolts () {
+        assert_eq! (Millivolts (-415.5).to_string (), "-415.5 mV");
+    }
+
+    #[test]
+    fn parses_calibration_state_response () {
+        let response = "?Cal,0\0".as_bytes ();
+        assert_eq! (CalibrationStateResponse::parse (response).unwrap (),
+                    CalibrationStateResponse (CalibrationState::Uncalibrated));
+
+        let response = "?Cal,1\0".as_bytes ();
+        assert_eq! (CalibrationStateResponse::parse (response).unwrap (),
+                    CalibrationStateResponse (CalibrationState::Calibrated));
+
+        let response = "?Cal,2\0".as_bytes ();
+        assert! (CalibrationStateResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn encodes_calibration_command () {
+        let command = CalibrationCommand::new (Millivolts (225.0)).unwrap ();
+        assert_eq! (command.encode (), "Cal,225.0".as_bytes ());
+
+        let command = CalibrationCommand::new (Millivolts (-2000.0)).unwrap ();
+        assert_eq! (command.encode (), "Cal,-2000.0".as_bytes ());
+
+        assert! (CalibrationCommand::new (Millivolts (2000.5)).is_err ());
+        assert! (CalibrationCommand::new (Millivolts (f64::NAN)).is_err ());
+    }
+
+    #[test]
+    fn encodes_commands () {
+        let commands = [(Command::Read, "R"),
+                        (Command::calibrate (225.0).unwrap (), "Cal,225.0"),
+                        (Command::calibrate (-87.5).unwrap (), "Cal,-87.5"),
+                        (Command::Common (CommonCommand::CalibrationClear), "Cal,clear"),
+                        (Command::Common (CommonCommand::CalibrationQuery), "Cal,?"),
+                        (CommonCommand::Find.into (), "Find")];
+
+        for &(ref command, encoded) in commands.iter () {
+            assert_eq! (command.encode (), encoded.as_bytes ());
+            assert_eq! (&Command::parse (encoded.as_bytes ()).unwrap (), command);
+        }
+
+        assert_eq! (Command::parse ("cal,225\0".as_bytes ()).unwrap (), Command::Calibrate (Millivolts (225.0)));
+    }
+
+    #[test]
+    fn invalid_commands_yield_error () {
+        assert! (Command::calibrate (2500.0).is_err ());
+
+        assert! (Command::parse ("Cal".as_bytes ()).is_err ());
+        assert! (Command::parse ("Cal,-2001".as_bytes ()).is_err ());
+        assert! (Command::parse ("Cal,mid,225".as_bytes ()).is_err ());
+        assert! (Command::parse ("T,25".as_bytes ()).is_err ());
+    }
+
+    #[test]
+    fn commands_have_processing_delay () {
+        assert_eq! (Command::Read.delay (), Duration::from_millis (900));
+        assert_eq! (Command::calibrate (225.0).unwrap ().delay (), Duration::from_millis (900));
+        assert_eq! (Command::Common (CommonCommand::Status).delay (), Duration::from_millis (300));
+    }
+
+    #[test]
+    fn displays_responses () {
+        assert_eq! (OrpResponse::parse ("225.3\0".as_bytes ()).unwrap ().to_string (), "potential is 225.3 mV");
+        assert_eq! (CalibrationStateResponse (CalibrationState::Calibrated).to_string (), "device is calibrated");
+    }
+
+    #[test]
+    fn displays_commands () {
+        assert_eq! (Command::Read.to_string (), "take a reading");
+        assert_eq! (CalibrationCommand::new (Millivolts (225.0)).unwrap ().to_string (), "calibrate at 225 mV");
+        assert_eq! (Command::Common (CommonCommand::Find).to_string (), "blink LED to find device");
+    }
+}
@@ -0,0 +1,723 @@
+//! Responses and commands for the EZO pH circuit
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use errors::*;
+use framing::{format_decimal, parse_number, SplitCommand};
+use common::validate_compensation_temperature;
+use {is_acknowledgment, parse_field, str_from_response, CommonCommand, ParseOptions, Temperature};
+
+/// A pH value, between 0 and 14
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Ph (f64);
+
+impl Ph {
+    /// Validates a pH value; it must be between 0 and 14, and not NaN.
+    pub fn new (ph: f64) -> Result<Ph> {
+        if (0.0 ..= 14.0).contains (&ph) {
+            Ok (Ph (ph))
+        } else {
+            Err (ErrorKind::ValueOutOfRange ("pH must be between 0 and 14").into ())
+        }
+    }
+
+    pub fn value (&self) -> f64 {
+        self.0
+    }
+}
+
+impl fmt::Display for Ph {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "pH {:.3}", self.0)
+    }
+}
+
+/// Response from the "R" command to take a pH reading
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PhResponse (pub Ph);
+
+impl PhResponse {
+    /// Parses the result of the "R" command to get a pH reading.  The "RT,n"
+    /// command gives the same response.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<PhResponse> {
+        let r = options.body (response)?;
+        let val = f64::from_str (r).chain_err (|| ErrorKind::ResponseParse)?;
+        let ph = Ph::new (val).chain_err (|| ErrorKind::ResponseParse)?;
+
+        Ok (PhResponse (ph))
+    }
+}
+
+impl fmt::Display for PhResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "reading is {}", self.0)
+    }
+}
+
+/// Response from the "Slope,?" command to query how closely the probe
+/// matches an ideal one
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SlopeResponse {
+    /// Percentage of the ideal slope in the acid range
+    pub acid: f64,
+    /// Percentage of the ideal slope in the base range
+    pub base: f64,
+    /// Offset of the zero point in millivolts; only reported by newer firmware
+    pub zero_point_offset: Option<f64>
+}
+
+impl SlopeResponse {
+    /// Parses the result of the "Slope,?" command.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<SlopeResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?Slope,") {
+            let mut split = rest.split (',');
+
+            let acid = parse_field (split.next ())?;
+            let base = parse_field (split.next ())?;
+
+            let zero_point_offset = match split.next () {
+                Some (s) => Some (parse_field (Some (s))?),
+                None     => None
+            };
+
+            if split.next ().is_some () {
+                return Err (ErrorKind::ResponseParse.into ());
+            }
+
+            Ok (SlopeResponse {
+                acid,
+                base,
+                zero_point_offset
+            })
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for SlopeResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "slope is {}% in the acid range and {}% in the base range", self.acid, self.base)?;
+
+        if let Some (offset) = self.zero_point_offset {
+            write! (f, ", zero point offset is {} mV", offset)?;
+        }
+
+        Ok (())
+    }
+}
+
+/// Response from the "T,?" command to query the temperature which the
+/// circuit uses to compensate its readings.  The circuit always uses Celsius.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CompensationTemperatureResponse (pub Temperature);
+
+impl CompensationTemperatureResponse {
+    /// Parses the result of the "T,?" command.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<CompensationTemperatureResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?T,") {
+            let val = parse_field (Some (rest))?;
+            Ok (CompensationTemperatureResponse (Temperature::Celsius (val)))
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for CompensationTemperatureResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "compensation temperature is {}", self.0)
+    }
+}
+
+/// Number of points at which the pH probe has been calibrated
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum CalibrationState {
+    Uncalibrated,
+    OnePoint,
+    TwoPoint,
+    ThreePoint
+}
+
+impl fmt::Display for CalibrationState {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CalibrationState::Uncalibrated => write! (f, "uncalibrated"),
+            CalibrationState::OnePoint     => write! (f, "calibrated at one point"),
+            CalibrationState::TwoPoint     => write! (f, "calibrated at two points"),
+            CalibrationState::ThreePoint   => write! (f, "calibrated at three points")
+        }
+    }
+}
+
+/// Response from the "Cal,?" command to query the pH circuit's calibration
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CalibrationStateResponse (pub CalibrationState);
+
+impl CalibrationStateResponse {
+    /// Parses the result of the "Cal,?" command to query how many
+    /// calibration points the pH circuit has.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<CalibrationStateResponse> {
+        let r = options.body (response)?;
+
+        match options.strip_keyword (r, "?Cal,") {
+            Some ("0") => Ok (CalibrationStateResponse (CalibrationState::Uncalibrated)),
+            Some ("1") => Ok (CalibrationStateResponse (CalibrationState::OnePoint)),
+            Some ("2") => Ok (CalibrationStateResponse (CalibrationState::TwoPoint)),
+            Some ("3") => Ok (CalibrationStateResponse (CalibrationState::ThreePoint)),
+            _ => Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for CalibrationStateResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "device is {}", self.0)
+    }
+}
+
+/// Commands which can be sent to the EZO pH circuit
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Command {
+    /// "R", take a pH reading
+    Read,
+    /// "Cal,mid,n", calibrate the midpoint; this must be done first
+    CalibrateMid (Ph),
+    /// "Cal,low,n", calibrate the low point
+    CalibrateLow (Ph),
+    /// "Cal,high,n", calibrate the high point
+    CalibrateHigh (Ph),
+    /// "T,n", set the temperature in Celsius used to compensate readings
+    SetCompensationTemperature (f64),
+    /// "RT,n", set the compensation temperature in Celsius and take a pH
+    /// reading, in a single round trip
+    ReadCompensated (f64),
+    /// "T,?", query the compensation temperature
+    CompensationTemperatureQuery,
+    /// "Slope,?", query the probe's slope
+    SlopeQuery,
+    /// A command which is common to all the EZO circuits
+    Common (CommonCommand)
+}
+
+fn calibration_ph (ph: f64) -> Result<Ph> {
+    Ph::new (ph).chain_err (|| ErrorKind::InvalidCommandParameter ("pH must be between 0 and 14"))
+}
+
+impl Command {
+    /// Creates a command to calibrate the midpoint at the given pH, which
+    /// must be between 0 and 14.
+    pub fn calibrate_mid (ph: f64) -> Result<Command> {
+        Ok (Command::CalibrateMid (calibration_ph (ph)?))
+    }
+
+    /// Creates a command to calibrate the low point at the given pH, which
+    /// must be between 0 and 14.
+    pub fn calibrate_low (ph: f64) -> Result<Command> {
+        Ok (Command::CalibrateLow (calibration_ph (ph)?))
+    }
+
+    /// Creates a command to calibrate the high point at the given pH, which
+    /// must be between 0 and 14.
+    pub fn calibrate_high (ph: f64) -> Result<Command> {
+        Ok (Command::CalibrateHigh (calibration_ph (ph)?))
+    }
+
+    /// Creates a command to set the compensation temperature in Celsius.
+    /// This must be within the range of the EZO RTD's probe, so that its
+    /// readings can be passed directly.
+    pub fn set_compensation_temperature (temperature: f64) -> Result<Command> {
+        Ok (Command::SetCompensationTemperature (validate_compensation_temperature (temperature)?))
+    }
+
+    /// Creates a command to set the compensation temperature in Celsius and
+    /// take a reading at the same time.  The temperature has the same range
+    /// as for `set_compensation_temperature()`.
+    pub fn read_compensated (temperature: f64) -> Result<Command> {
+        Ok (Command::ReadCompensated (validate_compensation_temperature (temperature)?))
+    }
+
+    /// Parses a command string as it would be sent to the device, like
+    /// "Cal,mid,7.00" or "T,?", in the same way as `saiba_parser::Command::parse()`.
+    pub fn parse (command: &[u8]) -> Result<Command> {
+        let split = SplitCommand::new (command)?;
+
+        if let Some (common) = CommonCommand::from_split (&split)? {
+            return Ok (Command::Common (common));
+        }
+
+        let mut args = split.keyword.as_ref ().map (|k| k.splitn (2, ','));
+        let first = args.as_mut ().and_then (|a| a.next ());
+        let second = args.as_mut ().and_then (|a| a.next ());
+
+        let command = match (split.name.as_str (), first, second) {
+            ("r", None, None)                 => Command::Read,
+            ("cal", Some ("mid"), Some (n))   => Command::calibrate_mid (parse_number (n)?)?,
+            ("cal", Some ("low"), Some (n))   => Command::calibrate_low (parse_number (n)?)?,
+            ("cal", Some ("high"), Some (n))  => Command::calibrate_high (parse_number (n)?)?,
+            ("rt", Some (n), None)            => Command::read_compensated (parse_number (n)?)?,
+            ("t", Some ("?"), None)           => Command::CompensationTemperatureQuery,
+            ("t", Some (n), None)             => Command::set_compensation_temperature (parse_number (n)?)?,
+            ("slope", Some ("?"), None)       => Command::SlopeQuery,
+            _                                 => return Err (ErrorKind::CommandParse.into ())
+        };
+
+        Ok (command)
+    }
+
+    /// Returns how long to wait after sending this command before reading
+    /// the device's response over I2C.
+    pub fn delay (&self) -> Duration {
+        match *self {
+            Command::Read
+                | Command::ReadCompensated (_)
+                | Command::CalibrateMid (_)
+                | Command::CalibrateLow (_)
+                | Command::CalibrateHigh (_) => Duration::from_millis (900),
+
+            Command::Common (ref common) => common.delay (),
+            _                            => Duration::from_millis (300)
+        }
+    }
+
+    /// Returns the bytes to send to the device for this command.  In I2C
+    /// mode these are sent as-is; in UART mode, the caller must append a
+    /// carriage return.
+    pub fn encode (&self) -> Vec<u8> {
+        let s = match *self {
+            Command::Read                               => "R".to_string (),
+            Command::CalibrateMid (ph)                  => format! ("Cal,mid,{}", format_decimal (ph.0)),
+            Command::CalibrateLow (ph)                  => format! ("Cal,low,{}", format_decimal (ph.0)),
+            Command::CalibrateHigh (ph)                 => format! ("Cal,high,{}", format_decimal (ph.0)),
+            Command::SetCompensationTemperature (t)     => format! ("T,{}", format_decimal (t)),
+            Command::ReadCompensated (t)                => format! ("RT,{}", format_decimal (t)),
+            Command::CompensationTemperatureQuery       => "T,?".to_string (),
+            Command::SlopeQuery                         => "Slope,?".to_string (),
+            Command::Common (ref common)                => return common.encode ()
+        };
+
+        s.into_bytes ()
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Command::Read                           => write! (f, "take a reading"),
+            Command::CalibrateMid (ph)              => write! (f, "calibrate midpoint at {}", ph),
+            Command::CalibrateLow (ph)              => write! (f, "calibrate low point at {}", ph),
+            Command::CalibrateHigh (ph)             => write! (f, "calibrate high point at {}", ph),
+            Command::SetCompensationTemperature (t) => write! (f, "set compensation temperature to {} °C", format_decimal (t)),
+            Command::ReadCompensated (t)            => write! (f, "take a reading compensated at {} °C", format_decimal (t)),
+            Command::CompensationTemperatureQuery   => write! (f, "query compensation temperature"),
+            Command::SlopeQuery                     => write! (f, "query probe slope"),
+            Command::Common (ref common)            => common.fmt (f)
+        }
+    }
+}
+
+impl From<CommonCommand> for Command {
+    fn from (c: CommonCommand) -> Command {
+        Command::Common (c)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum CalibrationStep {
+    Mid,
+    Low,
+    High,
+    Slope,
+    Done
+}
+
+/// Sequences the commands to calibrate the pH probe.
+///
+/// The circuit requires the midpoint to be calibrated first, since doing so
+/// clears the low and high points; this sends "Cal,mid,n", then the
+/// optional "Cal,low,n" and "Cal,high,n", and finally "Slope,?" to verify
+/// the probe if more than one point was calibrated.  Use `next_command()`
+/// and `handle_response()` in the same way as for
+/// `saiba_parser::CalibrationBackup`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhCalibrationSession {
+    mid: Ph,
+    low: Option<Ph>,
+    high: Option<Ph>,
+    step: CalibrationStep,
+    slope: Option<SlopeResponse>
+}
+
+impl PhCalibrationSession {
+    /// Creates a calibration session with the pH of each calibration
+    /// solution.  The low point must be below the midpoint, and the high
+    /// point above it.
+    pub fn new (mid: Ph, low: Option<Ph>, high: Option<Ph>) -> Result<PhCalibrationSession> {
+        if low.map_or (false, |low| low >= mid) || high.map_or (false, |high| high <= mid) {
+            return Err (ErrorKind::InvalidCommandParameter ("calibration points must be ordered low < mid < high").into ());
+        }
+
+        Ok (PhCalibrationSession {
+            mid,
+            low,
+            high,
+            step: CalibrationStep::Mid,
+            slope: None
+        })
+    }
+
+    fn following (&self, step: CalibrationStep) -> CalibrationStep {
+        match step {
+            CalibrationStep::Mid if self.low.is_some ()  => CalibrationStep::Low,
+            CalibrationStep::Mid if self.high.is_some () => CalibrationStep::High,
+            CalibrationStep::Mid                         => CalibrationStep::Done,
+            CalibrationStep::Low if self.high.is_some () => CalibrationStep::High,
+            CalibrationStep::Low | CalibrationStep::High => CalibrationStep::Slope,
+            CalibrationStep::Slope | CalibrationStep::Done => CalibrationStep::Done
+        }
+    }
+
+    /// Returns the next command to send, or `None` if the calibration is complete.
+    pub fn next_command (&self) -> Option<Command> {
+        match self.step {
+            CalibrationStep::Mid   => Some (Command::CalibrateMid (self.mid)),
+            CalibrationStep::Low   => self.low.map (Command::CalibrateLow),
+            CalibrationStep::High  => self.high.map (Command::CalibrateHigh),
+            CalibrationStep::Slope => Some (Command::SlopeQuery),
+            CalibrationStep::Done  => None
+        }
+    }
+
+    /// Parses the device's response to the command from `next_command()`.
+    /// If this returns an error, the session does not advance, and the same
+    /// command can be retried.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn handle_response (&mut self, response: &[u8]) -> Result<()> {
+        match self.step {
+            CalibrationStep::Done  => return Err (ErrorKind::CalibrationSequence.into ()),
+            CalibrationStep::Slope => self.slope = Some (SlopeResponse::parse (response)?),
+
+            _ => {
+                if !is_acknowledgment (str_from_response (response)?, &ParseOptions::default ()) {
+                    return Err (ErrorKind::ResponseParse.into ());
+                }
+            }
+        }
+
+        self.step = self.following (self.step);
+        Ok (())
+    }
+
+    /// Whether all the calibration commands have been acknowledged.
+    pub fn is_done (&self) -> bool {
+        self.step == CalibrationStep::Done
+    }
+
+    /// Returns the probe's slope as reported by the device after
+    /// calibration, or `None` if it has not been queried yet or if only the
+    /// midpoint was calibrated.
+    pub fn slope (&self) -> Option<SlopeResponse> {
+        self.slope
+    }
+}
+
+response_parse! (PhResponse, SlopeResponse, CompensationTemperatureResponse,
+                 CalibrationStateResponse);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ph_response () {
+        let response = "9.560\0".as_bytes ();
+        assert_eq! (PhResponse::parse (response).unwrap (),
+                    PhResponse (Ph::new (9.56).unwrap ()));
+
+        let response = "0.000\0".as_bytes ();
+        assert_eq! (PhResponse::parse (response).unwrap (),
+                    PhResponse (Ph::new (0.0).unwrap ()));
+
+        let response = "14.000\r".as_bytes ();
+        assert_eq! (PhResponse::parse (response).unwrap (),
+                    PhResponse (Ph::new (14.0).unwrap ()));
+    }
+
+    #[test]
+    fn parsing_invalid_ph_response_yields_error () {
+        let response = "\0".as_bytes ();
+        assert! (PhResponse::parse (response).is_err ());
+
+        let response = "-0.5\0".as_bytes ();
+        assert! (PhResponse::parse (response).is_err ());
+
+        let response = "14.01\0".as_bytes ();
+        assert! (PhResponse::parse (response).is_err ());
+
+        let response = "NaN\0".as_bytes ();
+        assert! (PhResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_slope_response () {
+        let response = "?Slope,99.7,100.3\0".as_bytes ();
+        assert_eq! (SlopeResponse::parse (response).unwrap (),
+                    SlopeResponse {
+                        acid: 99.7,
+                        base: 100.3,
+                        zero_point_offset: None
+                    });
+
+        let response = "?Slope,99.7,100.3,-0.89\0".as_bytes ();
+        assert_eq! (SlopeResponse::parse (response).unwrap (),
+                    SlopeResponse {
+                        acid: 99.7,
+                        base: 100.3,
+                        zero_point_offset: Some (-0.89)
+                    });
+    }
+
+    #[test]
+    fn parsing_invalid_slope_response_yields_error () {
+        let response = "?Slope,\0".as_bytes ();
+        assert! (SlopeResponse::parse (response).is_err ());
+
+        let response = "?Slope,99.7\0".as_bytes ();
+        assert! (SlopeResponse::parse (response).is_err ());
+
+        let response = "?Slope,99.7,x\0".as_bytes ();
+        assert! (SlopeResponse::parse (response).is_err ());
+
+        let response = "?Slope,99.7,100.3,\0".as_bytes ();
+        assert! (SlopeResponse::parse (response).is_err ());
+
+        let response = "?Slope,99.7,100.3,-0.89,1\0".as_bytes ();
+        assert! (SlopeResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_compensation_temperature_response () {
+        let response = "?T,19.5\0".as_bytes ();
+        assert_eq! (CompensationTemperatureResponse::parse (response).unwrap (),
+                    CompensationTemperatureResponse (Temperature::Celsius (19.5)));
+
+        let response = "?T,25.0\0".as_bytes ();
+        assert_eq! (CompensationTemperatureResponse::parse (response).unwrap (),
+                    CompensationTemperatureResponse (Temperature::Celsius (25.0)));
+    }
+
+    #[test]
+    fn parsing_invalid_compensation_temperature_response_yields_error () {
+        let response = "?T,\0".as_bytes ();
+        assert! (CompensationTemperatureResponse::parse (response).is_err ());
+
+        let response = "?T,warm\0".as_bytes ();
+        assert! (CompensationTemperatureResponse::parse (response).is_err ());
+
+        let response = "19.5\0".as_bytes ();
+        assert! (CompensationTemperatureResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_calibration_state_response () {
+        let response = "?Cal,0\0".as_bytes ();
+        assert_eq! (CalibrationStateResponse::parse (response).unwrap (),
+                    CalibrationStateResponse (CalibrationState::Uncalibrated));
+
+        let response = "?Cal,1\0".as_bytes ();
+        assert_eq! (CalibrationStateResponse::parse (response).unwrap (),
+                    CalibrationStateResponse (CalibrationState::OnePoint));
+
+        let response = "?Cal,2\0".as_bytes ();
+        assert_eq! (CalibrationStateResponse::parse (response).unwrap (),
+                    CalibrationStateResponse (CalibrationState::TwoPoint));
+
+        let response = "?Cal,3\0".as_bytes ();
+        assert_eq! (CalibrationStateResponse::parse (response).unwrap (),
+                    CalibrationStateResponse (CalibrationState::ThreePoint));
+    }
+
+    #[test]
+    fn parsing_invalid_calibration_state_response_yields_error () {
+        let response = "?Cal,\0".as_bytes ();
+        assert! (CalibrationStateResponse::parse (response).is_err ());
+
+        let response = "?Cal,4\0".as_bytes ();
+        assert! (CalibrationStateResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn encodes_commands () {
+        let commands = [(Command::Read, "R"),
+                        (Command::calibrate_mid (7.0).unwrap (), "Cal,mid,7.0"),
+                        (Command::calibrate_low (4.0).unwrap (), "Cal,low,4.0"),
+                        (Command::calibrate_high (10.0).unwrap (), "Cal,high,10.0"),
+                        (Command::set_compensation_temperature (19.5).unwrap (), "T,19.5"),
+                        (Command::read_compensated (21.25).unwrap (), "RT,21.25"),
+                        (Command::CompensationTemperatureQuery, "T,?"),
+                        (Command::SlopeQuery, "Slope,?"),
+                        (Command::Common (CommonCommand::CalibrationClear), "Cal,clear"),
+                        (CommonCommand::Find.into (), "Find")];
+
+        for &(ref command, encoded) in commands.iter () {
+            assert_eq! (command.encode (), encoded.as_bytes ());
+            assert_eq! (&Command::parse (encoded.as_bytes ()).unwrap (), command);
+        }
+
+        assert_eq! (Command::parse ("cal,MID,7.00".as_bytes ()).unwrap (), Command::CalibrateMid (Ph::new (7.0).unwrap ()));
+    }
+
+    #[test]
+    fn invalid_commands_yield_error () {
+        assert! (Command::calibrate_mid (-1.0).is_err ());
+        assert! (Command::calibrate_low (14.5).is_err ());
+        assert! (Command::calibrate_high (f64::NAN).is_err ());
+        assert! (Command::set_compensation_temperature (2000.0).is_err ());
+        assert! (Command::read_compensated (-200.0).is_err ());
+
+        assert! (Command::parse ("Cal,mid".as_bytes ()).is_err ());
+        assert! (Command::parse ("Cal,mid,15".as_bytes ()).is_err ());
+        assert! (Command::parse ("Cal,7.00".as_bytes ()).is_err ());
+        assert! (Command::parse ("S,c".as_bytes ()).is_err ());
+        assert! (Command::parse ("RT".as_bytes ()).is_err ());
+        assert! (Command::parse ("RT,?".as_bytes ()).is_err ());
+    }
+
+    #[test]
+    fn commands_have_processing_delay () {
+        assert_eq! (Command::Read.delay (), Duration::from_millis (900));
+        assert_eq! (Command::CalibrateMid (Ph::new (7.0).unwrap ()).delay (), Duration::from_millis (900));
+        assert_eq! (Command::read_compensated (25.0).unwrap ().delay (), Duration::from_millis (900));
+        assert_eq! (Command::SlopeQuery.delay (), Duration::from_millis (300));
+        assert_eq! (Command::Common (CommonCommand::Status).delay (), Duration::from_millis (300));
+    }
+
+    #[test]
+    fn validates_ph () {
+        assert_eq! (Ph::new (0.0).unwrap ().value (), 0.0);
+        assert_eq! (Ph::new (14.0).unwrap ().value (), 14.0);
+
+        assert! (Ph::new (-0.001).is_err ());
+        assert! (Ph::new (14.001).is_err ());
+        assert! (Ph::new (f64::NAN).is_err ());
+        assert! (Ph::new (f64::INFINITY).is_err ());
+    }
+
+    #[test]
+    fn compares_and_displays_ph () {
+        assert! (Ph::new (4.0).unwrap () < Ph::new (7.0).unwrap ());
+        assert! (Ph::new (10.0).unwrap () > Ph::new (7.0).unwrap ());
+        assert_eq! (Ph::new (7.0).unwrap ().to_string (), "pH 7.000");
+    }
+
+    fn ph (value: f64) -> Ph {
+        Ph::new (value).unwrap ()
+    }
+
+    #[test]
+    fn calibration_session_runs_three_points () {
+        let mut session = PhCalibrationSession::new (ph (7.0), Some (ph (4.0)), Some (ph (10.0))).unwrap ();
+
+        assert_eq! (session.next_command (), Some (Command::CalibrateMid (ph (7.0))));
+        session.handle_response ("\0".as_bytes ()).unwrap ();
+
+        assert_eq! (session.next_command (), Some (Command::CalibrateLow (ph (4.0))));
+        session.handle_response ("*OK\r".as_bytes ()).unwrap ();
+
+        assert_eq! (session.next_command (), Some (Command::CalibrateHigh (ph (10.0))));
+        session.handle_response ("\0".as_bytes ()).unwrap ();
+
+        assert_eq! (session.next_command (), Some (Command::SlopeQuery));
+        assert! (!session.is_done ());
+        session.handle_response ("?Slope,99.7,100.3\0".as_bytes ()).unwrap ();
+
+        assert! (session.is_done ());
+        assert_eq! (session.next_command (), None);
+        assert_eq! (session.slope (), Some (SlopeResponse { acid: 99.7, base: 100.3, zero_point_offset: None }));
+
+        match *session.handle_response ("\0".as_bytes ()).unwrap_err ().kind () {
+            ErrorKind::CalibrationSequence => (),
+            _ => panic! ("should have gotten a CalibrationSequence error")
+        }
+    }
+
+    #[test]
+    fn calibration_session_skips_missing_points () {
+        let mut session = PhCalibrationSession::new (ph (7.0), None, None).unwrap ();
+        session.handle_response ("\0".as_bytes ()).unwrap ();
+        assert! (session.is_done ());
+        assert_eq! (session.slope (), None);
+
+        let mut session = PhCalibrationSession::new (ph (7.0), None, Some (ph (10.0))).unwrap ();
+        session.handle_response ("\0".as_bytes ()).unwrap ();
+        assert_eq! (session.next_command (), Some (Command::CalibrateHigh (ph (10.0))));
+        session.handle_response ("\0".as_bytes ()).unwrap ();
+        assert_eq! (session.next_command (), Some (Command::SlopeQuery));
+    }
+
+    #[test]
+    fn calibration_session_retries_after_bad_response () {
+        let mut session = PhCalibrationSession::new (ph (7.0), Some (ph (4.0)), None).unwrap ();
+
+        assert! (session.handle_response ("?Cal,1\0".as_bytes ()).is_err ());
+        assert_eq! (session.next_command (), Some (Command::CalibrateMid (ph (7.0))));
+
+        session.handle_response ("\0".as_bytes ()).unwrap ();
+        session.handle_response ("\0".as_bytes ()).unwrap ();
+
+        assert! (session.handle_response ("?Slope,99.7\0".as_bytes ()).is_err ());
+        assert_eq! (session.next_command (), Some (Command::SlopeQuery));
+    }
+
+    #[test]
+    fn calibration_session_validates_point_order () {
+        assert! (PhCalibrationSession::new (ph (7.0), Some (ph (7.0)), None).is_err ());
+        assert! (PhCalibrationSession::new (ph (7.0), Some (ph (10.0)), None).is_err ());
+        assert! (PhCalibrationSession::new (ph (7.0), None, Some (ph (4.0))).is_err ());
+    }
+
+    #[test]
+    fn displays_responses () {
+        assert_eq! (PhResponse::parse ("7.012\0".as_bytes ()).unwrap ().to_string (), "reading is pH 7.012");
+        assert_eq! (SlopeResponse::parse ("?Slope,99.7,100.3,-0.89\0".as_bytes ()).unwrap ().to_string (),
+                    "slope is 99.7% in the acid range and 100.3% in the base range, zero point offset is -0.89 mV");
+        assert_eq! (SlopeResponse::parse ("?Slope,99.7,100.3\0".as_bytes ()).unwrap ().to_string (),
+                    "slope is 99.7% in the acid range and 100.3% in the base range");
+        assert_eq! (CompensationTemperatureResponse (Temperature::Celsius (19.5)).to_string (),
+                    "compensation temperature is 19.5 °C");
+        assert_eq! (CalibrationStateResponse (CalibrationState::TwoPoint).to_string (),
+                    "device is calibrated at two points");
+    }
+
+    #[test]
+    fn displays_commands () {
+        assert_eq! (Command::Read.to_string (), "take a reading");
+        assert_eq! (Command::calibrate_mid (7.0).unwrap ().to_string (), "calibrate midpoint at pH 7.000");
+        assert_eq! (Command::read_compensated (19.5).unwrap ().to_string (),
+                    "take a reading compensated at 19.5 °C");
+        assert_eq! (Command::SlopeQuery.to_string (), "query probe slope");
+        assert_eq! (Command::Common (CommonCommand::Find).to_string (), "blink LED to find device");
+    }
+}
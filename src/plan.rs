@@ -0,0 +1,124 @@
+use std::slice;
+use std::time::Duration;
+use std::vec;
+
+use Command;
+
+/// A command in a `CommandPlan`, along with how long to wait for its
+/// response and how many times to retry it if it fails
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedCommand {
+    pub command: Command,
+    pub delay: Duration,
+    pub retries: u32
+}
+
+/// A sequence of commands to send to the device in order.
+///
+/// Each command is annotated with the delay the driver must wait before
+/// reading the response, which defaults to `Command::delay()`, and with the
+/// number of times it may be retried.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CommandPlan {
+    steps: Vec<PlannedCommand>,
+    retries: u32
+}
+
+impl CommandPlan {
+    pub fn new () -> CommandPlan {
+        CommandPlan::default ()
+    }
+
+    /// Sets the number of retries for the commands which are added after
+    /// this.  The default is 0.
+    pub fn retries (mut self, retries: u32) -> CommandPlan {
+        self.retries = retries;
+        self
+    }
+
+    /// Adds a command to the plan, with its default delay.
+    pub fn then (self, command: Command) -> CommandPlan {
+        let delay = command.delay ();
+        self.then_with_delay (command, delay)
+    }
+
+    /// Adds a command to the plan, with a custom delay before reading
+    /// its response.
+    pub fn then_with_delay (mut self, command: Command, delay: Duration) -> CommandPlan {
+        let retries = self.retries;
+
+        self.steps.push (PlannedCommand {
+            command,
+            delay,
+            retries
+        });
+
+        self
+    }
+
+    /// The commands in the plan, in order.
+    pub fn steps (&self) -> &[PlannedCommand] {
+        &self.steps
+    }
+
+    /// The sum of the delays of all the commands, i.e. the minimum time
+    /// needed to execute the plan if no retries are needed.
+    pub fn total_delay (&self) -> Duration {
+        self.steps.iter ().fold (Duration::from_millis (0), |total, step| total + step.delay)
+    }
+}
+
+impl IntoIterator for CommandPlan {
+    type Item = PlannedCommand;
+    type IntoIter = vec::IntoIter<PlannedCommand>;
+
+    fn into_iter (self) -> Self::IntoIter {
+        self.steps.into_iter ()
+    }
+}
+
+impl<'a> IntoIterator for &'a CommandPlan {
+    type Item = &'a PlannedCommand;
+    type IntoIter = slice::Iter<'a, PlannedCommand>;
+
+    fn into_iter (self) -> Self::IntoIter {
+        self.steps.iter ()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TemperatureScale;
+
+    #[test]
+    fn builds_command_plan () {
+        let plan = CommandPlan::new ()
+            .then (Command::SetScale (TemperatureScale::Kelvin))
+            .retries (3)
+            .then (Command::ScaleQuery)
+            .then_with_delay (Command::Read, Duration::from_millis (1000));
+
+        assert_eq! (plan.steps (),
+                    &[PlannedCommand {
+                        command: Command::SetScale (TemperatureScale::Kelvin),
+                        delay: Duration::from_millis (300),
+                        retries: 0
+                    },
+                    PlannedCommand {
+                        command: Command::ScaleQuery,
+                        delay: Duration::from_millis (300),
+                        retries: 3
+                    },
+                    PlannedCommand {
+                        command: Command::Read,
+                        delay: Duration::from_millis (1000),
+                        retries: 3
+                    }]);
+
+        assert_eq! (plan.total_delay (), Duration::from_millis (1600));
+
+        let commands: Vec<Command> = plan.into_iter ().map (|step| step.command).collect ();
+        assert_eq! (commands, vec! [Command::SetScale (TemperatureScale::Kelvin), Command::ScaleQuery, Command::Read]);
+    }
+}
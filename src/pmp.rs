@@ -0,0 +1,758 @@
+//! Responses and commands for the EZO PMP (peristaltic pump) circuit
+
+use std::fmt;
+use std::time::Duration;
+
+use errors::*;
+use framing::{format_decimal, parse_number, SplitCommand};
+use {parse_field, CommonCommand, ParseOptions};
+
+/// A volume in milliliters.  Volumes dispensed in reverse are negative.
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Milliliters (pub f64);
+
+impl fmt::Display for Milliliters {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{} ml", self.0)
+    }
+}
+
+/// Response from the "Dispensed,?" command to query the volume dispensed by
+/// the current or last dose
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DispensedResponse {
+    pub volume: Milliliters,
+    /// Whether the pump has finished dispensing, as signaled by "*DD"
+    pub done: bool
+}
+
+impl DispensedResponse {
+    /// Parses the result of the "Dispensed,?" command, like
+    /// "?Dispensed,12.5".  Once the dose is finished the pump may append a
+    /// "*DD" marker, like "?Dispensed,25,*DD".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<DispensedResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?Dispensed,") {
+            let mut split = rest.split (',');
+
+            let volume = Milliliters (parse_field (split.next ())?);
+
+            let done = match split.next () {
+                Some (s) if options.matches (s, "*DD") => true,
+                None                                   => false,
+                _                                      => return Err (ErrorKind::ResponseParse.into ())
+            };
+
+            if split.next ().is_some () {
+                return Err (ErrorKind::ResponseParse.into ());
+            }
+
+            Ok (DispensedResponse {
+                volume,
+                done
+            })
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for DispensedResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.done {
+            write! (f, "pump dispensed {}", self.volume)
+        } else {
+            write! (f, "pump has dispensed {} so far", self.volume)
+        }
+    }
+}
+
+/// Response from the "TV,?" command to query the total volume which the
+/// pump has dispensed since it was last cleared.  Volumes dispensed in
+/// reverse are subtracted from this total.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TotalVolumeResponse (pub Milliliters);
+
+impl TotalVolumeResponse {
+    /// Parses the result of the "TV,?" command, like "?TV,250.5".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<TotalVolumeResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?TV,") {
+            Ok (TotalVolumeResponse (Milliliters (parse_field (Some (rest))?)))
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for TotalVolumeResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "total volume is {}", self.0)
+    }
+}
+
+/// Response from the "ATV,?" command to query the absolute total volume
+/// which the pump has dispensed since it was last cleared.  Unlike "TV,?",
+/// volumes dispensed in reverse are added to this total, so it is never
+/// negative.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AbsoluteTotalVolumeResponse (pub Milliliters);
+
+impl AbsoluteTotalVolumeResponse {
+    /// Parses the result of the "ATV,?" command, like "?ATV,260.5".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<AbsoluteTotalVolumeResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?ATV,") {
+            let volume: f64 = parse_field (Some (rest))?;
+
+            if volume >= 0.0 {
+                Ok (AbsoluteTotalVolumeResponse (Milliliters (volume)))
+            } else {
+                Err (ErrorKind::ResponseParse.into ())
+            }
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for AbsoluteTotalVolumeResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "absolute total volume is {}", self.0)
+    }
+}
+
+/// Which of its dispensing modes the pump has been calibrated for.  The
+/// pump is calibrated separately by dispensing a fixed volume, and by
+/// dispensing a volume over a period of time.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum PmpCalibrationState {
+    Uncalibrated,
+    Volume,
+    VolumeOverTime,
+    VolumeAndVolumeOverTime
+}
+
+impl fmt::Display for PmpCalibrationState {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PmpCalibrationState::Uncalibrated            => write! (f, "uncalibrated"),
+            PmpCalibrationState::Volume                  => write! (f, "calibrated for volume"),
+            PmpCalibrationState::VolumeOverTime          => write! (f, "calibrated for volume over time"),
+            PmpCalibrationState::VolumeAndVolumeOverTime => write! (f, "calibrated for volume and volume over time")
+        }
+    }
+}
+
+/// Response from the "Cal,?" command to query the pump's calibration
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CalibrationStateResponse (pub PmpCalibrationState);
+
+impl CalibrationStateResponse {
+    /// Parses the result of the "Cal,?" command to query which dispensing
+    /// modes the pump has been calibrated for.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<CalibrationStateResponse> {
+        let r = options.body (response)?;
+
+        match options.strip_keyword (r, "?Cal,") {
+            Some ("0") => Ok (CalibrationStateResponse (PmpCalibrationState::Uncalibrated)),
+            Some ("1") => Ok (CalibrationStateResponse (PmpCalibrationState::Volume)),
+            Some ("2") => Ok (CalibrationStateResponse (PmpCalibrationState::VolumeOverTime)),
+            Some ("3") => Ok (CalibrationStateResponse (PmpCalibrationState::VolumeAndVolumeOverTime)),
+            _ => Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for CalibrationStateResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "pump is {}", self.0)
+    }
+}
+
+/// Whether the pump's current dose is paused
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum PauseState {
+    Running,
+    Paused
+}
+
+impl fmt::Display for PauseState {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PauseState::Running => write! (f, "running"),
+            PauseState::Paused  => write! (f, "paused")
+        }
+    }
+}
+
+/// Response from the "P,?" command to query whether the pump is paused
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PauseStateResponse (pub PauseState);
+
+impl PauseStateResponse {
+    /// Parses the result of the "P,?" command to query the pause state.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<PauseStateResponse> {
+        let r = options.body (response)?;
+
+        match options.strip_keyword (r, "?P,") {
+            Some ("0") => Ok (PauseStateResponse (PauseState::Running)),
+            Some ("1") => Ok (PauseStateResponse (PauseState::Paused)),
+            _ => Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for PauseStateResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "pump is {}", self.0)
+    }
+}
+
+/// Supply voltage below which the pump motor runs too slowly to dispense
+/// accurately
+pub const MIN_PUMP_VOLTAGE: f64 = 12.0;
+
+/// Response from the "PV,?" command to query the voltage which the pump
+/// motor is receiving
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PumpVoltageResponse (pub f64);
+
+impl PumpVoltageResponse {
+    /// Parses the result of the "PV,?" command, like "?PV,12.1".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<PumpVoltageResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?PV,") {
+            let voltage: f64 = parse_field (Some (rest))?;
+
+            if voltage >= 0.0 {
+                Ok (PumpVoltageResponse (voltage))
+            } else {
+                Err (ErrorKind::ResponseParse.into ())
+            }
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+
+    /// Whether the voltage is below `MIN_PUMP_VOLTAGE`.
+    pub fn is_low (&self) -> bool {
+        self.0 < MIN_PUMP_VOLTAGE
+    }
+}
+
+impl fmt::Display for PumpVoltageResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "pump voltage is {} V", self.0)
+    }
+}
+
+/// Response from the "DC,?" command to query the fastest flow rate, in
+/// milliliters per minute, at which the pump can dispense
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MaxFlowRateResponse (pub f64);
+
+impl MaxFlowRateResponse {
+    /// Parses the result of the "DC,?" command, like "?maxrate,105.5".  The
+    /// rate must be positive.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<MaxFlowRateResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?maxrate,") {
+            let rate: f64 = parse_field (Some (rest))?;
+
+            if rate > 0.0 && rate.is_finite () {
+                Ok (MaxFlowRateResponse (rate))
+            } else {
+                Err (ErrorKind::ResponseParse.into ())
+            }
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+
+    /// Whether the pump can dispense as fast as `command` requires.  This
+    /// is always true for commands other than `Command::DispenseOverTime`
+    /// and `Command::DispenseAtRate`.
+    pub fn allows (&self, command: &Command) -> bool {
+        match *command {
+            Command::DispenseOverTime (volume, minutes) => volume.0.abs () / f64::from (minutes) <= self.0,
+            Command::DispenseAtRate (rate, _)           => rate.abs () <= self.0,
+            _                                           => true
+        }
+    }
+}
+
+impl fmt::Display for MaxFlowRateResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "maximum flow rate is {} ml/min", self.0)
+    }
+}
+
+/// Largest volume, in milliliters, which the pump accepts in a single
+/// dispensing command, in either direction
+pub const MAX_DISPENSE_VOLUME: f64 = 99_999.0;
+
+/// Longest time, in minutes, over which the pump can spread a dose
+pub const MAX_DISPENSE_MINUTES: u32 = 1440;
+
+/// Direction in which the pump runs
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Direction {
+    Forward,
+    Reverse
+}
+
+impl fmt::Display for Direction {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Direction::Forward => write! (f, "forward"),
+            Direction::Reverse => write! (f, "in reverse")
+        }
+    }
+}
+
+/// Commands which can be sent to the EZO PMP circuit.  Throughout, negative
+/// volumes and flow rates run the pump in reverse.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Command {
+    /// "D,ml", dispense a volume
+    Dispense (Milliliters),
+    /// "D,ml,min", dispense a volume evenly over a number of minutes
+    DispenseOverTime (Milliliters, u32),
+    /// "DC,ml/min,min", dispense at a constant flow rate in milliliters per
+    /// minute for a number of minutes, or "DC,ml/min,*" until stopped
+    DispenseAtRate (f64, Option<u32>),
+    /// "D,*" or "D,-*", dispense until stopped
+    DispenseContinuously (Direction),
+    /// "X", stop dispensing
+    Stop,
+    /// "Dispensed,?", query the volume dispensed by the current or last dose
+    DispensedQuery,
+    /// "TV,?", query the total volume dispensed
+    TotalVolumeQuery,
+    /// "ATV,?", query the absolute total volume dispensed
+    AbsoluteTotalVolumeQuery,
+    /// "DC,?", query the maximum flow rate of the pump
+    MaxFlowRateQuery,
+    /// "P,?", query whether the pump is paused
+    PauseQuery,
+    /// "PV,?", query the voltage of the pump motor
+    PumpVoltageQuery,
+    /// A command which is common to all the EZO circuits
+    Common (CommonCommand)
+}
+
+fn validate_volume (volume: f64) -> Result<Milliliters> {
+    if volume != 0.0 && volume.abs () <= MAX_DISPENSE_VOLUME {
+        Ok (Milliliters (volume))
+    } else {
+        Err (ErrorKind::InvalidCommandParameter ("volume must be nonzero and at most 99999 ml in either direction").into ())
+    }
+}
+
+fn validate_minutes (minutes: u32) -> Result<u32> {
+    if (1 ..= MAX_DISPENSE_MINUTES).contains (&minutes) {
+        Ok (minutes)
+    } else {
+        Err (ErrorKind::InvalidCommandParameter ("dispensing time must be between 1 and 1440 minutes").into ())
+    }
+}
+
+impl Command {
+    /// Creates a command to dispense a volume in milliliters, which must
+    /// not be zero nor larger than `MAX_DISPENSE_VOLUME` in either direction.
+    pub fn dispense (volume: f64) -> Result<Command> {
+        Ok (Command::Dispense (validate_volume (volume)?))
+    }
+
+    /// Creates a command to dispense a volume in milliliters over a number
+    /// of minutes.  The volume is validated as in `Command::dispense()`, and
+    /// the minutes must be between 1 and `MAX_DISPENSE_MINUTES`.
+    pub fn dispense_over_time (volume: f64, minutes: u32) -> Result<Command> {
+        Ok (Command::DispenseOverTime (validate_volume (volume)?, validate_minutes (minutes)?))
+    }
+
+    /// Creates a command to dispense at a constant flow rate in milliliters
+    /// per minute, which must not be zero, either for a number of minutes
+    /// between 1 and `MAX_DISPENSE_MINUTES`, or until stopped if `minutes`
+    /// is `None`.
+    pub fn dispense_at_rate (rate: f64, minutes: Option<u32>) -> Result<Command> {
+        if rate == 0.0 || !rate.is_finite () {
+            return Err (ErrorKind::InvalidCommandParameter ("flow rate must be nonzero").into ());
+        }
+
+        let minutes = match minutes {
+            Some (m) => Some (validate_minutes (m)?),
+            None     => None
+        };
+
+        Ok (Command::DispenseAtRate (rate, minutes))
+    }
+
+    /// Parses a command string as it would be sent to the device, like
+    /// "D,10" or "DC,5,*", in the same way as `saiba_parser::Command::parse()`.
+    pub fn parse (command: &[u8]) -> Result<Command> {
+        let split = SplitCommand::new (command)?;
+
+        if let Some (common) = CommonCommand::from_split (&split)? {
+            return Ok (Command::Common (common));
+        }
+
+        let mut args = split.keyword.as_ref ().map (|k| k.splitn (2, ','));
+        let first = args.as_mut ().and_then (|a| a.next ());
+        let second = args.as_mut ().and_then (|a| a.next ());
+
+        let command = match (split.name.as_str (), first, second) {
+            ("d", Some ("*"), None)             => Command::DispenseContinuously (Direction::Forward),
+            ("d", Some ("-*"), None)            => Command::DispenseContinuously (Direction::Reverse),
+            ("d", Some (v), None)               => Command::dispense (parse_number (v)?)?,
+            ("d", Some (v), Some (m))           => Command::dispense_over_time (parse_number (v)?, parse_number (m)?)?,
+            ("dc", Some (r), Some ("*"))        => Command::dispense_at_rate (parse_number (r)?, None)?,
+            ("dc", Some (r), Some (m))          => Command::dispense_at_rate (parse_number (r)?, Some (parse_number (m)?))?,
+            ("dc", Some ("?"), None)            => Command::MaxFlowRateQuery,
+            ("x", None, None)                   => Command::Stop,
+            ("dispensed", Some ("?"), None)     => Command::DispensedQuery,
+            ("tv", Some ("?"), None)            => Command::TotalVolumeQuery,
+            ("atv", Some ("?"), None)           => Command::AbsoluteTotalVolumeQuery,
+            ("p", Some ("?"), None)             => Command::PauseQuery,
+            ("pv", Some ("?"), None)            => Command::PumpVoltageQuery,
+            _                                   => return Err (ErrorKind::CommandParse.into ())
+        };
+
+        Ok (command)
+    }
+
+    /// Returns how long to wait after sending this command before reading
+    /// the device's response over I2C.
+    pub fn delay (&self) -> Duration {
+        match *self {
+            Command::Common (ref common) => common.delay (),
+            _                            => Duration::from_millis (300)
+        }
+    }
+
+    /// Returns the bytes to send to the device for this command.  In I2C
+    /// mode these are sent as-is; in UART mode, the caller must append a
+    /// carriage return.
+    pub fn encode (&self) -> Vec<u8> {
+        let s = match *self {
+            Command::Dispense (volume)                  => format! ("D,{}", format_decimal (volume.0)),
+            Command::DispenseOverTime (volume, minutes) => format! ("D,{},{}", format_decimal (volume.0), minutes),
+            Command::DispenseAtRate (rate, Some (m))    => format! ("DC,{},{}", format_decimal (rate), m),
+            Command::DispenseAtRate (rate, None)        => format! ("DC,{},*", format_decimal (rate)),
+            Command::DispenseContinuously (Direction::Forward) => "D,*".to_string (),
+            Command::DispenseContinuously (Direction::Reverse) => "D,-*".to_string (),
+            Command::Stop                               => "X".to_string (),
+            Command::DispensedQuery                     => "Dispensed,?".to_string (),
+            Command::TotalVolumeQuery                   => "TV,?".to_string (),
+            Command::AbsoluteTotalVolumeQuery           => "ATV,?".to_string (),
+            Command::MaxFlowRateQuery                   => "DC,?".to_string (),
+            Command::PauseQuery                         => "P,?".to_string (),
+            Command::PumpVoltageQuery                   => "PV,?".to_string (),
+            Command::Common (ref common)                => return common.encode ()
+        };
+
+        s.into_bytes ()
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Command::Dispense (volume)                  => write! (f, "dispense {}", volume),
+            Command::DispenseOverTime (volume, minutes) => write! (f, "dispense {} over {} min", volume, minutes),
+            Command::DispenseAtRate (rate, Some (m))    => write! (f, "dispense at {} ml/min for {} min", format_decimal (rate), m),
+            Command::DispenseAtRate (rate, None)        => write! (f, "dispense at {} ml/min until stopped", format_decimal (rate)),
+            Command::DispenseContinuously (direction)   => write! (f, "dispense {} until stopped", direction),
+            Command::Stop                               => write! (f, "stop dispensing"),
+            Command::DispensedQuery                     => write! (f, "query dispensed volume"),
+            Command::TotalVolumeQuery                   => write! (f, "query total volume"),
+            Command::AbsoluteTotalVolumeQuery           => write! (f, "query absolute total volume"),
+            Command::MaxFlowRateQuery                   => write! (f, "query maximum flow rate"),
+            Command::PauseQuery                         => write! (f, "query pause state"),
+            Command::PumpVoltageQuery                   => write! (f, "query pump voltage"),
+            Command::Common (ref common)                => common.fmt (f)
+        }
+    }
+}
+
+impl From<CommonCommand> for Command {
+    fn from (c: CommonCommand) -> Command {
+        Command::Common (c)
+    }
+}
+
+response_parse! (DispensedResponse, TotalVolumeResponse, AbsoluteTotalVolumeResponse,
+                 CalibrationStateResponse, PauseStateResponse, PumpVoltageResponse,
+                 MaxFlowRateResponse);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dispensed_response () {
+        let response = "?Dispensed,12.5\0".as_bytes ();
+        assert_eq! (DispensedResponse::parse (response).unwrap (),
+                    DispensedResponse {
+                        volume: Milliliters (12.5),
+                        done: false
+                    });
+
+        let response = "?Dispensed,-3.25,*DD\r".as_bytes ();
+        assert_eq! (DispensedResponse::parse (response).unwrap (),
+                    DispensedResponse {
+                        volume: Milliliters (-3.25),
+                        done: true
+                    });
+    }
+
+    #[test]
+    fn parsing_invalid_dispensed_response_yields_error () {
+        let response = "?Dispensed,\0".as_bytes ();
+        assert! (DispensedResponse::parse (response).is_err ());
+
+        let response = "?Dispensed,12.5,*OK\0".as_bytes ();
+        assert! (DispensedResponse::parse (response).is_err ());
+
+        let response = "?Dispensed,12.5,*DD,1\0".as_bytes ();
+        assert! (DispensedResponse::parse (response).is_err ());
+
+        let response = "?TV,12.5\0".as_bytes ();
+        assert! (DispensedResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_total_volume_response () {
+        let response = "?TV,250.5\0".as_bytes ();
+        assert_eq! (TotalVolumeResponse::parse (response).unwrap (),
+                    TotalVolumeResponse (Milliliters (250.5)));
+
+        let response = "?TV,-10\r".as_bytes ();
+        assert_eq! (TotalVolumeResponse::parse (response).unwrap (),
+                    TotalVolumeResponse (Milliliters (-10.0)));
+    }
+
+    #[test]
+    fn parsing_invalid_total_volume_response_yields_error () {
+        let response = "?TV,\0".as_bytes ();
+        assert! (TotalVolumeResponse::parse (response).is_err ());
+
+        let response = "?TV,1,2\0".as_bytes ();
+        assert! (TotalVolumeResponse::parse (response).is_err ());
+
+        let response = "?ATV,250.5\0".as_bytes ();
+        assert! (TotalVolumeResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_absolute_total_volume_response () {
+        let response = "?ATV,260.5\0".as_bytes ();
+        assert_eq! (AbsoluteTotalVolumeResponse::parse (response).unwrap (),
+                    AbsoluteTotalVolumeResponse (Milliliters (260.5)));
+
+        let response = "?ATV,0\r".as_bytes ();
+        assert_eq! (AbsoluteTotalVolumeResponse::parse (response).unwrap (),
+                    AbsoluteTotalVolumeResponse (Milliliters (0.0)));
+    }
+
+    #[test]
+    fn parsing_invalid_absolute_total_volume_response_yields_error () {
+        let response = "?ATV,\0".as_bytes ();
+        assert! (AbsoluteTotalVolumeResponse::parse (response).is_err ());
+
+        let response = "?ATV,-10\0".as_bytes ();
+        assert! (AbsoluteTotalVolumeResponse::parse (response).is_err ());
+
+        let response = "?TV,260.5\0".as_bytes ();
+        assert! (AbsoluteTotalVolumeResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_calibration_state_response () {
+        let states = [("?Cal,0\0", PmpCalibrationState::Uncalibrated),
+                      ("?Cal,1\0", PmpCalibrationState::Volume),
+                      ("?Cal,2\0", PmpCalibrationState::VolumeOverTime),
+                      ("?Cal,3\r", PmpCalibrationState::VolumeAndVolumeOverTime)];
+
+        for &(response, state) in states.iter () {
+            assert_eq! (CalibrationStateResponse::parse (response.as_bytes ()).unwrap (),
+                        CalibrationStateResponse (state));
+        }
+    }
+
+    #[test]
+    fn parsing_invalid_calibration_state_response_yields_error () {
+        let response = "?Cal,\0".as_bytes ();
+        assert! (CalibrationStateResponse::parse (response).is_err ());
+
+        let response = "?Cal,4\0".as_bytes ();
+        assert! (CalibrationStateResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_pause_state_response () {
+        let response = "?P,0\0".as_bytes ();
+        assert_eq! (PauseStateResponse::parse (response).unwrap (),
+                    PauseStateResponse (PauseState::Running));
+
+        let response = "?P,1\r".as_bytes ();
+        assert_eq! (PauseStateResponse::parse (response).unwrap (),
+                    PauseStateResponse (PauseState::Paused));
+    }
+
+    #[test]
+    fn parsing_invalid_pause_state_response_yields_error () {
+        let response = "?P,\0".as_bytes ();
+        assert! (PauseStateResponse::parse (response).is_err ());
+
+        let response = "?P,2\0".as_bytes ();
+        assert! (PauseStateResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_pump_voltage_response () {
+        let response = "?PV,12.1\0".as_bytes ();
+        let voltage = PumpVoltageResponse::parse (response).unwrap ();
+        assert_eq! (voltage, PumpVoltageResponse (12.1));
+        assert! (!voltage.is_low ());
+
+        let response = "?PV,9.4\r".as_bytes ();
+        let voltage = PumpVoltageResponse::parse (response).unwrap ();
+        assert_eq! (voltage, PumpVoltageResponse (9.4));
+        assert! (voltage.is_low ());
+    }
+
+    #[test]
+    fn parsing_invalid_pump_voltage_response_yields_error () {
+        let response = "?PV,\0".as_bytes ();
+        assert! (PumpVoltageResponse::parse (response).is_err ());
+
+        let response = "?PV,-1\0".as_bytes ();
+        assert! (PumpVoltageResponse::parse (response).is_err ());
+
+        let response = "?P,12.1\0".as_bytes ();
+        assert! (PumpVoltageResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn encodes_commands () {
+        let commands = [(Command::dispense (10.0).unwrap (), "D,10.0"),
+                        (Command::dispense (-2.5).unwrap (), "D,-2.5"),
+                        (Command::dispense_over_time (100.0, 30).unwrap (), "D,100.0,30"),
+                        (Command::dispense_over_time (-5.0, 1).unwrap (), "D,-5.0,1"),
+                        (Command::dispense_at_rate (1.5, Some (60)).unwrap (), "DC,1.5,60"),
+                        (Command::dispense_at_rate (-1.5, None).unwrap (), "DC,-1.5,*"),
+                        (Command::DispenseContinuously (Direction::Forward), "D,*"),
+                        (Command::DispenseContinuously (Direction::Reverse), "D,-*"),
+                        (Command::Stop, "X"),
+                        (Command::DispensedQuery, "Dispensed,?"),
+                        (Command::TotalVolumeQuery, "TV,?"),
+                        (Command::AbsoluteTotalVolumeQuery, "ATV,?"),
+                        (Command::MaxFlowRateQuery, "DC,?"),
+                        (Command::PauseQuery, "P,?"),
+                        (Command::PumpVoltageQuery, "PV,?"),
+                        (CommonCommand::Status.into (), "Status")];
+
+        for &(ref command, encoded) in commands.iter () {
+            assert_eq! (command.encode (), encoded.as_bytes ());
+            assert_eq! (&Command::parse (encoded.as_bytes ()).unwrap (), command);
+        }
+    }
+
+    #[test]
+    fn invalid_commands_yield_error () {
+        assert! (Command::dispense (0.0).is_err ());
+        assert! (Command::dispense (100_000.0).is_err ());
+        assert! (Command::dispense (-100_000.0).is_err ());
+        assert! (Command::dispense_over_time (10.0, 0).is_err ());
+        assert! (Command::dispense_over_time (10.0, 1441).is_err ());
+        assert! (Command::dispense_at_rate (0.0, None).is_err ());
+        assert! (Command::dispense_at_rate (1.0, Some (0)).is_err ());
+
+        assert! (Command::parse ("D,0".as_bytes ()).is_err ());
+        assert! (Command::parse ("D,10,-1".as_bytes ()).is_err ());
+        assert! (Command::parse ("D,10,1.5".as_bytes ()).is_err ());
+        assert! (Command::parse ("DC,1".as_bytes ()).is_err ());
+        assert! (Command::parse ("X,1".as_bytes ()).is_err ());
+    }
+
+    #[test]
+    fn parses_max_flow_rate_response () {
+        let response = "?maxrate,105.5\0".as_bytes ();
+        assert_eq! (MaxFlowRateResponse::parse (response).unwrap (), MaxFlowRateResponse (105.5));
+
+        let response = "?maxrate,\0".as_bytes ();
+        assert! (MaxFlowRateResponse::parse (response).is_err ());
+
+        let response = "?maxrate,0\0".as_bytes ();
+        assert! (MaxFlowRateResponse::parse (response).is_err ());
+
+        let response = "?DC,105.5\0".as_bytes ();
+        assert! (MaxFlowRateResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn max_flow_rate_limits_timed_dispensing () {
+        let max = MaxFlowRateResponse (50.0);
+
+        assert! (max.allows (&Command::dispense_over_time (100.0, 2).unwrap ()));
+        assert! (!max.allows (&Command::dispense_over_time (-101.0, 2).unwrap ()));
+        assert! (max.allows (&Command::dispense_at_rate (-50.0, None).unwrap ()));
+        assert! (!max.allows (&Command::dispense_at_rate (50.5, Some (10)).unwrap ()));
+        assert! (max.allows (&Command::dispense (1000.0).unwrap ()));
+    }
+
+    #[test]
+    fn displays_responses () {
+        assert_eq! (DispensedResponse::parse ("?Dispensed,12.5\0".as_bytes ()).unwrap ().to_string (),
+                    "pump has dispensed 12.5 ml so far");
+        assert_eq! (DispensedResponse::parse ("?Dispensed,25,*DD\0".as_bytes ()).unwrap ().to_string (),
+                    "pump dispensed 25 ml");
+        assert_eq! (TotalVolumeResponse::parse ("?TV,-250.5\0".as_bytes ()).unwrap ().to_string (),
+                    "total volume is -250.5 ml");
+        assert_eq! (CalibrationStateResponse (PmpCalibrationState::VolumeOverTime).to_string (),
+                    "pump is calibrated for volume over time");
+        assert_eq! (MaxFlowRateResponse::parse ("?maxrate,105.5\0".as_bytes ()).unwrap ().to_string (),
+                    "maximum flow rate is 105.5 ml/min");
+    }
+
+    #[test]
+    fn displays_commands () {
+        assert_eq! (Command::dispense (-10.0).unwrap ().to_string (), "dispense -10 ml");
+        assert_eq! (Command::DispenseContinuously (Direction::Reverse).to_string (), "dispense in reverse until stopped");
+        assert_eq! (Command::DispenseAtRate (1.5, None).to_string (), "dispense at 1.5 ml/min until stopped");
+        assert_eq! (Command::Stop.to_string (), "stop dispensing");
+        assert_eq! (Command::Common (CommonCommand::Find).to_string (), "blink LED to find device");
+    }
+}
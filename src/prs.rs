@@ -0,0 +1,552 @@
+//! Responses and commands for the EZO PRS (pressure) sensor
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use errors::*;
+use framing::{format_decimal, parse_flag, parse_number, SplitCommand};
+use {parse_field, CommonCommand, ParseOptions};
+
+/// Unit in which the PRS sensor reports its readings
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PressureUnit {
+    Psi,
+    Atmospheres,
+    Bar,
+    Kilopascals,
+    InchesOfWater,
+    CentimetersOfWater
+}
+
+impl PressureUnit {
+    fn code (&self) -> &'static str {
+        match *self {
+            PressureUnit::Psi                => "psi",
+            PressureUnit::Atmospheres        => "atm",
+            PressureUnit::Bar                => "bar",
+            PressureUnit::Kilopascals        => "kPa",
+            PressureUnit::InchesOfWater      => "inh2o",
+            PressureUnit::CentimetersOfWater => "cmh2o"
+        }
+    }
+
+    fn from_code (code: &str, options: &ParseOptions) -> Option<PressureUnit> {
+        match code {
+            c if options.matches (c, "psi")   => Some (PressureUnit::Psi),
+            c if options.matches (c, "atm")   => Some (PressureUnit::Atmospheres),
+            c if options.matches (c, "bar")   => Some (PressureUnit::Bar),
+            c if options.matches (c, "kPa")   => Some (PressureUnit::Kilopascals),
+            c if options.matches (c, "inh2o") => Some (PressureUnit::InchesOfWater),
+            c if options.matches (c, "cmh2o") => Some (PressureUnit::CentimetersOfWater),
+            _                                 => None
+        }
+    }
+
+    // Size of one of this unit in kilopascals
+    fn kilopascals (&self) -> f64 {
+        match *self {
+            PressureUnit::Psi                => 6.894757,
+            PressureUnit::Atmospheres        => 101.325,
+            PressureUnit::Bar                => 100.0,
+            PressureUnit::Kilopascals        => 1.0,
+            PressureUnit::InchesOfWater      => 0.249089,
+            PressureUnit::CentimetersOfWater => 0.0980665
+        }
+    }
+}
+
+impl fmt::Display for PressureUnit {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{}", self.code ())
+    }
+}
+
+/// A pressure in some unit
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Pressure {
+    pub value: f64,
+    pub unit: PressureUnit
+}
+
+impl fmt::Display for Pressure {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{} {}", self.value, self.unit)
+    }
+}
+
+impl Pressure {
+    /// Converts the pressure to another unit.  Water columns are the
+    /// conventional ones, with water at 1000 kg/m³ and standard gravity.
+    pub fn to (&self, unit: PressureUnit) -> Pressure {
+        Pressure {
+            value: self.value * self.unit.kilopascals () / unit.kilopascals (),
+            unit
+        }
+    }
+}
+
+/// Response from the "R" command to take a pressure reading
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PrsResponse (pub Pressure);
+
+impl PrsResponse {
+    /// Parses the result of the "R" command to get a pressure reading.
+    /// Note that this depends on knowing the unit which the sensor is
+    /// configured to use.  If the sensor is configured to include the unit
+    /// in its readings, like "14.7,psi", it must match `unit`.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse (response: &[u8], unit: PressureUnit) -> Result<PrsResponse> {
+        PrsResponse::parse_with_options (response, unit, &ParseOptions::default ())
+    }
+
+    /// Like `parse()`, with the leniencies in `options`.
+    pub fn parse_with_options (response: &[u8], unit: PressureUnit, options: &ParseOptions)
+                               -> Result<PrsResponse> {
+        let r = options.body (response)?;
+        let mut split = r.splitn (2, ',');
+
+        let value = f64::from_str (split.next ().unwrap ()).chain_err (|| ErrorKind::ResponseParse)?;
+
+        if let Some (code) = split.next () {
+            if PressureUnit::from_code (code, options) != Some (unit) {
+                return Err (ErrorKind::ResponseParse.into ());
+            }
+        }
+
+        Ok (PrsResponse (Pressure { value, unit }))
+    }
+}
+
+impl fmt::Display for PrsResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "pressure is {}", self.0)
+    }
+}
+
+/// Response from the "U,?" command to query the unit of the sensor's readings
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PressureUnitResponse (pub PressureUnit);
+
+impl PressureUnitResponse {
+    /// Parses the result of the "U,?" command, like "?U,psi".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<PressureUnitResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?U,") {
+            match PressureUnit::from_code (rest, options) {
+                Some (unit) => Ok (PressureUnitResponse (unit)),
+                None        => Err (ErrorKind::ResponseParse.into ())
+            }
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for PressureUnitResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "pressure unit is {}", self.0)
+    }
+}
+
+/// Response from the "Alarm,?" command to query the configuration of the
+/// sensor's alarm pin, like "?Alarm,30.5,0.5,1".  The threshold and
+/// tolerance are in the unit which the sensor is configured to use.
+pub type AlarmConfigResponse = ::AlarmConfigResponse<f64>;
+
+/// Response from the "Dec,?" command to query how many decimal places the
+/// sensor includes in its readings
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DecimalPlacesResponse (pub u8);
+
+impl DecimalPlacesResponse {
+    /// Parses the result of the "Dec,?" command, like "?Dec,2".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<DecimalPlacesResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?Dec,") {
+            Ok (DecimalPlacesResponse (parse_field (Some (rest))?))
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for DecimalPlacesResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "readings have {} decimal places", self.0)
+    }
+}
+
+/// Maximum number of decimal places for "Dec,n"
+pub const MAX_DECIMAL_PLACES: u8 = 4;
+
+/// Commands which can be sent to the EZO PRS sensor
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Command {
+    /// "R", take a reading
+    Read,
+    /// "U,unit", set the unit of readings
+    SetUnit (PressureUnit),
+    /// "U,?", query the unit of readings
+    UnitQuery,
+    /// "Dec,n", set the number of decimal places in readings
+    SetDecimalPlaces (u8),
+    /// "Dec,?", query the number of decimal places in readings
+    DecimalPlacesQuery,
+    /// "Alarm,en,n", enable or disable the alarm pin
+    SetAlarmEnabled (bool),
+    /// "Alarm,n", set the pressure, in the configured unit, at which the
+    /// alarm goes off
+    SetAlarmThreshold (f64),
+    /// "Alarm,tol,n", set how far below the threshold the alarm resets
+    SetAlarmTolerance (f64),
+    /// "Alarm,?", query the alarm configuration
+    AlarmQuery,
+    /// "Cal,0", calibrate the zero point
+    CalibrateZero,
+    /// "Cal,n", calibrate at a known pressure in the configured unit
+    Calibrate (f64),
+    /// A command which is common to all the EZO circuits
+    Common (CommonCommand)
+}
+
+fn validate_pressure (pressure: f64, reason: &'static str) -> Result<f64> {
+    if pressure >= 0.0 && pressure.is_finite () {
+        Ok (pressure)
+    } else {
+        Err (ErrorKind::InvalidCommandParameter (reason).into ())
+    }
+}
+
+impl Command {
+    /// Creates a command to set the number of decimal places, which must
+    /// not be more than `MAX_DECIMAL_PLACES`.
+    pub fn set_decimal_places (places: u8) -> Result<Command> {
+        if places <= MAX_DECIMAL_PLACES {
+            Ok (Command::SetDecimalPlaces (places))
+        } else {
+            Err (ErrorKind::InvalidCommandParameter ("decimal places must be at most 4").into ())
+        }
+    }
+
+    /// Creates a command to set the alarm threshold, which must not be negative.
+    pub fn set_alarm_threshold (threshold: f64) -> Result<Command> {
+        Ok (Command::SetAlarmThreshold (validate_pressure (threshold, "alarm threshold must not be negative")?))
+    }
+
+    /// Creates a command to set the alarm tolerance, which must not be negative.
+    pub fn set_alarm_tolerance (tolerance: f64) -> Result<Command> {
+        Ok (Command::SetAlarmTolerance (validate_pressure (tolerance, "alarm tolerance must not be negative")?))
+    }
+
+    /// Creates a command to calibrate at a known pressure, which must be
+    /// positive; use `Command::CalibrateZero` for the zero point.
+    pub fn calibrate (pressure: f64) -> Result<Command> {
+        if pressure > 0.0 && pressure.is_finite () {
+            Ok (Command::Calibrate (pressure))
+        } else {
+            Err (ErrorKind::InvalidCommandParameter ("calibration pressure must be positive").into ())
+        }
+    }
+
+    /// Parses a command string as it would be sent to the device, like
+    /// "U,psi" or "Dec,2", in the same way as `saiba_parser::Command::parse()`.
+    pub fn parse (command: &[u8]) -> Result<Command> {
+        let split = SplitCommand::new (command)?;
+
+        if let Some (common) = CommonCommand::from_split (&split)? {
+            return Ok (Command::Common (common));
+        }
+
+        let mut args = split.keyword.as_ref ().map (|k| k.splitn (2, ','));
+        let first = args.as_mut ().and_then (|a| a.next ());
+        let second = args.as_mut ().and_then (|a| a.next ());
+
+        let command = match (split.name.as_str (), first, second) {
+            ("r", None, None)                   => Command::Read,
+            ("u", Some ("?"), None)             => Command::UnitQuery,
+            ("u", Some (u), None)               => Command::SetUnit (PressureUnit::from_code (u, &split.options ()).ok_or (ErrorKind::CommandParse)?),
+            ("dec", Some ("?"), None)           => Command::DecimalPlacesQuery,
+            ("dec", Some (n), None)             => Command::set_decimal_places (parse_number (n)?)?,
+            ("alarm", Some ("en"), Some (n))    => Command::SetAlarmEnabled (parse_flag (n)?),
+            ("alarm", Some ("tol"), Some (n))   => Command::set_alarm_tolerance (parse_number (n)?)?,
+            ("alarm", Some ("?"), None)         => Command::AlarmQuery,
+            ("alarm", Some (n), None)           => Command::set_alarm_threshold (parse_number (n)?)?,
+            ("cal", Some ("0"), None)           => Command::CalibrateZero,
+            ("cal", Some (n), None)             => Command::calibrate (parse_number (n)?)?,
+            _                                   => return Err (ErrorKind::CommandParse.into ())
+        };
+
+        Ok (command)
+    }
+
+    /// Returns how long to wait after sending this command before reading
+    /// the device's response over I2C.
+    pub fn delay (&self) -> Duration {
+        match *self {
+            Command::Read
+                | Command::CalibrateZero
+                | Command::Calibrate (_) => Duration::from_millis (900),
+
+            Command::Common (ref common) => common.delay (),
+            _                            => Duration::from_millis (300)
+        }
+    }
+
+    /// Returns the bytes to send to the device for this command.  In I2C
+    /// mode these are sent as-is; in UART mode, the caller must append a
+    /// carriage return.
+    pub fn encode (&self) -> Vec<u8> {
+        let s = match *self {
+            Command::Read                               => "R".to_string (),
+            Command::SetUnit (unit)                     => format! ("U,{}", unit.code ()),
+            Command::UnitQuery                          => "U,?".to_string (),
+            Command::SetDecimalPlaces (n)               => format! ("Dec,{}", n),
+            Command::DecimalPlacesQuery                 => "Dec,?".to_string (),
+            Command::SetAlarmEnabled (enabled)          => format! ("Alarm,en,{}", if enabled { 1 } else { 0 }),
+            Command::SetAlarmThreshold (p)              => format! ("Alarm,{}", format_decimal (p)),
+            Command::SetAlarmTolerance (p)              => format! ("Alarm,tol,{}", format_decimal (p)),
+            Command::AlarmQuery                         => "Alarm,?".to_string (),
+            Command::CalibrateZero                      => "Cal,0".to_string (),
+            Command::Calibrate (p)                      => format! ("Cal,{}", format_decimal (p)),
+            Command::Common (ref common)                => return common.encode ()
+        };
+
+        s.into_bytes ()
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Command::Read                    => write! (f, "take a reading"),
+            Command::SetUnit (unit)          => write! (f, "set pressure unit to {}", unit),
+            Command::UnitQuery               => write! (f, "query pressure unit"),
+            Command::SetDecimalPlaces (n)    => write! (f, "set decimal places to {}", n),
+            Command::DecimalPlacesQuery      => write! (f, "query decimal places"),
+            Command::SetAlarmEnabled (true)  => write! (f, "enable alarm"),
+            Command::SetAlarmEnabled (false) => write! (f, "disable alarm"),
+            Command::SetAlarmThreshold (p)   => write! (f, "set alarm threshold to {}", format_decimal (p)),
+            Command::SetAlarmTolerance (p)   => write! (f, "set alarm tolerance to {}", format_decimal (p)),
+            Command::AlarmQuery              => write! (f, "query alarm configuration"),
+            Command::CalibrateZero           => write! (f, "calibrate zero point"),
+            Command::Calibrate (p)           => write! (f, "calibrate at {}", format_decimal (p)),
+            Command::Common (ref common)     => common.fmt (f)
+        }
+    }
+}
+
+impl From<CommonCommand> for Command {
+    fn from (c: CommonCommand) -> Command {
+        Command::Common (c)
+    }
+}
+
+response_parse! (PressureUnitResponse, DecimalPlacesResponse);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_prs_response () {
+        let response = "14.7\0".as_bytes ();
+        assert_eq! (PrsResponse::parse (response, PressureUnit::Psi).unwrap (),
+                    PrsResponse (Pressure { value: 14.7, unit: PressureUnit::Psi }));
+
+        let response = "101.3,kPa\r".as_bytes ();
+        assert_eq! (PrsResponse::parse (response, PressureUnit::Kilopascals).unwrap (),
+                    PrsResponse (Pressure { value: 101.3, unit: PressureUnit::Kilopascals }));
+
+        let response = "35.2,cmh2o\0".as_bytes ();
+        assert_eq! (PrsResponse::parse (response, PressureUnit::CentimetersOfWater).unwrap (),
+                    PrsResponse (Pressure { value: 35.2, unit: PressureUnit::CentimetersOfWater }));
+    }
+
+    #[test]
+    fn parsing_invalid_prs_response_yields_error () {
+        let response = "\0".as_bytes ();
+        assert! (PrsResponse::parse (response, PressureUnit::Psi).is_err ());
+
+        let response = "14.7,bar\0".as_bytes ();
+        assert! (PrsResponse::parse (response, PressureUnit::Psi).is_err ());
+
+        let response = "14.7,psi,1\0".as_bytes ();
+        assert! (PrsResponse::parse (response, PressureUnit::Psi).is_err ());
+    }
+
+    #[test]
+    fn displays_pressure () {
+        assert_eq! (Pressure { value: 1.013, unit: PressureUnit::Bar }.to_string (), "1.013 bar");
+    }
+
+    #[test]
+    fn parses_pressure_unit_response () {
+        let units = [("?U,psi\0", PressureUnit::Psi),
+                     ("?U,atm\0", PressureUnit::Atmospheres),
+                     ("?U,bar\0", PressureUnit::Bar),
+                     ("?U,kPa\0", PressureUnit::Kilopascals),
+                     ("?U,inh2o\0", PressureUnit::InchesOfWater),
+                     ("?U,cmh2o\r", PressureUnit::CentimetersOfWater)];
+
+        for &(response, unit) in units.iter () {
+            assert_eq! (PressureUnitResponse::parse (response.as_bytes ()).unwrap (),
+                        PressureUnitResponse (unit));
+        }
+    }
+
+    #[test]
+    fn parsing_invalid_pressure_unit_response_yields_error () {
+        let response = "?U,\0".as_bytes ();
+        assert! (PressureUnitResponse::parse (response).is_err ());
+
+        let response = "?U,mmHg\0".as_bytes ();
+        assert! (PressureUnitResponse::parse (response).is_err ());
+
+        let response = "?U,psi,1\0".as_bytes ();
+        assert! (PressureUnitResponse::parse (response).is_err ());
+
+        let response = "psi\0".as_bytes ();
+        assert! (PressureUnitResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_unit_codes_in_any_case_only_leniently () {
+        let options = ParseOptions::lenient ();
+
+        let response = "?U,KPA\0".as_bytes ();
+        assert_eq! (PressureUnitResponse::parse_with_options (response, &options).unwrap (),
+                    PressureUnitResponse (PressureUnit::Kilopascals));
+        assert! (PressureUnitResponse::parse (response).is_err ());
+
+        let response = "14.7,PSI\0".as_bytes ();
+        assert! (PrsResponse::parse_with_options (response, PressureUnit::Psi, &options).is_ok ());
+        assert! (PrsResponse::parse (response, PressureUnit::Psi).is_err ());
+
+        assert_eq! (Command::parse ("U,KPA\0".as_bytes ()).unwrap (),
+                    Command::SetUnit (PressureUnit::Kilopascals));
+    }
+
+    #[test]
+    fn parses_alarm_config_response () {
+        let response = "?Alarm,30.5,0.5,1\0".as_bytes ();
+        assert_eq! (AlarmConfigResponse::parse (response).unwrap (),
+                    AlarmConfigResponse {
+                        threshold: 30.5,
+                        tolerance: 0.5,
+                        enabled: true
+                    });
+
+        let response = "?Alarm,30.5,0.5,yes\0".as_bytes ();
+        assert! (AlarmConfigResponse::parse (response).is_err ());
+
+        let response = "?Alarm,30.5\0".as_bytes ();
+        assert! (AlarmConfigResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_decimal_places_response () {
+        let response = "?Dec,2\0".as_bytes ();
+        assert_eq! (DecimalPlacesResponse::parse (response).unwrap (), DecimalPlacesResponse (2));
+
+        let response = "?Dec,0\r".as_bytes ();
+        assert_eq! (DecimalPlacesResponse::parse (response).unwrap (), DecimalPlacesResponse (0));
+
+        let response = "?Dec,\0".as_bytes ();
+        assert! (DecimalPlacesResponse::parse (response).is_err ());
+
+        let response = "?Dec,-1\0".as_bytes ();
+        assert! (DecimalPlacesResponse::parse (response).is_err ());
+
+        let response = "?U,2\0".as_bytes ();
+        assert! (DecimalPlacesResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn encodes_commands () {
+        let commands = [(Command::Read, "R"),
+                        (Command::SetUnit (PressureUnit::Psi), "U,psi"),
+                        (Command::SetUnit (PressureUnit::Kilopascals), "U,kPa"),
+                        (Command::SetUnit (PressureUnit::InchesOfWater), "U,inh2o"),
+                        (Command::UnitQuery, "U,?"),
+                        (Command::set_decimal_places (2).unwrap (), "Dec,2"),
+                        (Command::DecimalPlacesQuery, "Dec,?"),
+                        (Command::SetAlarmEnabled (true), "Alarm,en,1"),
+                        (Command::set_alarm_threshold (30.5).unwrap (), "Alarm,30.5"),
+                        (Command::set_alarm_tolerance (0.5).unwrap (), "Alarm,tol,0.5"),
+                        (Command::AlarmQuery, "Alarm,?"),
+                        (Command::CalibrateZero, "Cal,0"),
+                        (Command::calibrate (14.7).unwrap (), "Cal,14.7"),
+                        (Command::Common (CommonCommand::CalibrationClear), "Cal,clear")];
+
+        for &(ref command, encoded) in commands.iter () {
+            assert_eq! (command.encode (), encoded.as_bytes ());
+            assert_eq! (&Command::parse (encoded.as_bytes ()).unwrap (), command);
+        }
+    }
+
+    #[test]
+    fn invalid_commands_yield_error () {
+        assert! (Command::set_decimal_places (5).is_err ());
+        assert! (Command::set_alarm_threshold (-1.0).is_err ());
+        assert! (Command::set_alarm_tolerance (f64::NAN).is_err ());
+        assert! (Command::calibrate (0.0).is_err ());
+
+        assert! (Command::parse ("U,mmHg".as_bytes ()).is_err ());
+        assert! (Command::parse ("Dec,x".as_bytes ()).is_err ());
+        assert! (Command::parse ("Cal,-3".as_bytes ()).is_err ());
+    }
+
+    #[test]
+    fn commands_have_processing_delay () {
+        assert_eq! (Command::Read.delay (), Duration::from_millis (900));
+        assert_eq! (Command::UnitQuery.delay (), Duration::from_millis (300));
+    }
+
+    #[test]
+    fn converts_pressure_units () {
+        let atm = Pressure { value: 1.0, unit: PressureUnit::Atmospheres };
+
+        let conversions = [(PressureUnit::Psi, 14.696),
+                           (PressureUnit::Bar, 1.01325),
+                           (PressureUnit::Kilopascals, 101.325),
+                           (PressureUnit::InchesOfWater, 406.78),
+                           (PressureUnit::CentimetersOfWater, 1033.23)];
+
+        for &(unit, value) in conversions.iter () {
+            let converted = atm.to (unit);
+            assert_eq! (converted.unit, unit);
+            assert! ((converted.value - value).abs () / value < 1e-4);
+            assert! ((converted.to (PressureUnit::Atmospheres).value - 1.0).abs () < 1e-9);
+        }
+    }
+
+    #[test]
+    fn displays_responses () {
+        assert_eq! (PrsResponse::parse ("14.7,psi\0".as_bytes (), PressureUnit::Psi).unwrap ().to_string (),
+                    "pressure is 14.7 psi");
+        assert_eq! (PressureUnitResponse::parse ("?U,kPa\0".as_bytes ()).unwrap ().to_string (), "pressure unit is kPa");
+        assert_eq! (DecimalPlacesResponse::parse ("?Dec,2\0".as_bytes ()).unwrap ().to_string (),
+                    "readings have 2 decimal places");
+    }
+
+    #[test]
+    fn displays_commands () {
+        assert_eq! (Command::SetUnit (PressureUnit::Bar).to_string (), "set pressure unit to bar");
+        assert_eq! (Command::SetAlarmThreshold (30.5).to_string (), "set alarm threshold to 30.5");
+        assert_eq! (Command::CalibrateZero.to_string (), "calibrate zero point");
+        assert_eq! (Command::Common (CommonCommand::Find).to_string (), "blink LED to find device");
+    }
+}
@@ -0,0 +1,222 @@
+use std::fmt;
+
+use errors::*;
+use {BaudRateResponse, CalibrationStateResponse, DataLoggerStorageIntervalResponse,
+     DeviceInfoResponse, DeviceStatusResponse, ExportInfoResponse, ExportStringResponse,
+     LedStateResponse, MemoryLocationResponse, MemoryRecallResponse, NameResponse, ParseOptions,
+     ProtocolLockResponse, StatusToken, TemperatureResponse, TemperatureScale,
+     TemperatureScaleResponse};
+
+/// Any of the responses which the EZO RTD circuit can send, for when the
+/// caller does not know which command the response belongs to, like with
+/// unsolicited lines in UART mode.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Response {
+    /// An empty response, with which the device acknowledges commands in I2C mode
+    Acknowledgment,
+    Status (StatusToken),
+    Temperature (TemperatureResponse),
+    MemoryRecall (MemoryRecallResponse),
+    ExportInfo (ExportInfoResponse),
+    ExportString (ExportStringResponse),
+    TemperatureScale (TemperatureScaleResponse),
+    DataLoggerStorageInterval (DataLoggerStorageIntervalResponse),
+    DeviceInfo (DeviceInfoResponse),
+    DeviceStatus (DeviceStatusResponse),
+    CalibrationState (CalibrationStateResponse),
+    LedState (LedStateResponse),
+    ProtocolLock (ProtocolLockResponse),
+    MemoryLocation (MemoryLocationResponse),
+    BaudRate (BaudRateResponse),
+    Name (NameResponse)
+}
+
+impl Response {
+    /// Parses a response without knowing which command it belongs to.
+    ///
+    /// Query responses are recognized by their prefix, like "?S,", and
+    /// status lines by their initial asterisk.  Otherwise, a bare number
+    /// with a decimal point is a temperature reading in the given scale,
+    /// since the RTD always reports temperatures with decimals; two
+    /// comma-separated numbers are the result of "M" if the second one has
+    /// a decimal point, or the result of "Export,?" if not.  Anything else,
+    /// including a bare number without a decimal point, is taken to be a
+    /// piece of calibration data from "Export".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse (response: &[u8], scale: TemperatureScale) -> Result<Response> {
+        Response::parse_with_options (response, scale, &ParseOptions::default ())
+    }
+
+    /// Like `parse()`, with the leniencies in `options`.
+    pub fn parse_with_options (response: &[u8], scale: TemperatureScale, options: &ParseOptions)
+                               -> Result<Response> {
+        let r = options.body (response)?;
+
+        if r.is_empty () {
+            return Ok (Response::Acknowledgment);
+        }
+
+        if options.matches (r, "*DONE") {
+            return Ok (Response::ExportString (ExportStringResponse::Done));
+        }
+
+        if r.starts_with ('*') {
+            return Ok (Response::Status (StatusToken::parse_with_options (response, options)?));
+        }
+
+        if r.starts_with ('?') {
+            let name = r.get (1..).unwrap ().split (',').next ().unwrap ();
+
+            let parsed = match name {
+                n if options.matches (n, "S")      =>
+                    Response::TemperatureScale (TemperatureScaleResponse::parse_with_options (response, options)?),
+                n if options.matches (n, "D")      =>
+                    Response::DataLoggerStorageInterval (DataLoggerStorageIntervalResponse::parse_with_options (response, options)?),
+                n if options.matches (n, "I")      =>
+                    Response::DeviceInfo (DeviceInfoResponse::parse_with_options (response, options)?),
+                n if options.matches (n, "Status") =>
+                    Response::DeviceStatus (DeviceStatusResponse::parse_with_options (response, options)?),
+                n if options.matches (n, "Cal")    =>
+                    Response::CalibrationState (CalibrationStateResponse::parse_with_options (response, options)?),
+                n if options.matches (n, "L")      =>
+                    Response::LedState (LedStateResponse::parse_with_options (response, options)?),
+                n if options.matches (n, "Plock")  =>
+                    Response::ProtocolLock (ProtocolLockResponse::parse_with_options (response, options)?),
+                n if options.matches (n, "M")      =>
+                    Response::MemoryLocation (MemoryLocationResponse::parse_with_options (response, options)?),
+                n if options.matches (n, "Baud")   =>
+                    Response::BaudRate (BaudRateResponse::parse_with_options (response, options)?),
+                n if options.matches (n, "Name")   =>
+                    Response::Name (NameResponse::parse_with_options (response, options)?),
+                _                                  => return Err (ErrorKind::ResponseParse.into ())
+            };
+
+            return Ok (parsed);
+        }
+
+        let mut split = r.split (',');
+        let first = split.next ().unwrap ();
+
+        match (split.next (), split.next ()) {
+            (None, None) if first.contains ('.') =>
+                Ok (Response::Temperature (TemperatureResponse::parse_with_options (response, scale, options)?)),
+
+            (None, None) => Ok (Response::ExportString (ExportStringResponse::parse_with_options (response, options)?)),
+
+            (Some (second), None) if second.contains ('.') =>
+                Ok (Response::MemoryRecall (MemoryRecallResponse::parse_with_options (response, scale, options)?)),
+
+            (Some (_), None) => Ok (Response::ExportInfo (ExportInfoResponse::parse_with_options (response, options)?)),
+
+            _ => Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for Response {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Response::Acknowledgment                    => write! (f, "command acknowledged"),
+            Response::Status (ref r)                    => write! (f, "{}", r),
+            Response::Temperature (ref r)               => write! (f, "{}", r),
+            Response::MemoryRecall (ref r)              => write! (f, "{}", r),
+            Response::ExportInfo (ref r)                => write! (f, "{}", r),
+            Response::ExportString (ref r)              => write! (f, "{}", r),
+            Response::TemperatureScale (ref r)          => write! (f, "{}", r),
+            Response::DataLoggerStorageInterval (ref r) => write! (f, "{}", r),
+            Response::DeviceInfo (ref r)                => write! (f, "{}", r),
+            Response::DeviceStatus (ref r)              => write! (f, "{}", r),
+            Response::CalibrationState (ref r)          => write! (f, "{}", r),
+            Response::LedState (ref r)                  => write! (f, "{}", r),
+            Response::ProtocolLock (ref r)              => write! (f, "{}", r),
+            Response::MemoryLocation (ref r)            => write! (f, "{}", r),
+            Response::BaudRate (ref r)                  => write! (f, "{}", r),
+            Response::Name (ref r)                      => write! (f, "{}", r)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {BaudRate, LedState, Temperature};
+
+    #[test]
+    fn detects_response_type () {
+        let scale = TemperatureScale::Celsius;
+
+        assert_eq! (Response::parse ("\0".as_bytes (), scale).unwrap (), Response::Acknowledgment);
+
+        assert_eq! (Response::parse ("*OK\r".as_bytes (), scale).unwrap (),
+                    Response::Status (StatusToken::Ok));
+
+        assert_eq! (Response::parse ("*DONE\0".as_bytes (), scale).unwrap (),
+                    Response::ExportString (ExportStringResponse::Done));
+
+        assert_eq! (Response::parse ("25.104\0".as_bytes (), scale).unwrap (),
+                    Response::Temperature (TemperatureResponse (Temperature::Celsius (25.104))));
+
+        assert_eq! (Response::parse ("3,25.104\0".as_bytes (), scale).unwrap (),
+                    Response::MemoryRecall (MemoryRecallResponse {
+                        location: 3,
+                        temperature: Temperature::Celsius (25.104)
+                    }));
+
+        assert_eq! (Response::parse ("10,120\0".as_bytes (), scale).unwrap (),
+                    Response::ExportInfo (ExportInfoResponse {
+                        num_strings: 10,
+                        num_bytes: 120
+                    }));
+
+        assert_eq! (Response::parse ("596F7520\0".as_bytes (), scale).unwrap (),
+                    Response::ExportString (ExportStringResponse::Data ("596F7520".to_string ())));
+
+        assert_eq! (Response::parse ("31323334\0".as_bytes (), scale).unwrap (),
+                    Response::ExportString (ExportStringResponse::Data ("31323334".to_string ())));
+
+        assert_eq! (Response::parse ("?S,k\0".as_bytes (), scale).unwrap (),
+                    Response::TemperatureScale (TemperatureScaleResponse (TemperatureScale::Kelvin)));
+
+        assert_eq! (Response::parse ("?L,1\0".as_bytes (), scale).unwrap (),
+                    Response::LedState (LedStateResponse (LedState::On)));
+
+        assert_eq! (Response::parse ("?Baud,9600\0".as_bytes (), scale).unwrap (),
+                    Response::BaudRate (BaudRateResponse (BaudRate::Bps9600)));
+
+        assert_eq! (Response::parse ("?Name,tank\0".as_bytes (), scale).unwrap (),
+                    Response::Name (NameResponse ("tank".to_string ())));
+    }
+
+    #[test]
+    fn detects_response_type_with_options () {
+        let scale = TemperatureScale::Celsius;
+        let options = ParseOptions::lenient ();
+
+        assert_eq! (Response::parse_with_options ("?s,k\r\n".as_bytes (), scale, &options).unwrap (),
+                    Response::TemperatureScale (TemperatureScaleResponse (TemperatureScale::Kelvin)));
+
+        assert_eq! (Response::parse_with_options ("*done".as_bytes (), scale, &options).unwrap (),
+                    Response::ExportString (ExportStringResponse::Done));
+
+        assert_eq! (Response::parse_with_options (" 25.104\0".as_bytes (), scale, &options).unwrap (),
+                    Response::Temperature (TemperatureResponse (Temperature::Celsius (25.104))));
+
+        assert! (Response::parse ("?s,k\r".as_bytes (), scale).is_err ());
+    }
+
+    #[test]
+    fn detecting_invalid_response_yields_error () {
+        let scale = TemperatureScale::Celsius;
+
+        assert! (Response::parse ("".as_bytes (), scale).is_err ());
+        assert! (Response::parse ("*XX\0".as_bytes (), scale).is_err ());
+        assert! (Response::parse ("?Foo,1\0".as_bytes (), scale).is_err ());
+        assert! (Response::parse ("?S,x\0".as_bytes (), scale).is_err ());
+        assert! (Response::parse ("1,2,3\0".as_bytes (), scale).is_err ());
+        assert! (Response::parse ("59 6F 75\0".as_bytes (), scale).is_err ());
+        assert! (Response::parse ("-1023.000\0".as_bytes (), scale).is_err ());
+    }
+}
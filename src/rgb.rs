@@ -0,0 +1,681 @@
+//! Responses and commands for the EZO RGB (color) sensor
+
+use std::fmt;
+
+use errors::*;
+use framing::{write_output_params, write_reading};
+use {parse_field, parse_output_params, LedState, ParseOptions};
+
+/// A color as red, green and blue channels
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rgb {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8
+}
+
+impl fmt::Display for Rgb {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "R {} G {} B {}", self.red, self.green, self.blue)
+    }
+}
+
+/// Illuminance in lux
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Lux (pub u32);
+
+impl fmt::Display for Lux {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{} lx", self.0)
+    }
+}
+
+/// A color in the CIE 1931 xyY color space
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Cie {
+    /// Chromaticity x coordinate
+    pub x: f64,
+    /// Chromaticity y coordinate
+    pub y: f64,
+    /// Luminance
+    pub luminance: f64
+}
+
+impl fmt::Display for Cie {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "x {} y {} Y {}", self.x, self.y, self.luminance)
+    }
+}
+
+/// Which parameters the RGB sensor includes in its readings.  The sensor
+/// always reports the enabled parameters in the order of the fields here.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct OutputParams {
+    /// "RGB", red, green and blue channels
+    pub rgb: bool,
+    /// "LUX", illuminance
+    pub lux: bool,
+    /// "CIE", color in the CIE xyY color space
+    pub cie: bool
+}
+
+impl OutputParams {
+    /// All of the parameters enabled.
+    pub fn all () -> OutputParams {
+        OutputParams {
+            rgb: true,
+            lux: true,
+            cie: true
+        }
+    }
+}
+
+impl fmt::Display for OutputParams {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_output_params (f, &[("RGB", self.rgb),
+                                  ("LUX", self.lux),
+                                  ("CIE", self.cie)])
+    }
+}
+
+/// Response from the "R" command to take a reading.  Only the parameters
+/// which are enabled in the sensor's output configuration are present.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct RgbResponse {
+    pub rgb: Option<Rgb>,
+    pub lux: Option<Lux>,
+    pub cie: Option<Cie>
+}
+
+impl RgbResponse {
+    /// Parses the result of the "R" command to take a reading, given the
+    /// parameters which are enabled in the sensor, as reported by "O,?".
+    /// The sensor puts a "Lux" field before the illuminance and an "xyY"
+    /// field before the CIE color, like "25,39,138,Lux,1250,xyY,0.14,0.07,9";
+    /// these are optional here.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse (response: &[u8], params: &OutputParams) -> Result<RgbResponse> {
+        RgbResponse::parse_with_options (response, params, &ParseOptions::default ())
+    }
+
+    /// Like `parse()`, with the leniencies in `options`.
+    pub fn parse_with_options (response: &[u8], params: &OutputParams, options: &ParseOptions)
+                               -> Result<RgbResponse> {
+        let r = options.body (response)?;
+        let mut split = r.split (',').peekable ();
+
+        let rgb = if params.rgb {
+            Some (Rgb {
+                red: parse_field (split.next ())?,
+                green: parse_field (split.next ())?,
+                blue: parse_field (split.next ())?
+            })
+        } else {
+            None
+        };
+
+        let lux = if params.lux {
+            if split.peek ().map_or (false, |s| options.matches (s, "Lux")) {
+                split.next ();
+            }
+
+            Some (Lux (parse_field (split.next ())?))
+        } else {
+            None
+        };
+
+        let cie = if params.cie {
+            if split.peek ().map_or (false, |s| options.matches (s, "xyY")) {
+                split.next ();
+            }
+
+            Some (Cie {
+                x: parse_field (split.next ())?,
+                y: parse_field (split.next ())?,
+                luminance: parse_field (split.next ())?
+            })
+        } else {
+            None
+        };
+
+        if split.next ().is_some () {
+            return Err (ErrorKind::ResponseParse.into ());
+        }
+
+        Ok (RgbResponse {
+            rgb,
+            lux,
+            cie
+        })
+    }
+}
+
+impl fmt::Display for RgbResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_reading (f, &[("color", self.rgb.as_ref ().map (|v| v as _)),
+                            ("illuminance", self.lux.as_ref ().map (|v| v as _)),
+                            ("CIE color", self.cie.as_ref ().map (|v| v as _))])
+    }
+}
+
+/// Response from the "O,?" command to query which parameters are enabled
+/// in readings
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct OutputParamsResponse (pub OutputParams);
+
+impl OutputParamsResponse {
+    /// Parses the result of the "O,?" command, like "?O,RGB,LUX,CIE".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<OutputParamsResponse> {
+        let enabled = parse_output_params (response, &["RGB", "LUX", "CIE"], options)?;
+
+        Ok (OutputParamsResponse (OutputParams {
+            rgb: enabled[0],
+            lux: enabled[1],
+            cie: enabled[2]
+        }))
+    }
+}
+
+impl fmt::Display for OutputParamsResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "output parameters are {}", self.0)
+    }
+}
+
+/// Reading of the proximity sensor, from 0 for nothing detected up to
+/// `MAX_PROXIMITY` for an object touching the sensor
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Proximity (pub u16);
+
+impl fmt::Display for Proximity {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{}", self.0)
+    }
+}
+
+/// Largest possible proximity reading
+pub const MAX_PROXIMITY: Proximity = Proximity (1023);
+
+/// Response from the proximity sensor, like "P,245"
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ProximityResponse (pub Proximity);
+
+impl ProximityResponse {
+    /// Parses a proximity reading, like "P,245".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<ProximityResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "P,") {
+            let proximity = Proximity (parse_field (Some (rest))?);
+
+            if proximity <= MAX_PROXIMITY {
+                Ok (ProximityResponse (proximity))
+            } else {
+                Err (ErrorKind::ResponseParse.into ())
+            }
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for ProximityResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "proximity is {}", self.0)
+    }
+}
+
+/// Power of the infrared LED which the proximity sensor uses; a higher
+/// power detects objects further away
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProximityLedPower {
+    /// "L"
+    Low,
+    /// "M"
+    Medium,
+    /// "H"
+    High
+}
+
+impl ProximityLedPower {
+    fn from_code (code: &str, options: &ParseOptions) -> Option<ProximityLedPower> {
+        match code {
+            c if options.matches (c, "L") => Some (ProximityLedPower::Low),
+            c if options.matches (c, "M") => Some (ProximityLedPower::Medium),
+            c if options.matches (c, "H") => Some (ProximityLedPower::High),
+            _                             => None
+        }
+    }
+}
+
+impl fmt::Display for ProximityLedPower {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let power = match *self {
+            ProximityLedPower::Low    => "low",
+            ProximityLedPower::Medium => "medium",
+            ProximityLedPower::High   => "high"
+        };
+
+        write! (f, "{}", power)
+    }
+}
+
+/// Response from the "P,?" command to query the configuration of the
+/// proximity sensor
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ProximityConfigResponse {
+    pub enabled: bool,
+    pub led_power: ProximityLedPower
+}
+
+impl ProximityConfigResponse {
+    /// Parses the result of the "P,?" command, like "?P,1,H".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<ProximityConfigResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?P,") {
+            let mut split = rest.split (',');
+
+            let enabled = match split.next () {
+                Some ("0") => false,
+                Some ("1") => true,
+                _          => return Err (ErrorKind::ResponseParse.into ())
+            };
+
+            let led_power = split.next ()
+                .and_then (|code| ProximityLedPower::from_code (code, options))
+                .ok_or (ErrorKind::ResponseParse)?;
+
+            if split.next ().is_some () {
+                return Err (ErrorKind::ResponseParse.into ());
+            }
+
+            Ok (ProximityConfigResponse {
+                enabled,
+                led_power
+            })
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for ProximityConfigResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "proximity sensor is {} with {} LED power",
+                if self.enabled { "enabled" } else { "disabled" },
+                self.led_power)
+    }
+}
+
+/// Response from the "IND,?" command to query the state of the indicator
+/// LED.  Unlike the other circuits, the RGB sensor's "L" command controls
+/// its illumination LEDs instead of the indicator LED.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct IndicatorLedResponse (pub LedState);
+
+impl IndicatorLedResponse {
+    /// Parses the result of the "IND,?" command, like "?IND,1".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<IndicatorLedResponse> {
+        let r = options.body (response)?;
+
+        match options.strip_keyword (r, "?IND,") {
+            Some ("0") => Ok (IndicatorLedResponse (LedState::Off)),
+            Some ("1") => Ok (IndicatorLedResponse (LedState::On)),
+            _ => Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for IndicatorLedResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "indicator LED is {}", self.0)
+    }
+}
+
+/// Smallest gamma correction factor which the sensor supports
+pub const MIN_GAMMA: f64 = 0.01;
+
+/// Largest gamma correction factor which the sensor supports
+pub const MAX_GAMMA: f64 = 4.99;
+
+/// Response from the "G,?" command to query the gamma correction factor
+/// which the sensor applies to its RGB readings
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GammaResponse (pub f64);
+
+impl GammaResponse {
+    /// Parses the result of the "G,?" command, like "?G,1.99".  The factor
+    /// must be between `MIN_GAMMA` and `MAX_GAMMA`.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<GammaResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?G,") {
+            let gamma: f64 = parse_field (Some (rest))?;
+
+            if (MIN_GAMMA ..= MAX_GAMMA).contains (&gamma) {
+                Ok (GammaResponse (gamma))
+            } else {
+                Err (ErrorKind::ResponseParse.into ())
+            }
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for GammaResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "gamma correction is {}", self.0)
+    }
+}
+
+/// Response from the "L,?" command to query the brightness of the sensor's
+/// illumination LEDs
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LedBrightnessResponse {
+    /// Brightness as a percentage from 0 to 100
+    pub brightness: u8,
+    /// Whether the LEDs only light up while taking a reading, as
+    /// signaled by a trailing "T"
+    pub auto: bool
+}
+
+impl LedBrightnessResponse {
+    /// Parses the result of the "L,?" command, like "?L,32" or "?L,32,T".
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<LedBrightnessResponse> {
+        let r = options.body (response)?;
+
+        if let Some (rest) = options.strip_keyword (r, "?L,") {
+            let mut split = rest.split (',');
+
+            let brightness: u8 = parse_field (split.next ())?;
+            if brightness > 100 {
+                return Err (ErrorKind::ResponseParse.into ());
+            }
+
+            let auto = match split.next () {
+                Some (s) if options.matches (s, "T") => true,
+                None                                 => false,
+                _                                    => return Err (ErrorKind::ResponseParse.into ())
+            };
+
+            if split.next ().is_some () {
+                return Err (ErrorKind::ResponseParse.into ());
+            }
+
+            Ok (LedBrightnessResponse {
+                brightness,
+                auto
+            })
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for LedBrightnessResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "LED brightness is {}%", self.brightness)?;
+
+        if self.auto {
+            write! (f, " while taking readings")?;
+        }
+
+        Ok (())
+    }
+}
+
+response_parse! (OutputParamsResponse, ProximityResponse, ProximityConfigResponse,
+                 IndicatorLedResponse, GammaResponse, LedBrightnessResponse);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rgb_response () {
+        let response = "25,39,138\0".as_bytes ();
+        let params = OutputParams { rgb: true, ..Default::default () };
+        assert_eq! (RgbResponse::parse (response, &params).unwrap (),
+                    RgbResponse {
+                        rgb: Some (Rgb { red: 25, green: 39, blue: 138 }),
+                        ..Default::default ()
+                    });
+
+        let response = "25,39,138,Lux,1250,xyY,0.1455,0.0721,9\r".as_bytes ();
+        assert_eq! (RgbResponse::parse (response, &OutputParams::all ()).unwrap (),
+                    RgbResponse {
+                        rgb: Some (Rgb { red: 25, green: 39, blue: 138 }),
+                        lux: Some (Lux (1250)),
+                        cie: Some (Cie { x: 0.1455, y: 0.0721, luminance: 9.0 })
+                    });
+
+        let response = "1250,0.1455,0.0721,9\0".as_bytes ();
+        let params = OutputParams { lux: true, cie: true, ..Default::default () };
+        assert_eq! (RgbResponse::parse (response, &params).unwrap (),
+                    RgbResponse {
+                        rgb: None,
+                        lux: Some (Lux (1250)),
+                        cie: Some (Cie { x: 0.1455, y: 0.0721, luminance: 9.0 })
+                    });
+    }
+
+    #[test]
+    fn parsing_invalid_rgb_response_yields_error () {
+        let response = "25,39\0".as_bytes ();
+        assert! (RgbResponse::parse (response, &OutputParams::all ()).is_err ());
+
+        let response = "25,39,256\0".as_bytes ();
+        let params = OutputParams { rgb: true, ..Default::default () };
+        assert! (RgbResponse::parse (response, &params).is_err ());
+
+        let response = "25,39,138,Lux,1250\0".as_bytes ();
+        assert! (RgbResponse::parse (response, &params).is_err ());
+    }
+
+    #[test]
+    fn parses_output_params_response () {
+        let response = "?O,RGB,LUX,CIE\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams::all ()));
+
+        let response = "?O,RGB,CIE\r".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams {
+                        rgb: true,
+                        lux: false,
+                        cie: true
+                    }));
+
+        let response = "?O,No output\0".as_bytes ();
+        assert_eq! (OutputParamsResponse::parse (response).unwrap (),
+                    OutputParamsResponse (OutputParams::default ()));
+    }
+
+    #[test]
+    fn parsing_invalid_output_params_response_yields_error () {
+        let response = "?O,\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+
+        let response = "?O,RGB,RGB\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+
+        let response = "?O,RGB,HUM\0".as_bytes ();
+        assert! (OutputParamsResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_proximity_response () {
+        let response = "P,245\0".as_bytes ();
+        assert_eq! (ProximityResponse::parse (response).unwrap (), ProximityResponse (Proximity (245)));
+
+        let response = "P,1023\r".as_bytes ();
+        assert_eq! (ProximityResponse::parse (response).unwrap (), ProximityResponse (MAX_PROXIMITY));
+    }
+
+    #[test]
+    fn parsing_invalid_proximity_response_yields_error () {
+        let response = "P,\0".as_bytes ();
+        assert! (ProximityResponse::parse (response).is_err ());
+
+        let response = "P,1024\0".as_bytes ();
+        assert! (ProximityResponse::parse (response).is_err ());
+
+        let response = "?P,245\0".as_bytes ();
+        assert! (ProximityResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_proximity_config_response () {
+        let response = "?P,1,H\0".as_bytes ();
+        assert_eq! (ProximityConfigResponse::parse (response).unwrap (),
+                    ProximityConfigResponse {
+                        enabled: true,
+                        led_power: ProximityLedPower::High
+                    });
+
+        let response = "?P,0,L\r".as_bytes ();
+        assert_eq! (ProximityConfigResponse::parse (response).unwrap (),
+                    ProximityConfigResponse {
+                        enabled: false,
+                        led_power: ProximityLedPower::Low
+                    });
+
+        let response = "?P,1,h\0".as_bytes ();
+        assert_eq! (ProximityConfigResponse::parse_with_options (response, &ParseOptions::lenient ()).unwrap (),
+                    ProximityConfigResponse {
+                        enabled: true,
+                        led_power: ProximityLedPower::High
+                    });
+    }
+
+    #[test]
+    fn parsing_invalid_proximity_config_response_yields_error () {
+        let response = "?P,1\0".as_bytes ();
+        assert! (ProximityConfigResponse::parse (response).is_err ());
+
+        let response = "?P,2,M\0".as_bytes ();
+        assert! (ProximityConfigResponse::parse (response).is_err ());
+
+        let response = "?P,1,h\0".as_bytes ();
+        assert! (ProximityConfigResponse::parse (response).is_err ());
+
+        let response = "?P,1,X\0".as_bytes ();
+        assert! (ProximityConfigResponse::parse (response).is_err ());
+
+        let response = "?P,1,M,0\0".as_bytes ();
+        assert! (ProximityConfigResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_indicator_led_response () {
+        let response = "?IND,0\0".as_bytes ();
+        assert_eq! (IndicatorLedResponse::parse (response).unwrap (), IndicatorLedResponse (LedState::Off));
+
+        let response = "?IND,1\r".as_bytes ();
+        assert_eq! (IndicatorLedResponse::parse (response).unwrap (), IndicatorLedResponse (LedState::On));
+    }
+
+    #[test]
+    fn parsing_invalid_indicator_led_response_yields_error () {
+        let response = "?IND,\0".as_bytes ();
+        assert! (IndicatorLedResponse::parse (response).is_err ());
+
+        let response = "?L,1\0".as_bytes ();
+        assert! (IndicatorLedResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_gamma_response () {
+        let response = "?G,1.99\0".as_bytes ();
+        assert_eq! (GammaResponse::parse (response).unwrap (), GammaResponse (1.99));
+
+        let response = "?G,0.01\r".as_bytes ();
+        assert_eq! (GammaResponse::parse (response).unwrap (), GammaResponse (MIN_GAMMA));
+
+        let response = "?G,4.99\0".as_bytes ();
+        assert_eq! (GammaResponse::parse (response).unwrap (), GammaResponse (MAX_GAMMA));
+    }
+
+    #[test]
+    fn parsing_invalid_gamma_response_yields_error () {
+        let response = "?G,\0".as_bytes ();
+        assert! (GammaResponse::parse (response).is_err ());
+
+        let response = "?G,0\0".as_bytes ();
+        assert! (GammaResponse::parse (response).is_err ());
+
+        let response = "?G,5\0".as_bytes ();
+        assert! (GammaResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_led_brightness_response () {
+        let response = "?L,32\0".as_bytes ();
+        assert_eq! (LedBrightnessResponse::parse (response).unwrap (),
+                    LedBrightnessResponse {
+                        brightness: 32,
+                        auto: false
+                    });
+
+        let response = "?L,100,T\r".as_bytes ();
+        assert_eq! (LedBrightnessResponse::parse (response).unwrap (),
+                    LedBrightnessResponse {
+                        brightness: 100,
+                        auto: true
+                    });
+    }
+
+    #[test]
+    fn parsing_invalid_led_brightness_response_yields_error () {
+        let response = "?L,\0".as_bytes ();
+        assert! (LedBrightnessResponse::parse (response).is_err ());
+
+        let response = "?L,101\0".as_bytes ();
+        assert! (LedBrightnessResponse::parse (response).is_err ());
+
+        let response = "?L,32,X\0".as_bytes ();
+        assert! (LedBrightnessResponse::parse (response).is_err ());
+
+        let response = "?L,32,T,1\0".as_bytes ();
+        assert! (LedBrightnessResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn displays_responses () {
+        let response = "25,39,138,Lux,1250,xyY,0.14,0.07,9\0".as_bytes ();
+        assert_eq! (RgbResponse::parse (response, &OutputParams::all ()).unwrap ().to_string (),
+                    "color R 25 G 39 B 138, illuminance 1250 lx, CIE color x 0.14 y 0.07 Y 9");
+        assert_eq! (OutputParamsResponse (OutputParams::all ()).to_string (), "output parameters are RGB, LUX, CIE");
+        assert_eq! (ProximityResponse::parse ("P,245\0".as_bytes ()).unwrap ().to_string (), "proximity is 245");
+        assert_eq! (ProximityConfigResponse::parse ("?P,1,H\0".as_bytes ()).unwrap ().to_string (),
+                    "proximity sensor is enabled with high LED power");
+        assert_eq! (GammaResponse::parse ("?G,1.99\0".as_bytes ()).unwrap ().to_string (), "gamma correction is 1.99");
+        assert_eq! (LedBrightnessResponse::parse ("?L,32\0".as_bytes ()).unwrap ().to_string (),
+                    "LED brightness is 32%");
+        assert_eq! (LedBrightnessResponse::parse ("?L,32,T\0".as_bytes ()).unwrap ().to_string (),
+                    "LED brightness is 32% while taking readings");
+    }
+}
@@ -0,0 +1,1142 @@
+//! Responses and commands for the EZO RTD (temperature) circuit
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use errors::*;
+use framing::{format_decimal, parse_number, ParseOptions, SplitCommand};
+use common::*;
+
+/// Response from the "S,?" command to query temperature scale
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TemperatureScaleResponse (pub TemperatureScale);
+
+impl TemperatureScaleResponse {
+    /// Parses the result of the "S,?" command to query temperature scale.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<TemperatureScaleResponse> {
+        let r = options.body (response)?;
+
+        match options.strip_keyword (r, "?S,") {
+            Some (c) if options.matches (c, "c") => Ok (TemperatureScaleResponse (TemperatureScale::Celsius)),
+            Some (c) if options.matches (c, "k") => Ok (TemperatureScaleResponse (TemperatureScale::Kelvin)),
+            Some (c) if options.matches (c, "f") => Ok (TemperatureScaleResponse (TemperatureScale::Fahrenheit)),
+            _ => Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for TemperatureScaleResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "temperature scale is {}", self.0)
+    }
+}
+
+/// Interval between automatic logging of readings
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum DataLoggerStorageInterval {
+    /// The data logger is disabled
+    Off,
+    /// Seconds between readings, from 10 to 320000
+    IntervalSeconds (u32)
+}
+
+impl DataLoggerStorageInterval {
+    // The device uses 0 to mean that the data logger is off
+    fn from_seconds (seconds: u32) -> Option<DataLoggerStorageInterval> {
+        match seconds {
+            0             => Some (DataLoggerStorageInterval::Off),
+            10 ..= 320000 => Some (DataLoggerStorageInterval::IntervalSeconds (seconds)),
+            _             => None
+        }
+    }
+}
+
+impl fmt::Display for DataLoggerStorageInterval {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DataLoggerStorageInterval::Off                    => write! (f, "off"),
+            DataLoggerStorageInterval::IntervalSeconds (secs) => write! (f, "every {} s", secs)
+        }
+    }
+}
+
+/// Response from the "D,?" command to query the data logger's storage interval
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DataLoggerStorageIntervalResponse (pub DataLoggerStorageInterval);
+
+impl DataLoggerStorageIntervalResponse {
+    /// Parses the result of the "D,?" command to query the data logger's
+    /// storage interval.  Returns the number of seconds between readings,
+    /// or whether the data logger is off.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result <DataLoggerStorageIntervalResponse> {
+        let r = options.body (response)?;
+
+        if let Some (num_str) = options.strip_keyword (r, "?D,") {
+            let num = u32::from_str (num_str).chain_err (|| ErrorKind::ResponseParse)?;
+
+            match DataLoggerStorageInterval::from_seconds (num) {
+                Some (interval) => Ok (DataLoggerStorageIntervalResponse (interval)),
+                None            => Err (ErrorKind::ResponseParse.into ())
+            }
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for DataLoggerStorageIntervalResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            DataLoggerStorageInterval::Off => write! (f, "data logger is off"),
+            interval                       => write! (f, "data logger stores a reading {}", interval)
+        }
+    }
+}
+
+/// Response from the "R" command to take a temperature reading
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TemperatureResponse (pub Temperature);
+
+impl TemperatureResponse {
+    /// Parses the result of the "D" command to get a temperature reading.
+    /// Note that this depends on knowing the temperature scale
+    /// which the device is configured to use.
+    ///
+    /// If the probe is disconnected or shorted, the device reports a
+    /// sentinel value of -1023.xxx, and this returns a `ProbeFault` error.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse (response: &[u8], scale: TemperatureScale) -> Result <TemperatureResponse> {
+        TemperatureResponse::parse_with_options (response, scale, &ParseOptions::default ())
+    }
+
+    /// Like `parse()`, with the leniencies in `options`.
+    pub fn parse_with_options (response: &[u8], scale: TemperatureScale, options: &ParseOptions)
+                               -> Result <TemperatureResponse> {
+        let r = options.body (response)?;
+        let val = f64::from_str (r).chain_err (|| ErrorKind::ResponseParse)?;
+
+        if val <= -1023.0 && val > -1024.0 {
+            return Err (ErrorKind::ProbeFault.into ());
+        }
+
+        Ok (TemperatureResponse (Temperature::new (scale, val)))
+    }
+}
+
+impl fmt::Display for TemperatureResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "temperature is {}", self.0)
+    }
+}
+
+/// Response from the "M" command to recall the next stored reading
+/// from the device's memory
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MemoryRecallResponse {
+    pub location: u32,
+    pub temperature: Temperature
+}
+
+impl MemoryRecallResponse {
+    /// Parses the result of the "M" command to recall a stored reading.
+    /// Like `TemperatureResponse::parse`, this depends on knowing the
+    /// temperature scale which the device is configured to use.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse (response: &[u8], scale: TemperatureScale) -> Result<MemoryRecallResponse> {
+        MemoryRecallResponse::parse_with_options (response, scale, &ParseOptions::default ())
+    }
+
+    /// Like `parse()`, with the leniencies in `options`.
+    pub fn parse_with_options (response: &[u8], scale: TemperatureScale, options: &ParseOptions)
+                               -> Result<MemoryRecallResponse> {
+        let r = options.body (response)?;
+        let mut split = r.split (',');
+
+        let location = if let Some (location_str) = split.next () {
+            u32::from_str (location_str).chain_err (|| ErrorKind::ResponseParse)?
+        } else {
+            return Err (ErrorKind::ResponseParse.into ());
+        };
+
+        let value = if let Some (value_str) = split.next () {
+            f64::from_str (value_str).chain_err (|| ErrorKind::ResponseParse)?
+        } else {
+            return Err (ErrorKind::ResponseParse.into ());
+        };
+
+        if split.next ().is_some () {
+            return Err (ErrorKind::ResponseParse.into ());
+        }
+
+        Ok (MemoryRecallResponse {
+            location,
+            temperature: Temperature::new (scale, value)
+        })
+    }
+}
+
+impl fmt::Display for MemoryRecallResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "stored reading {} is {}", self.location, self.temperature)
+    }
+}
+
+/// Response from the "M,?" command to query the last memory location
+/// used by the data logger
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MemoryLocationResponse (pub u32);
+
+impl MemoryLocationResponse {
+    /// Parses the result of the "M,?" command to query the data logger's
+    /// current memory location.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    pub fn parse_with_options (response: &[u8], options: &ParseOptions) -> Result<MemoryLocationResponse> {
+        let r = options.body (response)?;
+
+        if let Some (num_str) = options.strip_keyword (r, "?M,") {
+            let num = u32::from_str (num_str).chain_err (|| ErrorKind::ResponseParse)?;
+            Ok (MemoryLocationResponse (num))
+        } else {
+            Err (ErrorKind::ResponseParse.into ())
+        }
+    }
+}
+
+impl fmt::Display for MemoryLocationResponse {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "data logger memory location is {}", self.0)
+    }
+}
+
+/// Commands which can be sent to the EZO RTD circuit
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Command {
+    /// "R", take a temperature reading
+    Read,
+    /// "S,c", "S,k" or "S,f", set the temperature scale
+    SetScale (TemperatureScale),
+    /// "S,?", query the temperature scale
+    ScaleQuery,
+    /// "D,n", set the data logger's storage interval
+    SetDataLoggerInterval (DataLoggerStorageInterval),
+    /// "D,?", query the data logger's storage interval
+    DataLoggerQuery,
+    /// "M", recall the next stored reading from memory
+    MemoryRecall,
+    /// "M,?", query the last memory location used by the data logger
+    MemoryLocationQuery,
+    /// "M,clear", clear the stored readings
+    MemoryClear,
+    /// "Cal,t", calibrate the probe at the given temperature in Celsius
+    Calibrate (CalibrationTemperature),
+    /// "Cal,clear", delete the calibration data
+    CalibrationClear,
+    /// "Cal,?", query whether the device has been calibrated
+    CalibrationQuery,
+    /// "Export,?", query the size of the calibration export
+    ExportInfo,
+    /// "Export", get the next piece of calibration data
+    Export,
+    /// "Import,n", load a piece of calibration data
+    Import (CalibrationData),
+    /// "I", query the device information
+    DeviceInfo,
+    /// "Status", query the restart reason and supply voltage
+    Status,
+    /// "L,1" or "L,0", turn the LED on or off
+    SetLed (LedState),
+    /// "L,?", query the LED state
+    LedQuery,
+    /// "Plock,1" or "Plock,0", lock or unlock the protocol
+    SetProtocolLock (ProtocolLockState),
+    /// "Plock,?", query the protocol lock
+    ProtocolLockQuery,
+    /// "Sleep", enter low-power mode
+    Sleep,
+    /// "Find", blink the LED to locate the device
+    Find,
+    /// "Factory", reset the device to its factory settings
+    Factory,
+    /// "Baud,n", switch to UART mode at the given baud rate
+    Baud (BaudRate),
+    /// "Baud,?", query the UART baud rate
+    BaudQuery,
+    /// "I2C,n", change the device's I2C address; the device reboots afterwards
+    I2cAddress (I2cAddress),
+    /// "Name,n", set the device's name; an empty name clears it
+    SetName (DeviceName),
+    /// "Name,?", query the device's name
+    NameQuery
+}
+
+/// A reference temperature in Celsius at which the probe can be calibrated
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+pub struct CalibrationTemperature (f64);
+
+impl CalibrationTemperature {
+    /// Validates a calibration temperature; it must be between
+    /// `MIN_CALIBRATION_TEMPERATURE` and `MAX_CALIBRATION_TEMPERATURE`,
+    /// and not NaN.
+    pub fn new (temperature: f64) -> Result<CalibrationTemperature> {
+        if (MIN_CALIBRATION_TEMPERATURE ..= MAX_CALIBRATION_TEMPERATURE).contains (&temperature) {
+            Ok (CalibrationTemperature (temperature))
+        } else {
+            Err (ErrorKind::InvalidCommandParameter ("calibration temperature is out of the probe's range").into ())
+        }
+    }
+
+    pub fn value (&self) -> f64 {
+        self.0
+    }
+}
+
+impl fmt::Display for CalibrationTemperature {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write! (f, "{} °C", format_decimal (self.0))
+    }
+}
+
+/// The "Cal" commands to calibrate the probe at a reference temperature,
+/// or to clear the calibration data
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CalibrationCommand {
+    // None means "Cal,clear"
+    temperature: Option<CalibrationTemperature>
+}
+
+impl CalibrationCommand {
+    /// Creates a command to calibrate the probe at the given reference
+    /// temperature in Celsius, which must be within the probe's range.
+    pub fn at (temperature: f64) -> Result<CalibrationCommand> {
+        Ok (CalibrationCommand { temperature: Some (CalibrationTemperature::new (temperature)?) })
+    }
+
+    /// Creates a command to delete the calibration data.
+    pub fn clear () -> CalibrationCommand {
+        CalibrationCommand { temperature: None }
+    }
+
+    /// Returns the bytes to send to the device for this command, with the
+    /// temperature formatted as a decimal number with at most three
+    /// decimal places.
+    pub fn encode (&self) -> Vec<u8> {
+        Command::from (*self).encode ()
+    }
+}
+
+impl From<CalibrationCommand> for Command {
+    fn from (c: CalibrationCommand) -> Command {
+        match c.temperature {
+            Some (temperature) => Command::Calibrate (temperature),
+            None               => Command::CalibrationClear
+        }
+    }
+}
+
+impl fmt::Display for CalibrationCommand {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Command::from (*self).fmt (f)
+    }
+}
+
+/// The "I2C,n" command to change the device's I2C address.
+///
+/// The device reboots after receiving this command, and will only respond
+/// at the new address afterwards; it does not send a response to the
+/// command itself.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct I2cAddressCommand (pub I2cAddress);
+
+impl I2cAddressCommand {
+    /// Creates a command to change the device's I2C address, which must
+    /// not be one of the reserved addresses.
+    pub fn new (address: u8) -> Result<I2cAddressCommand> {
+        Ok (I2cAddressCommand (I2cAddress::new (address)?))
+    }
+
+    /// Returns the bytes to send to the device for this command.
+    pub fn encode (&self) -> Vec<u8> {
+        Command::from (*self).encode ()
+    }
+}
+
+impl From<I2cAddressCommand> for Command {
+    fn from (c: I2cAddressCommand) -> Command {
+        Command::I2cAddress (c.0)
+    }
+}
+
+impl fmt::Display for I2cAddressCommand {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Command::from (*self).fmt (f)
+    }
+}
+
+fn scale_code (scale: TemperatureScale) -> &'static str {
+    match scale {
+        TemperatureScale::Celsius    => "c",
+        TemperatureScale::Kelvin     => "k",
+        TemperatureScale::Fahrenheit => "f"
+    }
+}
+
+impl Command {
+    /// Creates a command to set the data logger's storage interval.  An
+    /// interval of 0 turns the data logger off; otherwise it must be
+    /// between 10 and 320000 seconds.
+    pub fn set_data_logger_interval (seconds: u32) -> Result<Command> {
+        match DataLoggerStorageInterval::from_seconds (seconds) {
+            Some (interval) => Ok (Command::SetDataLoggerInterval (interval)),
+            None            => Err (ErrorKind::InvalidCommandParameter ("data logger interval must be 0 or 10-320000 seconds").into ())
+        }
+    }
+
+    /// Creates a command to calibrate the probe at the given temperature
+    /// in Celsius, which must be within the probe's range.
+    pub fn calibrate (temperature: f64) -> Result<Command> {
+        Ok (CalibrationCommand::at (temperature)?.into ())
+    }
+
+    /// Creates a command to set the device's name.  The name can have up
+    /// to 16 printable ASCII characters, without spaces or commas.
+    pub fn set_name (name: &str) -> Result<Command> {
+        Ok (CommonCommand::set_name (name)?.into ())
+    }
+
+    /// Creates a command to load a piece of calibration data, as obtained
+    /// from the "Export" command.
+    pub fn import (data: &str) -> Result<Command> {
+        Ok (CommonCommand::import (data)?.into ())
+    }
+
+    /// Parses a command string as it would be sent to the device, like
+    /// "D,60" or "S,?".  As with the device itself, the command names and
+    /// keywords are case-insensitive.  A single trailing nul or carriage
+    /// return is allowed.
+    ///
+    /// Parameters are validated in the same way as the constructors
+    /// like `Command::calibrate()`.
+    pub fn parse (command: &[u8]) -> Result<Command> {
+        let split = SplitCommand::new (command)?;
+
+        if let Some (common) = CommonCommand::from_split (&split)? {
+            return Ok (common.into ());
+        }
+
+        let command = match (split.name.as_str (), split.keyword.as_deref ()) {
+            ("r", None)              => Command::Read,
+            ("s", Some ("c"))        => Command::SetScale (TemperatureScale::Celsius),
+            ("s", Some ("k"))        => Command::SetScale (TemperatureScale::Kelvin),
+            ("s", Some ("f"))        => Command::SetScale (TemperatureScale::Fahrenheit),
+            ("s", Some ("?"))        => Command::ScaleQuery,
+            ("d", Some ("?"))        => Command::DataLoggerQuery,
+            ("d", Some (n))          => Command::set_data_logger_interval (parse_number (n)?)?,
+            ("m", None)              => Command::MemoryRecall,
+            ("m", Some ("?"))        => Command::MemoryLocationQuery,
+            ("m", Some ("clear"))    => Command::MemoryClear,
+            ("cal", Some (t))        => Command::calibrate (parse_number (t)?)?,
+            _                        => return Err (ErrorKind::CommandParse.into ())
+        };
+
+        Ok (command)
+    }
+
+    // The variants which correspond to CommonCommand
+    fn to_common (&self) -> Option<CommonCommand> {
+        let common = match *self {
+            Command::CalibrationClear         => CommonCommand::CalibrationClear,
+            Command::CalibrationQuery         => CommonCommand::CalibrationQuery,
+            Command::ExportInfo               => CommonCommand::ExportInfo,
+            Command::Export                   => CommonCommand::Export,
+            Command::Import (ref data)        => CommonCommand::Import (data.clone ()),
+            Command::DeviceInfo               => CommonCommand::DeviceInfo,
+            Command::Status                   => CommonCommand::Status,
+            Command::SetLed (state)           => CommonCommand::SetLed (state),
+            Command::LedQuery                 => CommonCommand::LedQuery,
+            Command::SetProtocolLock (state)  => CommonCommand::SetProtocolLock (state),
+            Command::ProtocolLockQuery        => CommonCommand::ProtocolLockQuery,
+            Command::Sleep                    => CommonCommand::Sleep,
+            Command::Find                     => CommonCommand::Find,
+            Command::Factory                  => CommonCommand::Factory,
+            Command::Baud (rate)              => CommonCommand::Baud (rate),
+            Command::BaudQuery                => CommonCommand::BaudQuery,
+            Command::I2cAddress (address)     => CommonCommand::I2cAddress (address),
+            Command::SetName (ref name)       => CommonCommand::SetName (name.clone ()),
+            Command::NameQuery                => CommonCommand::NameQuery,
+            _                                 => return None
+        };
+
+        Some (common)
+    }
+
+    /// Returns how long to wait after sending this command before reading
+    /// the device's response over I2C.
+    ///
+    /// Note that the device does not send a response at all to `Sleep`,
+    /// and that it reboots after `Factory`, `Baud` and `I2cAddress`.
+    pub fn delay (&self) -> Duration {
+        match *self {
+            Command::Read | Command::Calibrate (_) => Duration::from_millis (600),
+            _                                      => Duration::from_millis (300)
+        }
+    }
+
+    /// Returns the bytes to send to the device for this command.  In I2C
+    /// mode these are sent as-is; in UART mode, the caller must append a
+    /// carriage return.
+    pub fn encode (&self) -> Vec<u8> {
+        let s = match *self {
+            Command::Read                    => "R".to_string (),
+            Command::SetScale (scale)        => format! ("S,{}", scale_code (scale)),
+            Command::ScaleQuery              => "S,?".to_string (),
+
+            Command::SetDataLoggerInterval (DataLoggerStorageInterval::Off) =>
+                "D,0".to_string (),
+            Command::SetDataLoggerInterval (DataLoggerStorageInterval::IntervalSeconds (secs)) =>
+                format! ("D,{}", secs),
+            Command::DataLoggerQuery         => "D,?".to_string (),
+
+            Command::MemoryRecall            => "M".to_string (),
+            Command::MemoryLocationQuery     => "M,?".to_string (),
+            Command::MemoryClear             => "M,clear".to_string (),
+            Command::Calibrate (temperature) => format! ("Cal,{}", format_decimal (temperature.value ())),
+            _                                => return self.to_common ().unwrap ().encode ()
+        };
+
+        s.into_bytes ()
+    }
+}
+
+impl From<CommonCommand> for Command {
+    fn from (c: CommonCommand) -> Command {
+        match c {
+            CommonCommand::CalibrationClear        => Command::CalibrationClear,
+            CommonCommand::CalibrationQuery        => Command::CalibrationQuery,
+            CommonCommand::ExportInfo              => Command::ExportInfo,
+            CommonCommand::Export                  => Command::Export,
+            CommonCommand::Import (data)           => Command::Import (data),
+            CommonCommand::DeviceInfo              => Command::DeviceInfo,
+            CommonCommand::Status                  => Command::Status,
+            CommonCommand::SetLed (state)          => Command::SetLed (state),
+            CommonCommand::LedQuery                => Command::LedQuery,
+            CommonCommand::SetProtocolLock (state) => Command::SetProtocolLock (state),
+            CommonCommand::ProtocolLockQuery       => Command::ProtocolLockQuery,
+            CommonCommand::Sleep                   => Command::Sleep,
+            CommonCommand::Find                    => Command::Find,
+            CommonCommand::Factory                 => Command::Factory,
+            CommonCommand::Baud (rate)             => Command::Baud (rate),
+            CommonCommand::BaudQuery               => Command::BaudQuery,
+            CommonCommand::I2cAddress (address)    => Command::I2cAddress (address),
+            CommonCommand::SetName (name)          => Command::SetName (name),
+            CommonCommand::NameQuery               => Command::NameQuery
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Command::Read                    => write! (f, "take a reading"),
+            Command::SetScale (scale)        => write! (f, "set temperature scale to {}", scale),
+            Command::ScaleQuery              => write! (f, "query temperature scale"),
+
+            Command::SetDataLoggerInterval (DataLoggerStorageInterval::Off) =>
+                write! (f, "turn data logger off"),
+            Command::SetDataLoggerInterval (DataLoggerStorageInterval::IntervalSeconds (secs)) =>
+                write! (f, "set data logger interval to {} s", secs),
+            Command::DataLoggerQuery         => write! (f, "query data logger interval"),
+
+            Command::MemoryRecall            => write! (f, "recall next stored reading"),
+            Command::MemoryLocationQuery     => write! (f, "query data logger memory location"),
+            Command::MemoryClear             => write! (f, "clear stored readings"),
+            Command::Calibrate (temperature) => write! (f, "calibrate at {}", temperature),
+            _                                => self.to_common ().unwrap ().fmt (f)
+        }
+    }
+}
+
+/// A command whose response from the device is parsed into a specific type.
+///
+/// This is named `TypedCommand` because `Command` is the enum of all the
+/// commands; each type that implements this trait can produce one of
+/// those with `command()`.
+pub trait TypedCommand {
+    type Response;
+
+    /// Returns the command to send to the device.
+    fn command (&self) -> Command;
+
+    /// Parses the device's response to the command.
+    ///
+    /// Assumes that the passed response is the device's response without
+    /// the initial status byte.
+    fn parse_response (&self, response: &[u8]) -> Result<Self::Response>;
+}
+
+macro_rules! typed_command {
+    ($name:ident, $command:expr, $response:ident, $doc:expr) => {
+        #[doc = $doc]
+        #[derive(Debug, Copy, Clone, PartialEq)]
+        pub struct $name;
+
+        impl TypedCommand for $name {
+            type Response = $response;
+
+            fn command (&self) -> Command {
+                $command
+            }
+
+            fn parse_response (&self, response: &[u8]) -> Result<$response> {
+                $response::parse (response)
+            }
+        }
+    }
+}
+
+typed_command! (ScaleQuery,          Command::ScaleQuery,          TemperatureScaleResponse,
+                "The \"S,?\" command to query the temperature scale");
+typed_command! (DataLoggerQuery,     Command::DataLoggerQuery,     DataLoggerStorageIntervalResponse,
+                "The \"D,?\" command to query the data logger's storage interval");
+typed_command! (MemoryLocationQuery, Command::MemoryLocationQuery, MemoryLocationResponse,
+                "The \"M,?\" command to query the last memory location used by the data logger");
+typed_command! (CalibrationQuery,    Command::CalibrationQuery,    CalibrationStateResponse,
+                "The \"Cal,?\" command to query whether the device has been calibrated");
+typed_command! (ExportInfoQuery,     Command::ExportInfo,          ExportInfoResponse,
+                "The \"Export,?\" command to query the size of the calibration export");
+typed_command! (Export,              Command::Export,              ExportStringResponse,
+                "The \"Export\" command to get the next piece of calibration data");
+typed_command! (DeviceInfoQuery,     Command::DeviceInfo,          DeviceInfoResponse,
+                "The \"I\" command to query the device information");
+typed_command! (StatusQuery,         Command::Status,              DeviceStatusResponse,
+                "The \"Status\" command to query the restart reason and supply voltage");
+typed_command! (LedQuery,            Command::LedQuery,            LedStateResponse,
+                "The \"L,?\" command to query the LED state");
+typed_command! (ProtocolLockQuery,   Command::ProtocolLockQuery,   ProtocolLockResponse,
+                "The \"Plock,?\" command to query the protocol lock");
+typed_command! (BaudQuery,           Command::BaudQuery,           BaudRateResponse,
+                "The \"Baud,?\" command to query the UART baud rate");
+typed_command! (NameQuery,           Command::NameQuery,           NameResponse,
+                "The \"Name,?\" command to query the device's name");
+typed_command! (Find,                Command::Find,                FindResponse,
+                "The \"Find\" command to blink the LED to locate the device");
+typed_command! (Sleep,               Command::Sleep,               SleepResponse,
+                "The \"Sleep\" command to enter low-power mode");
+
+/// The "R" command to take a temperature reading.  This needs to know the
+/// temperature scale which the device is configured to use.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ReadTemperature (pub TemperatureScale);
+
+impl TypedCommand for ReadTemperature {
+    type Response = TemperatureResponse;
+
+    fn command (&self) -> Command {
+        Command::Read
+    }
+
+    fn parse_response (&self, response: &[u8]) -> Result<TemperatureResponse> {
+        TemperatureResponse::parse (response, self.0)
+    }
+}
+
+/// The "M" command to recall the next stored reading.  This needs to know
+/// the temperature scale which the device is configured to use.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MemoryRecall (pub TemperatureScale);
+
+impl TypedCommand for MemoryRecall {
+    type Response = MemoryRecallResponse;
+
+    fn command (&self) -> Command {
+        Command::MemoryRecall
+    }
+
+    fn parse_response (&self, response: &[u8]) -> Result<MemoryRecallResponse> {
+        MemoryRecallResponse::parse (response, self.0)
+    }
+}
+
+/// The "Import,n" command to load a piece of calibration data
+#[derive(Debug, Clone, PartialEq)]
+pub struct Import (pub CalibrationData);
+
+impl TypedCommand for Import {
+    type Response = ImportResponse;
+
+    fn command (&self) -> Command {
+        Command::Import (self.0.clone ())
+    }
+
+    fn parse_response (&self, response: &[u8]) -> Result<ImportResponse> {
+        ImportResponse::parse (response)
+    }
+}
+
+response_parse! (TemperatureScaleResponse, DataLoggerStorageIntervalResponse,
+                 MemoryLocationResponse);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use framing::{RawI2cResponse, StatusToken};
+
+    #[test]
+    fn parses_temperature_scale_response () {
+        let response = "?S,c\0".as_bytes ();
+        assert_eq! (TemperatureScaleResponse::parse (&response).unwrap (),
+                    TemperatureScaleResponse (TemperatureScale::Celsius));
+
+        let response = "?S,k\0".as_bytes ();
+        assert_eq! (TemperatureScaleResponse::parse (&response).unwrap (),
+                    TemperatureScaleResponse (TemperatureScale::Kelvin));
+
+        let response = "?S,f\0".as_bytes ();
+        assert_eq! (TemperatureScaleResponse::parse (&response).unwrap (),
+                    TemperatureScaleResponse (TemperatureScale::Fahrenheit));
+    }
+
+    #[test]
+    fn parses_mixed_case_temperature_scale_response_leniently () {
+        let options = ParseOptions::lenient ();
+
+        assert_eq! (options.parse::<TemperatureScaleResponse> ("?s,C".as_bytes ()).unwrap (),
+                    TemperatureScaleResponse (TemperatureScale::Celsius));
+        assert_eq! (options.parse::<MemoryLocationResponse> ("?m,12".as_bytes ()).unwrap (),
+                    MemoryLocationResponse (12));
+
+        let response = "?S,C\0".as_bytes ();
+        assert! (TemperatureScaleResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parsing_invalid_temperature_scale_response_yields_error () {
+        let response = "".as_bytes ();
+        assert! (TemperatureScaleResponse::parse (&response).is_err ());
+
+        let response = "\0".as_bytes ();
+        assert! (TemperatureScaleResponse::parse (&response).is_err ());
+
+        let response = "\x01".as_bytes ();
+        assert! (TemperatureScaleResponse::parse (&response).is_err ());
+
+        let response = "?S,\0".as_bytes ();
+        assert! (TemperatureScaleResponse::parse (&response).is_err ());
+    }
+
+    #[test]
+    fn parses_carriage_return_terminated_responses () {
+        let response = "?S,k\r".as_bytes ();
+        assert_eq! (TemperatureScaleResponse::parse (response).unwrap (),
+                    TemperatureScaleResponse (TemperatureScale::Kelvin));
+
+        let response = "25.104\r".as_bytes ();
+        assert_eq! (TemperatureResponse::parse (response, TemperatureScale::Celsius).unwrap (),
+                    TemperatureResponse (Temperature::Celsius (25.104)));
+
+        let response = "*OK\r".as_bytes ();
+        assert_eq! (StatusToken::parse (response).unwrap (), StatusToken::Ok);
+    }
+
+    #[test]
+    fn parsing_improperly_terminated_responses_yields_error () {
+        let response = "?S,k".as_bytes ();
+        assert! (TemperatureScaleResponse::parse (response).is_err ());
+
+        let response = "?S,k\r\0".as_bytes ();
+        assert! (TemperatureScaleResponse::parse (response).is_err ());
+
+        let response = "?S,k\0\0".as_bytes ();
+        assert! (TemperatureScaleResponse::parse (response).is_err ());
+
+        let response = b"?S,\xff\r";
+        assert! (TemperatureScaleResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_data_logger_storage_interval_response () {
+        let response = "?D,0\0".as_bytes ();
+        assert_eq! (DataLoggerStorageIntervalResponse::parse (response).unwrap (),
+                    DataLoggerStorageIntervalResponse (DataLoggerStorageInterval::Off));
+
+        let response = "?D,10\0".as_bytes ();
+        assert_eq! (DataLoggerStorageIntervalResponse::parse (response).unwrap (),
+                    DataLoggerStorageIntervalResponse (DataLoggerStorageInterval::IntervalSeconds (10)));
+
+        let response = "?D,42\0".as_bytes ();
+        assert_eq! (DataLoggerStorageIntervalResponse::parse (response).unwrap (),
+                    DataLoggerStorageIntervalResponse (DataLoggerStorageInterval::IntervalSeconds (42)));
+
+        let response = "?D,320000\0".as_bytes ();
+        assert_eq! (DataLoggerStorageIntervalResponse::parse (response).unwrap (),
+                    DataLoggerStorageIntervalResponse (DataLoggerStorageInterval::IntervalSeconds (320000)));
+    }
+
+    #[test]
+    fn parsing_invalid_data_logger_storage_interval_response_yields_error () {
+        let response = "?D,\0".as_bytes ();
+        assert! (DataLoggerStorageIntervalResponse::parse (response).is_err ());
+
+        let response = "?D,-1\0".as_bytes ();
+        assert! (DataLoggerStorageIntervalResponse::parse (response).is_err ());
+
+        let response = "?D,foo\0".as_bytes ();
+        assert! (DataLoggerStorageIntervalResponse::parse (response).is_err ());
+
+        let response = "?D,1\0".as_bytes ();
+        assert! (DataLoggerStorageIntervalResponse::parse (response).is_err ());
+
+        let response = "?D,9\0".as_bytes ();
+        assert! (DataLoggerStorageIntervalResponse::parse (response).is_err ());
+
+        let response = "?D,320001\0".as_bytes ();
+        assert! (DataLoggerStorageIntervalResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_temperature_response () {
+        let response = "0\0".as_bytes ();
+        assert_eq! (TemperatureResponse::parse (response, TemperatureScale::Celsius).unwrap (),
+                    TemperatureResponse (Temperature::Celsius (0.0)));
+
+        let response = "1234.5\0".as_bytes ();
+        assert_eq! (TemperatureResponse::parse (response, TemperatureScale::Kelvin).unwrap (),
+                    TemperatureResponse (Temperature::Kelvin (1234.5)));
+
+        let response = "-10.5\0".as_bytes ();
+        assert_eq! (TemperatureResponse::parse (response, TemperatureScale::Fahrenheit).unwrap (),
+                    TemperatureResponse (Temperature::Fahrenheit (-10.5)));
+    }
+
+    #[test]
+    fn parsing_invalid_temperature_response_yields_error () {
+        let response = "\0".as_bytes ();
+        assert! (TemperatureResponse::parse (response, TemperatureScale::Celsius).is_err ());
+
+        let response = "-x\0".as_bytes ();
+        assert! (TemperatureResponse::parse (response, TemperatureScale::Celsius).is_err ());
+    }
+
+    #[test]
+    fn parsing_temperature_response_with_probe_fault_yields_error () {
+        let response = "-1023.000\0".as_bytes ();
+        match *TemperatureResponse::parse (response, TemperatureScale::Celsius).unwrap_err ().kind () {
+            ErrorKind::ProbeFault => (),
+            _ => panic! ("expected ProbeFault")
+        }
+
+        let response = "-1023.562\0".as_bytes ();
+        match *TemperatureResponse::parse (response, TemperatureScale::Fahrenheit).unwrap_err ().kind () {
+            ErrorKind::ProbeFault => (),
+            _ => panic! ("expected ProbeFault")
+        }
+
+        let response = "-126.0\0".as_bytes ();
+        assert! (TemperatureResponse::parse (response, TemperatureScale::Celsius).is_ok ());
+    }
+
+    #[test]
+    fn parses_memory_recall_response () {
+        let response = "1,25.104\0".as_bytes ();
+        assert_eq! (MemoryRecallResponse::parse (response, TemperatureScale::Celsius).unwrap (),
+                    MemoryRecallResponse {
+                        location: 1,
+                        temperature: Temperature::Celsius (25.104)
+                    });
+
+        let response = "713,-10.5\0".as_bytes ();
+        assert_eq! (MemoryRecallResponse::parse (response, TemperatureScale::Fahrenheit).unwrap (),
+                    MemoryRecallResponse {
+                        location: 713,
+                        temperature: Temperature::Fahrenheit (-10.5)
+                    });
+    }
+
+    #[test]
+    fn parsing_invalid_memory_recall_response_yields_error () {
+        let response = "\0".as_bytes ();
+        assert! (MemoryRecallResponse::parse (response, TemperatureScale::Celsius).is_err ());
+
+        let response = "1\0".as_bytes ();
+        assert! (MemoryRecallResponse::parse (response, TemperatureScale::Celsius).is_err ());
+
+        let response = "-1,25.0\0".as_bytes ();
+        assert! (MemoryRecallResponse::parse (response, TemperatureScale::Celsius).is_err ());
+
+        let response = "1,x\0".as_bytes ();
+        assert! (MemoryRecallResponse::parse (response, TemperatureScale::Celsius).is_err ());
+
+        let response = "1,25.0,\0".as_bytes ();
+        assert! (MemoryRecallResponse::parse (response, TemperatureScale::Celsius).is_err ());
+    }
+
+    #[test]
+    fn parses_memory_location_response () {
+        let response = "?M,0\0".as_bytes ();
+        assert_eq! (MemoryLocationResponse::parse (response).unwrap (),
+                    MemoryLocationResponse (0));
+
+        let response = "?M,713\0".as_bytes ();
+        assert_eq! (MemoryLocationResponse::parse (response).unwrap (),
+                    MemoryLocationResponse (713));
+    }
+
+    #[test]
+    fn parsing_invalid_memory_location_response_yields_error () {
+        let response = "?M,\0".as_bytes ();
+        assert! (MemoryLocationResponse::parse (response).is_err ());
+
+        let response = "?M,-1\0".as_bytes ();
+        assert! (MemoryLocationResponse::parse (response).is_err ());
+
+        let response = "?M,foo\0".as_bytes ();
+        assert! (MemoryLocationResponse::parse (response).is_err ());
+    }
+
+    #[test]
+    fn parses_raw_i2c_response () {
+        let response = "\x01?S,c\0".as_bytes ();
+        let raw = RawI2cResponse::parse (response).unwrap ();
+        assert_eq! (raw, RawI2cResponse::Success ("?S,c\0".as_bytes ()));
+        assert_eq! (TemperatureScaleResponse::parse (raw.payload ().unwrap ()).unwrap (),
+                    TemperatureScaleResponse (TemperatureScale::Celsius));
+
+        assert_eq! (RawI2cResponse::parse (&[2, 0]).unwrap (), RawI2cResponse::SyntaxError);
+        assert_eq! (RawI2cResponse::parse (&[254]).unwrap (), RawI2cResponse::Pending);
+        assert_eq! (RawI2cResponse::parse (&[255]).unwrap (), RawI2cResponse::NoData);
+    }
+
+    #[test]
+    fn displays_responses () {
+        assert_eq! (TemperatureScaleResponse (TemperatureScale::Celsius).to_string (),
+                    "temperature scale is Celsius");
+        assert_eq! (DataLoggerStorageIntervalResponse (DataLoggerStorageInterval::IntervalSeconds (60)).to_string (),
+                    "data logger stores a reading every 60 s");
+        assert_eq! (DataLoggerStorageIntervalResponse (DataLoggerStorageInterval::Off).to_string (),
+                    "data logger is off");
+        assert_eq! (TemperatureResponse (Temperature::Celsius (25.5)).to_string (),
+                    "temperature is 25.5 °C");
+        assert_eq! (DeviceInfoResponse::parse ("?I,RTD,2.01\0".as_bytes ()).unwrap ().to_string (),
+                    "EZO RTD with firmware 2.01");
+        assert_eq! (DeviceStatusResponse::parse ("?Status,B,5.038\0".as_bytes ()).unwrap ().to_string (),
+                    "restarted due to brown-out, supply voltage is 5.038 V");
+        assert_eq! (MemoryRecallResponse::parse ("3,-10.5\0".as_bytes (), TemperatureScale::Kelvin).unwrap ().to_string (),
+                    "stored reading 3 is -10.5 K");
+        assert_eq! (NameResponse ("tank1".to_string ()).to_string (), "device name is \"tank1\"");
+        assert_eq! (NameResponse ("".to_string ()).to_string (), "device has no name");
+        assert_eq! (StatusToken::OverVoltage.to_string (), "over-voltage");
+        assert_eq! (BaudRateResponse (BaudRate::Bps115200).to_string (), "baud rate is 115200 bps");
+    }
+
+    #[test]
+    fn encodes_commands () {
+        let commands = [(Command::Read, "R"),
+                        (Command::SetScale (TemperatureScale::Celsius), "S,c"),
+                        (Command::SetScale (TemperatureScale::Kelvin), "S,k"),
+                        (Command::SetScale (TemperatureScale::Fahrenheit), "S,f"),
+                        (Command::ScaleQuery, "S,?"),
+                        (Command::SetDataLoggerInterval (DataLoggerStorageInterval::Off), "D,0"),
+                        (Command::SetDataLoggerInterval (DataLoggerStorageInterval::IntervalSeconds (60)), "D,60"),
+                        (Command::DataLoggerQuery, "D,?"),
+                        (Command::MemoryRecall, "M"),
+                        (Command::MemoryLocationQuery, "M,?"),
+                        (Command::MemoryClear, "M,clear"),
+                        (Command::calibrate (100.0).unwrap (), "Cal,100.0"),
+                        (Command::calibrate (-10.5).unwrap (), "Cal,-10.5"),
+                        (Command::CalibrationClear, "Cal,clear"),
+                        (Command::CalibrationQuery, "Cal,?"),
+                        (Command::ExportInfo, "Export,?"),
+                        (Command::Export, "Export"),
+                        (Command::import ("596F75206172").unwrap (), "Import,596F75206172"),
+                        (Command::DeviceInfo, "I"),
+                        (Command::Status, "Status"),
+                        (Command::SetLed (LedState::Off), "L,0"),
+                        (Command::SetLed (LedState::On), "L,1"),
+                        (Command::LedQuery, "L,?"),
+                        (Command::SetProtocolLock (ProtocolLockState::Unlocked), "Plock,0"),
+                        (Command::SetProtocolLock (ProtocolLockState::Locked), "Plock,1"),
+                        (Command::ProtocolLockQuery, "Plock,?"),
+                        (Command::Sleep, "Sleep"),
+                        (Command::Find, "Find"),
+                        (Command::Factory, "Factory"),
+                        (Command::Baud (BaudRate::Bps9600), "Baud,9600"),
+                        (Command::BaudQuery, "Baud,?"),
+                        (Command::I2cAddress (I2cAddress::new (102).unwrap ()), "I2C,102"),
+                        (Command::set_name ("tank1").unwrap (), "Name,tank1"),
+                        (Command::set_name ("").unwrap (), "Name,"),
+                        (Command::NameQuery, "Name,?")];
+
+        for &(ref command, encoded) in commands.iter () {
+            assert_eq! (command.encode (), encoded.as_bytes ());
+        }
+    }
+
+    #[test]
+    fn validates_command_parameters () {
+        assert_eq! (Command::set_data_logger_interval (0).unwrap (),
+                    Command::SetDataLoggerInterval (DataLoggerStorageInterval::Off));
+        assert_eq! (Command::set_data_logger_interval (10).unwrap (),
+                    Command::SetDataLoggerInterval (DataLoggerStorageInterval::IntervalSeconds (10)));
+        assert_eq! (Command::set_data_logger_interval (320000).unwrap (),
+                    Command::SetDataLoggerInterval (DataLoggerStorageInterval::IntervalSeconds (320000)));
+
+        assert_eq! (Command::calibrate (100.0).unwrap (),
+                    Command::Calibrate (CalibrationTemperature::new (100.0).unwrap ()));
+        assert_eq! (CalibrationTemperature::new (-126.0).unwrap ().value (), -126.0);
+
+        assert_eq! (Command::set_name ("tank1").unwrap (), Command::SetName (DeviceName::new ("tank1").unwrap ()));
+        assert_eq! (DeviceName::new ("").unwrap ().as_str (), "");
+
+        assert_eq! (Command::import ("596F75206172").unwrap (),
+                    Command::Import (CalibrationData::new ("596F75206172").unwrap ()));
+    }
+
+    #[test]
+    fn invalid_command_parameters_yield_error () {
+        assert! (Command::set_data_logger_interval (9).is_err ());
+        assert! (Command::set_data_logger_interval (320001).is_err ());
+
+        assert! (Command::calibrate (-126.1).is_err ());
+        assert! (Command::calibrate (1254.1).is_err ());
+        assert! (Command::calibrate (f64::NAN).is_err ());
+
+        assert! (Command::set_name ("seventeen_chars__").is_err ());
+        assert! (Command::set_name ("tank 1").is_err ());
+        assert! (Command::set_name ("tank,1").is_err ());
+
+        assert! (Command::import ("").is_err ());
+        assert! (Command::import ("596F 7520").is_err ());
+        assert! (Command::import ("*DONE").is_err ());
+        assert! (CalibrationData::new ("caf\u{e9}").is_err ());
+
+        match *Command::set_data_logger_interval (1).unwrap_err ().kind () {
+            ErrorKind::InvalidCommandParameter (_) => (),
+            _ => panic! ("expected InvalidCommandParameter")
+        }
+    }
+
+    #[test]
+    fn commands_have_processing_delay () {
+        assert_eq! (Command::Read.delay (), Duration::from_millis (600));
+        assert_eq! (Command::calibrate (100.0).unwrap ().delay (), Duration::from_millis (600));
+        assert_eq! (Command::ScaleQuery.delay (), Duration::from_millis (300));
+        assert_eq! (Command::CalibrationClear.delay (), Duration::from_millis (300));
+        assert_eq! (Command::Export.delay (), Duration::from_millis (300));
+    }
+
+    #[test]
+    fn typed_commands_parse_their_responses () {
+        assert_eq! (ScaleQuery.command (), Command::ScaleQuery);
+        assert_eq! (ScaleQuery.parse_response ("?S,k\0".as_bytes ()).unwrap (),
+                    TemperatureScaleResponse (TemperatureScale::Kelvin));
+
+        let read = ReadTemperature (TemperatureScale::Fahrenheit);
+        assert_eq! (read.command (), Command::Read);
+        assert_eq! (read.parse_response ("77.5\0".as_bytes ()).unwrap (),
+                    TemperatureResponse (Temperature::Fahrenheit (77.5)));
+
+        let import = Import (CalibrationData::new ("596F75206172").unwrap ());
+        assert_eq! (import.command (), Command::import ("596F75206172").unwrap ());
+        assert_eq! (import.parse_response ("*OK\r".as_bytes ()).unwrap (), ImportResponse::Accepted);
+
+        assert! (StatusQuery.parse_response ("?S,k\0".as_bytes ()).is_err ());
+    }
+
+    #[test]
+    fn parses_commands () {
+        let commands = ["R", "S,c", "S,k", "S,f", "S,?", "D,0", "D,60", "D,?", "M", "M,?", "M,clear",
+                        "Cal,100.0", "Cal,-10.5", "Cal,clear", "Cal,?", "Export,?", "Export",
+                        "Import,596F75206172", "I", "Status", "L,0", "L,1", "L,?", "Plock,0",
+                        "Plock,1", "Plock,?", "Sleep", "Find", "Factory", "Baud,9600", "Baud,?",
+                        "I2C,102", "Name,tank1", "Name,", "Name,?"];
+
+        for command in commands.iter () {
+            assert_eq! (Command::parse (command.as_bytes ()).unwrap ().encode (), command.as_bytes ());
+        }
+
+        assert_eq! (Command::parse ("r".as_bytes ()).unwrap (), Command::Read);
+        assert_eq! (Command::parse ("R\r".as_bytes ()).unwrap (), Command::Read);
+        assert_eq! (Command::parse ("cal,CLEAR\0".as_bytes ()).unwrap (), Command::CalibrationClear);
+        assert_eq! (Command::parse ("s,C".as_bytes ()).unwrap (), Command::SetScale (TemperatureScale::Celsius));
+        assert_eq! (Command::parse ("NAME,Tank1".as_bytes ()).unwrap (), Command::set_name ("Tank1").unwrap ());
+    }
+
+    #[test]
+    fn parsing_invalid_command_yields_error () {
+        let commands = ["", "X", "R,1", "S", "S,x", "D", "D,5", "D,x", "Cal", "Cal,2000", "Cal,x",
+                        "Import", "L,2", "Plock", "Baud,4800", "I2C,300", "I2C,0", "I2C,120",
+                        "Name", "Name,my tank", "Import,59 6F", "Import,*DONE",
+                        "R\r\r"];
+
+        for command in commands.iter () {
+            assert! (Command::parse (command.as_bytes ()).is_err ());
+        }
+    }
+
+    #[test]
+    fn formats_calibration_temperature () {
+        assert_eq! (CalibrationCommand::at (100.0).unwrap ().encode (), b"Cal,100.0");
+        assert_eq! (CalibrationCommand::at (25.5).unwrap ().encode (), b"Cal,25.5");
+        assert_eq! (CalibrationCommand::at (25.1234).unwrap ().encode (), b"Cal,25.123");
+        assert_eq! (CalibrationCommand::at (0.0000001).unwrap ().encode (), b"Cal,0.0");
+        assert_eq! (CalibrationCommand::at (-0.0001).unwrap ().encode (), b"Cal,0.0");
+        assert_eq! (CalibrationCommand::at (-10.25).unwrap ().encode (), b"Cal,-10.25");
+        assert_eq! (CalibrationCommand::at (1254.0).unwrap ().encode (), b"Cal,1254.0");
+        assert_eq! (CalibrationCommand::clear ().encode (), b"Cal,clear");
+
+        assert_eq! (Command::from (CalibrationCommand::clear ()), Command::CalibrationClear);
+        assert! (CalibrationCommand::at (2000.0).is_err ());
+    }
+
+    #[test]
+    fn validates_i2c_address () {
+        assert_eq! (I2cAddress::new (0x08).unwrap ().value (), 0x08);
+        assert_eq! (I2cAddress::new (0x66).unwrap ().value (), 0x66);
+        assert_eq! (I2cAddress::new (0x77).unwrap ().value (), 0x77);
+        assert_eq! (I2cAddressCommand::new (0x66).unwrap ().encode (), b"I2C,102");
+
+        assert! (I2cAddress::new (0).is_err ());
+        assert! (I2cAddress::new (0x07).is_err ());
+        assert! (I2cAddress::new (0x78).is_err ());
+        assert! (I2cAddress::new (0x7f).is_err ());
+        assert! (I2cAddressCommand::new (0x80).is_err ());
+    }
+
+    #[test]
+    fn displays_commands () {
+        assert_eq! (Command::set_data_logger_interval (60).unwrap ().to_string (),
+                    "set data logger interval to 60 s");
+        assert_eq! (Command::set_data_logger_interval (0).unwrap ().to_string (),
+                    "turn data logger off");
+        assert_eq! (Command::SetScale (TemperatureScale::Fahrenheit).to_string (),
+                    "set temperature scale to Fahrenheit");
+        assert_eq! (CalibrationCommand::at (100.0).unwrap ().to_string (), "calibrate at 100.0 °C");
+        assert_eq! (I2cAddressCommand::new (0x66).unwrap ().to_string (), "change I2C address to 0x66");
+        assert_eq! (Command::set_name ("tank1").unwrap ().to_string (), "set device name to \"tank1\"");
+        assert_eq! (Command::Baud (BaudRate::Bps9600).to_string (), "switch to UART mode at 9600 bps");
+    }
+
+    #[test]
+    fn converts_common_commands () {
+        assert_eq! (Command::from (CommonCommand::Find), Command::Find);
+        assert_eq! (CommonCommand::parse ("find".as_bytes ()).unwrap (), CommonCommand::Find);
+        assert_eq! (CommonCommand::parse ("L,?".as_bytes ()).unwrap ().encode (), b"L,?");
+        assert_eq! (CommonCommand::SetLed (LedState::On).to_string (), "turn LED on");
+        assert! (CommonCommand::parse ("R".as_bytes ()).is_err ());
+        assert! (CommonCommand::parse ("Cal,100".as_bytes ()).is_err ());
+    }
+}
@@ -0,0 +1,32 @@
+extern crate saiba_parser;
+
+use saiba_parser::{ErrorKind, RawI2cResponse};
+#[cfg(feature = "rtd")]
+use saiba_parser::{Result, TemperatureResponse, TemperatureScale};
+
+#[cfg(feature = "rtd")]
+fn parse_temperature (response: &[u8]) -> Result<TemperatureResponse> {
+    TemperatureResponse::parse (response, TemperatureScale::Celsius)
+}
+
+#[test]
+#[cfg(feature = "rtd")]
+fn probe_fault_can_be_matched_outside_the_crate () {
+    match *parse_temperature ("-1023.000\0".as_bytes ()).unwrap_err ().kind () {
+        ErrorKind::ProbeFault => (),
+        _ => panic! ("expected ProbeFault")
+    }
+}
+
+#[test]
+fn i2c_response_codes_can_be_matched_outside_the_crate () {
+    match *RawI2cResponse::Pending.payload ().unwrap_err ().kind () {
+        ErrorKind::StillProcessing => (),
+        _ => panic! ("expected StillProcessing")
+    }
+
+    match *RawI2cResponse::NoData.payload ().unwrap_err ().kind () {
+        ErrorKind::NoData => (),
+        _ => panic! ("expected NoData")
+    }
+}
@@ -0,0 +1,17 @@
+extern crate saiba_parser;
+
+use saiba_parser::{ParseOptions, StatusToken};
+
+#[test]
+fn builds_parse_options_outside_the_crate () {
+    let options = ParseOptions::default ().optional_terminator (true);
+
+    assert_eq! (options.parse::<StatusToken> ("*OK".as_bytes ()).unwrap (), StatusToken::Ok);
+    assert! (options.parse::<StatusToken> ("*ok".as_bytes ()).is_err ());
+
+    let options = ParseOptions::lenient ().trim_whitespace (false);
+
+    assert_eq! (options.parse::<StatusToken> ("*ok".as_bytes ()).unwrap (), StatusToken::Ok);
+    assert! (options.parse::<StatusToken> ("*OK\r\n".as_bytes ()).is_err ());
+    assert_eq! (options.ignore_case (false), ParseOptions::default ().optional_terminator (true));
+}